@@ -148,6 +148,7 @@ describe('Dex spec', () => {
       token1: token1.address,
       pair: expectedAddress,
       pairLen: 1,
+      poolType: 0,
     });
     expect((await factory.query.allPairsLength()).value.ok).toBe(1);
   });
@@ -0,0 +1,381 @@
+//! Constant-product math of the DEX pairs, shared by the pair and router contracts so that
+//! any contract depending on this crate quotes exactly as they do.
+//!
+//! Each function states its rounding direction, which always favors the pool: amounts paid
+//! out of a pair are rounded down and amounts paid into it are rounded up.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use primitive_types::{
+    U256,
+    U512,
+};
+
+/// Basis points in one.
+pub const BPS: u16 = 10_000;
+
+/// Rounding direction of a division.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+/// Reason a computation has no result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathError {
+    DivisionByZero,
+    /// The result does not fit in the returned type.
+    Overflow,
+    /// The result is negative.
+    Underflow,
+    /// A fee of more than `BPS`, or of `BPS` where something must be left after the fee.
+    InvalidFee,
+}
+
+/// Returns `a * b`, which always fits in `U256`.
+pub fn full_mul(a: u128, b: u128) -> U256 {
+    U256::from(a) * U256::from(b)
+}
+
+/// Returns `a * b / denominator` rounded as `rounding`, the product is kept in 512 bits.
+pub fn mul_div(a: U256, b: U256, denominator: U256, rounding: Rounding) -> Result<U256, MathError> {
+    if denominator.is_zero() {
+        return Err(MathError::DivisionByZero)
+    }
+    let product = a.full_mul(b);
+    let denominator = U512::from(denominator);
+    let mut result = product / denominator;
+    if rounding == Rounding::Up && !(product % denominator).is_zero() {
+        result += U512::one();
+    }
+    U256::try_from(result).map_err(|_| MathError::Overflow)
+}
+
+/// Same as [`mul_div`] for `u128` operands and result.
+pub fn mul_div_u128(
+    a: u128,
+    b: u128,
+    denominator: U256,
+    rounding: Rounding,
+) -> Result<u128, MathError> {
+    to_u128(mul_div(a.into(), b.into(), denominator, rounding)?)
+}
+
+pub fn to_u128(value: U256) -> Result<u128, MathError> {
+    value.try_into().map_err(|_| MathError::Overflow)
+}
+
+/// Returns the square root of `value` rounded as `rounding`.
+pub fn sqrt(value: U256, rounding: Rounding) -> U256 {
+    let root = value.integer_sqrt();
+    if rounding == Rounding::Up && root * root != value {
+        root + 1
+    } else {
+        root
+    }
+}
+
+/// Returns `fee_bps` of `amount` rounded as `rounding`.
+pub fn fee_amount(amount: u128, fee_bps: u16, rounding: Rounding) -> Result<u128, MathError> {
+    if fee_bps > BPS {
+        return Err(MathError::InvalidFee)
+    }
+    mul_div_u128(amount, fee_bps.into(), BPS.into(), rounding)
+}
+
+/// Returns `amount` minus `fee_bps` of it, rounded down.
+pub fn apply_fee(amount: u128, fee_bps: u16) -> Result<u128, MathError> {
+    Ok(amount - fee_amount(amount, fee_bps, Rounding::Up)?)
+}
+
+/// Returns the smallest amount which `fee_bps` reduces to at least `net_amount`, the inverse
+/// of [`apply_fee`] rounded up.
+pub fn gross_amount(net_amount: u128, fee_bps: u16) -> Result<u128, MathError> {
+    if fee_bps >= BPS {
+        return Err(MathError::InvalidFee)
+    }
+    mul_div_u128(net_amount, BPS.into(), (BPS - fee_bps).into(), Rounding::Up)
+}
+
+/// Returns the amount of token B worth `amount_a` at the ratio of the reserves, rounded as
+/// `rounding`.
+pub fn quote(
+    amount_a: u128,
+    reserve_a: u128,
+    reserve_b: u128,
+    rounding: Rounding,
+) -> Result<u128, MathError> {
+    mul_div_u128(amount_a, reserve_b, reserve_a.into(), rounding)
+}
+
+/// Returns the output of a swap of `amount_in` with `fee_bps` charged on the input, rounded
+/// down. It is the largest output for which the pair accepts the swap.
+pub fn amount_out(
+    amount_in: u128,
+    reserve_in: u128,
+    reserve_out: u128,
+    fee_bps: u16,
+) -> Result<u128, MathError> {
+    if fee_bps >= BPS {
+        return Err(MathError::InvalidFee)
+    }
+    let amount_in_with_fee = full_mul(amount_in, (BPS - fee_bps).into());
+    let denominator = full_mul(reserve_in, BPS.into()) + amount_in_with_fee;
+    to_u128(mul_div(
+        amount_in_with_fee,
+        reserve_out.into(),
+        denominator,
+        Rounding::Down,
+    )?)
+}
+
+/// Returns the input of a swap for `amount_out` with `fee_bps` charged on the input, rounded
+/// up. As in Uniswap V2 it is the exact input rounded down plus one, so that quotes match.
+pub fn amount_in(
+    amount_out: u128,
+    reserve_in: u128,
+    reserve_out: u128,
+    fee_bps: u16,
+) -> Result<u128, MathError> {
+    if fee_bps >= BPS {
+        return Err(MathError::InvalidFee)
+    }
+    let remaining_out = reserve_out
+        .checked_sub(amount_out)
+        .ok_or(MathError::Underflow)?;
+    let amount_in = mul_div(
+        full_mul(reserve_in, amount_out),
+        BPS.into(),
+        full_mul(remaining_out, (BPS - fee_bps).into()),
+        Rounding::Down,
+    )?;
+    to_u128(
+        amount_in
+            .checked_add(U256::one())
+            .ok_or(MathError::Overflow)?,
+    )
+}
+
+/// Returns `balance` scaled by `BPS` minus the fee on `amount_in`, the balance the pair
+/// checks the constant product against after a swap.
+pub fn adjusted_balance(balance: u128, amount_in: u128, fee_bps: u16) -> Result<U256, MathError> {
+    full_mul(balance, BPS.into())
+        .checked_sub(full_mul(amount_in, fee_bps.into()))
+        .ok_or(MathError::Underflow)
+}
+
+/// Returns whether the product of balances adjusted by [`adjusted_balance`] is at least the
+/// product of `reserves`.
+pub fn k_preserved(adjusted_balances: (U256, U256), reserves: (u128, u128)) -> bool {
+    adjusted_balances.0.full_mul(adjusted_balances.1)
+        >= full_mul(reserves.0, reserves.1).full_mul(full_mul(BPS.into(), BPS.into()))
+}
+
+/// Liquidity locked forever by the first deposit in a pair.
+pub const MINIMUM_LIQUIDITY: u128 = 1_000;
+
+/// Returns the liquidity minted to the first depositor, rounded down. `MINIMUM_LIQUIDITY` is
+/// locked on top of it.
+pub fn initial_liquidity(amount_0: u128, amount_1: u128) -> Result<u128, MathError> {
+    sqrt(full_mul(amount_0, amount_1), Rounding::Down)
+        .low_u128()
+        .checked_sub(MINIMUM_LIQUIDITY)
+        .ok_or(MathError::Underflow)
+}
+
+/// Returns the liquidity minted for `amounts` deposited on top of `reserves`, rounded down.
+/// The excess of either token over the ratio of the reserves is donated to the pair.
+pub fn liquidity_minted(
+    amounts: (u128, u128),
+    reserves: (u128, u128),
+    total_supply: u128,
+) -> Result<u128, MathError> {
+    let liquidity_0 = mul_div_u128(amounts.0, total_supply, reserves.0.into(), Rounding::Down)?;
+    let liquidity_1 = mul_div_u128(amounts.1, total_supply, reserves.1.into(), Rounding::Down)?;
+    Ok(liquidity_0.min(liquidity_1))
+}
+
+/// Returns the share of `balances` redeemed by burning `liquidity`, rounded down.
+pub fn burn_amounts(
+    liquidity: u128,
+    balances: (u128, u128),
+    total_supply: u128,
+) -> Result<(u128, u128), MathError> {
+    Ok((
+        mul_div_u128(liquidity, balances.0, total_supply.into(), Rounding::Down)?,
+        mul_div_u128(liquidity, balances.1, total_supply.into(), Rounding::Down)?,
+    ))
+}
+
+/// Returns the liquidity minted as protocol fee, 1/6 of the growth of sqrt(k) since
+/// `root_k_last`, rounded down.
+pub fn protocol_fee_liquidity(
+    total_supply: u128,
+    root_k: u128,
+    root_k_last: u128,
+) -> Result<u128, MathError> {
+    let growth = root_k
+        .checked_sub(root_k_last)
+        .ok_or(MathError::Underflow)?;
+    let denominator = full_mul(root_k, 5) + U256::from(root_k_last);
+    mul_div_u128(total_supply, growth, denominator, Rounding::Down)
+}
+
+/// Returns `numerator / denominator` as an unsigned 128.128 fixed-point number rounded down,
+/// `None` if `denominator` is 0.
+pub fn fraction_x128(numerator: u128, denominator: u128) -> Option<U256> {
+    if denominator == 0 {
+        return None
+    }
+    Some((U256::from(numerator) << 128) / U256::from(denominator))
+}
+
+/// Adds the 128.128 fixed-point prices of the reserves times `time_elapsed` to the price
+/// accumulators, wrapping around `U256::MAX`. A price is 0 while the reserve it divides by
+/// is empty.
+pub fn update_cumulative(
+    price_0_cumulative: U256,
+    price_1_cumulative: U256,
+    time_elapsed: U256,
+    reserve_0: u128,
+    reserve_1: u128,
+) -> (U256, U256) {
+    let accumulate = |cumulative: U256, price: Option<U256>| {
+        price
+            .unwrap_or_default()
+            .overflowing_mul(time_elapsed)
+            .0
+            .overflowing_add(cumulative)
+            .0
+    };
+    (
+        accumulate(price_0_cumulative, fraction_x128(reserve_1, reserve_0)),
+        accumulate(price_1_cumulative, fraction_x128(reserve_0, reserve_1)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const FEES_BPS: [u16; 4] = [0, 5, 30, 1_000];
+
+    fn swap_accepted(
+        reserves: (u128, u128),
+        amount_in: u128,
+        amount_out: u128,
+        fee_bps: u16,
+    ) -> bool {
+        let balance_in = match reserves.0.checked_add(amount_in) {
+            Some(balance_in) => balance_in,
+            None => return false,
+        };
+        let adjusted_in = adjusted_balance(balance_in, amount_in, fee_bps).unwrap();
+        let adjusted_out = adjusted_balance(reserves.1 - amount_out, 0, fee_bps).unwrap();
+        k_preserved((adjusted_in, adjusted_out), reserves)
+    }
+
+    #[test]
+    fn matches_uniswap_quotes() {
+        let one = 1_000_000_000_000_000_000;
+        assert_eq!(
+            amount_out(one, 100 * one, 100 * one, 30),
+            Ok(987_158_034_397_061_298)
+        );
+        assert_eq!(amount_in(1, 100, 100, 30), Ok(2));
+        assert_eq!(quote(3, 2, 5, Rounding::Down), Ok(7));
+        assert_eq!(quote(3, 2, 5, Rounding::Up), Ok(8));
+    }
+
+    #[test]
+    fn mul_div_keeps_full_product() {
+        assert_eq!(
+            mul_div(U256::MAX, U256::MAX, U256::MAX, Rounding::Down),
+            Ok(U256::MAX)
+        );
+        assert_eq!(
+            mul_div(U256::MAX, 2.into(), 1.into(), Rounding::Down),
+            Err(MathError::Overflow)
+        );
+        assert_eq!(
+            mul_div(1.into(), 1.into(), 0.into(), Rounding::Up),
+            Err(MathError::DivisionByZero)
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn mul_div_rounds_as_requested(a: u128, b: u128, denominator in 1..u128::MAX) {
+            let product = full_mul(a, b);
+            let denominator = U256::from(denominator);
+            let down = mul_div(a.into(), b.into(), denominator, Rounding::Down).unwrap();
+            let up = mul_div(a.into(), b.into(), denominator, Rounding::Up).unwrap();
+            prop_assert!(down.full_mul(denominator) <= U512::from(product));
+            prop_assert!((down + 1).full_mul(denominator) > U512::from(product));
+            prop_assert_eq!(up == down, (product % denominator).is_zero());
+            prop_assert!(up == down || up == down + 1);
+        }
+
+        #[test]
+        fn sqrt_rounds_as_requested(a: u128, b: u128) {
+            let value = full_mul(a, b);
+            let down = sqrt(value, Rounding::Down);
+            let up = sqrt(value, Rounding::Up);
+            prop_assert!(down * down <= value);
+            prop_assert!(U512::from(up) * U512::from(up) >= U512::from(value));
+            prop_assert!(up - down <= U256::one());
+        }
+
+        #[test]
+        fn fee_rounding_favors_pool(amount: u128, fee_index in 0..FEES_BPS.len()) {
+            let fee_bps = FEES_BPS[fee_index];
+            let net = apply_fee(amount, fee_bps).unwrap();
+            prop_assert!(full_mul(net, BPS.into()) <= full_mul(amount, (BPS - fee_bps).into()));
+            if let Ok(gross) = gross_amount(net, fee_bps) {
+                prop_assert!(gross <= amount);
+                prop_assert!(apply_fee(gross, fee_bps).unwrap() >= net);
+            }
+        }
+
+        #[test]
+        fn amount_out_is_largest_accepted_output(
+            reserve_in in 1..u128::MAX / 2,
+            reserve_out in 1..u128::MAX,
+            amount_in in 1..u128::MAX / 2,
+            fee_index in 0..FEES_BPS.len(),
+        ) {
+            let fee_bps = FEES_BPS[fee_index];
+            let out = amount_out(amount_in, reserve_in, reserve_out, fee_bps).unwrap();
+            prop_assert!(out < reserve_out);
+            prop_assert!(swap_accepted((reserve_in, reserve_out), amount_in, out, fee_bps));
+            if out + 1 < reserve_out {
+                prop_assert!(!swap_accepted((reserve_in, reserve_out), amount_in, out + 1, fee_bps));
+            }
+        }
+
+        #[test]
+        fn amount_in_is_accepted(
+            reserve_in in 1..u128::MAX,
+            reserve_out in 2..u128::MAX,
+            amount_out: u128,
+            fee_index in 0..FEES_BPS.len(),
+        ) {
+            let fee_bps = FEES_BPS[fee_index];
+            let amount_out = amount_out % (reserve_out - 1) + 1;
+            if let Ok(input) = amount_in(amount_out, reserve_in, reserve_out, fee_bps) {
+                prop_assume!(reserve_in.checked_add(input).is_some());
+                prop_assert!(swap_accepted((reserve_in, reserve_out), input, amount_out, fee_bps));
+                // Never more than one unit above the exact input.
+                if input >= 2 {
+                    prop_assert!(!swap_accepted(
+                        (reserve_in, reserve_out),
+                        input - 2,
+                        amount_out,
+                        fee_bps
+                    ));
+                }
+            }
+        }
+    }
+}
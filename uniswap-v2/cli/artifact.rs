@@ -0,0 +1,282 @@
+use crate::error::CliError;
+use scale::Encode;
+use serde::{
+    de::Error as _,
+    Deserialize,
+    Deserializer,
+};
+use std::{
+    collections::HashMap,
+    path::Path,
+};
+
+/// Bundle built by `cargo contract build`: the code of a contract with its ink! 4 metadata.
+///
+/// Only the parts needed to encode calls and to name the errors they return are read.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Artifact {
+    source: Source,
+    contract: ContractInfo,
+    spec: Spec,
+    #[serde(deserialize_with = "types_by_id")]
+    types: HashMap<u32, Def>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Source {
+    #[serde(deserialize_with = "bytes_from_hex")]
+    hash: Vec<u8>,
+    #[serde(default, deserialize_with = "bytes_from_hex")]
+    wasm: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ContractInfo {
+    name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Spec {
+    constructors: Vec<MessageSpec>,
+    messages: Vec<MessageSpec>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MessageSpec {
+    label: String,
+    #[serde(deserialize_with = "bytes_from_hex")]
+    selector: Vec<u8>,
+    return_type: Option<TypeRef>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TypeRef {
+    #[serde(rename = "type")]
+    id: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TypeEntry {
+    id: u32,
+    #[serde(rename = "type")]
+    ty: TypeDef,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TypeDef {
+    def: Def,
+}
+
+/// Definition of a type, only structs and enums are read.
+#[derive(Debug, Clone, Deserialize)]
+struct Def {
+    composite: Option<Fields>,
+    variant: Option<Variants>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Fields {
+    #[serde(default)]
+    fields: Vec<TypeRef>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Variants {
+    #[serde(default)]
+    variants: Vec<Variant>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Variant {
+    name: String,
+    index: u8,
+    #[serde(default)]
+    fields: Vec<TypeRef>,
+}
+
+impl Artifact {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, CliError> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .map_err(|error| CliError::Artifact(format!("{}: {error}", path.display())))?;
+        serde_json::from_str(&json)
+            .map_err(|error| CliError::Artifact(format!("{}: {error}", path.display())))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.contract.name
+    }
+
+    pub fn wasm(&self) -> &[u8] {
+        &self.source.wasm
+    }
+
+    pub fn code_hash(&self) -> Result<[u8; 32], CliError> {
+        self.source
+            .hash
+            .as_slice()
+            .try_into()
+            .map_err(|_| CliError::Artifact(format!("{}: code hash is not 32 bytes", self.name())))
+    }
+
+    /// Returns the input of the constructor `label` called with the encoded `args`.
+    pub fn constructor_data<A: Encode>(&self, label: &str, args: A) -> Result<Vec<u8>, CliError> {
+        Ok(input(self.constructor(label)?, args))
+    }
+
+    /// Returns the input of the message `label` called with the encoded `args`. Trait messages
+    /// are labelled with their trait, e.g. `Factory::create_pair`.
+    pub fn message_data<A: Encode>(&self, label: &str, args: A) -> Result<Vec<u8>, CliError> {
+        Ok(input(self.message(label)?, args))
+    }
+
+    /// Names the error encoded in `error`, returned by the message `label`.
+    ///
+    /// The error type is the one of the `Result` returned by the message. Variant names are
+    /// followed by the names of their fields which are enums too, e.g.
+    /// `PairError(Arithmetic(Mul, Mint))`.
+    pub fn describe_error(&self, label: &str, error: &[u8]) -> String {
+        self.message(label)
+            .ok()
+            .and_then(|message| message.return_type.as_ref())
+            .and_then(|outer| self.ok_type(outer.id))
+            .and_then(|result| self.err_type(result))
+            .and_then(|ty| self.describe(ty, &mut &error[..]))
+            .unwrap_or_else(|| format!("0x{}", hex::encode(error)))
+    }
+
+    fn constructor(&self, label: &str) -> Result<&MessageSpec, CliError> {
+        self.spec
+            .constructors
+            .iter()
+            .find(|constructor| constructor.label == label)
+            .ok_or_else(|| CliError::UnknownMessage(format!("{}::{label}", self.name())))
+    }
+
+    fn message(&self, label: &str) -> Result<&MessageSpec, CliError> {
+        self.spec
+            .messages
+            .iter()
+            .find(|message| message.label == label)
+            .ok_or_else(|| CliError::UnknownMessage(format!("{}::{label}", self.name())))
+    }
+
+    fn variants(&self, ty: u32) -> Option<&[Variant]> {
+        Some(&self.types.get(&ty)?.variant.as_ref()?.variants)
+    }
+
+    /// Type of the single field of the variant `name` of the enum `ty`.
+    fn variant_field(&self, ty: u32, name: &str) -> Option<u32> {
+        match self
+            .variants(ty)?
+            .iter()
+            .find(|variant| variant.name == name)?
+            .fields[..]
+        {
+            [ref field] => Some(field.id),
+            _ => None,
+        }
+    }
+
+    fn ok_type(&self, result: u32) -> Option<u32> {
+        self.variant_field(result, "Ok")
+    }
+
+    fn err_type(&self, result: u32) -> Option<u32> {
+        self.variant_field(result, "Err")
+    }
+
+    fn describe(&self, ty: u32, input: &mut &[u8]) -> Option<String> {
+        let def = self.types.get(&ty)?;
+        if let Some(Fields { fields }) = &def.composite {
+            // Newtypes are described by their field.
+            return match &fields[..] {
+                [field] => self.describe(field.id, input),
+                _ => None,
+            }
+        }
+        let variants = &def.variant.as_ref()?.variants;
+        let (index, rest) = input.split_first()?;
+        *input = rest;
+        let variant = variants.iter().find(|variant| variant.index == *index)?;
+        let fields: Vec<String> = variant
+            .fields
+            .iter()
+            .map_while(|field| self.describe(field.id, input))
+            .collect();
+        if fields.is_empty() {
+            Some(variant.name.clone())
+        } else {
+            Some(format!("{}({})", variant.name, fields.join(", ")))
+        }
+    }
+}
+
+fn input<A: Encode>(spec: &MessageSpec, args: A) -> Vec<u8> {
+    let mut data = spec.selector.clone();
+    args.encode_to(&mut data);
+    data
+}
+
+fn bytes_from_hex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    hex::decode(hex.trim_start_matches("0x")).map_err(D::Error::custom)
+}
+
+fn types_by_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<u32, Def>, D::Error> {
+    Ok(Vec::<TypeEntry>::deserialize(deserializer)?
+        .into_iter()
+        .map(|entry| (entry.id, entry.ty.def))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn factory() -> Artifact {
+        serde_json::from_str(include_str!("fixtures/factory_contract.contract")).unwrap()
+    }
+
+    #[test]
+    fn encodes_calls() {
+        let factory = factory();
+        assert_eq!(factory.name(), "factory_contract");
+        assert_eq!(factory.wasm(), hex::decode("0061736d01000000").unwrap());
+        assert_eq!(
+            factory
+                .constructor_data("new", ([1u8; 32], [2u8; 32]))
+                .unwrap()[..5],
+            [0x9b, 0xae, 0x9d, 0x5e, 1]
+        );
+        let data = factory
+            .message_data("Factory::set_fee_to", [7u8; 32])
+            .unwrap();
+        assert_eq!(data[..4], [0x3e, 0xf2, 0x05, 0xa7]);
+        assert_eq!(data[4..], [7u8; 32]);
+        assert!(matches!(
+            factory.message_data("Factory::fee_to", ()),
+            Err(CliError::UnknownMessage(_))
+        ));
+    }
+
+    #[test]
+    fn describes_errors() {
+        let factory = factory();
+        assert_eq!(
+            factory.describe_error("Factory::create_pair", &[7]),
+            "PairExists"
+        );
+        // PairError::Arithmetic(MathOp::Mul, PairLocation::Mint)
+        assert_eq!(
+            factory.describe_error("Factory::create_pair", &[0, 14, 2, 0]),
+            "PairError(Arithmetic(Mul, Mint))"
+        );
+        assert_eq!(
+            factory.describe_error("Factory::set_fee_to", &[4]),
+            "CallerIsNotFeeSetter"
+        );
+        assert_eq!(factory.describe_error("Factory::set_fee_to", &[42]), "0x2a");
+    }
+}
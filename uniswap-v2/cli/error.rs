@@ -0,0 +1,62 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum CliError {
+    /// A contract bundle could not be read.
+    Artifact(String),
+    UnknownMessage(String),
+    MissingSigner,
+    InvalidSigner(String),
+    Node(Box<subxt::Error>),
+    /// The runtime rejected a call or an instantiation.
+    Dispatch(String),
+    /// The contract trapped or reverted without returning an error of its own.
+    Reverted {
+        call: String,
+        data: Vec<u8>,
+    },
+    /// The message returned an `Err`, named from the metadata of the contract.
+    Contract {
+        call: String,
+        error: String,
+    },
+    Decode(scale::Error),
+    MissingEvent(&'static str),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Artifact(error) => write!(f, "invalid contract bundle {error}"),
+            CliError::UnknownMessage(label) => {
+                write!(f, "{label} is not in the metadata of the contract")
+            }
+            CliError::MissingSigner => {
+                write!(f, "a signer is required, pass --suri or set DEX_SURI")
+            }
+            CliError::InvalidSigner(error) => write!(f, "invalid signer: {error}"),
+            CliError::Node(error) => write!(f, "node error: {error}"),
+            CliError::Dispatch(error) => write!(f, "dispatch error: {error}"),
+            CliError::Reverted { call, data } => {
+                write!(f, "{call} reverted with 0x{}", hex::encode(data))
+            }
+            CliError::Contract { call, error } => write!(f, "{call} failed with {error}"),
+            CliError::Decode(error) => write!(f, "unexpected output: {error}"),
+            CliError::MissingEvent(event) => write!(f, "no {event} event was emitted"),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl From<subxt::Error> for CliError {
+    fn from(error: subxt::Error) -> Self {
+        CliError::Node(Box::new(error))
+    }
+}
+
+impl From<scale::Error> for CliError {
+    fn from(error: scale::Error) -> Self {
+        CliError::Decode(error)
+    }
+}
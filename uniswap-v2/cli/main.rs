@@ -0,0 +1,412 @@
+//! Operator tool deploying and administering the DEX contracts on a node running the
+//! contracts pallet.
+//!
+//! Contracts are called through the `.contract` bundles built by `cargo contract build`,
+//! read from `--artifacts`. Every command prints its result as text, or as JSON with
+//! `--json`. Balances are printed as decimal strings in JSON so they survive parsers
+//! limited to 64 bit numbers.
+
+mod artifact;
+mod error;
+mod node;
+
+use artifact::Artifact;
+use clap::{
+    Parser,
+    Subcommand,
+};
+use error::CliError;
+use node::{
+    Balance,
+    Node,
+    Submitted,
+};
+use serde::{
+    Serialize,
+    Serializer,
+};
+use std::{
+    fmt,
+    path::PathBuf,
+    process::ExitCode,
+    str::FromStr,
+};
+use subxt::utils::{
+    AccountId32,
+    H256,
+};
+use subxt_signer::{
+    sr25519::Keypair,
+    SecretUri,
+};
+
+#[derive(Debug, Parser)]
+#[command(name = "dex-cli", version, about)]
+struct Cli {
+    /// WebSocket endpoint of the node.
+    #[arg(long, env = "DEX_NODE_URL", default_value = "ws://127.0.0.1:9944")]
+    url: String,
+    /// Secret URI of the signing account, e.g. `//Alice` on a development node.
+    #[arg(long, env = "DEX_SURI", hide_env_values = true)]
+    suri: Option<String>,
+    /// Directory of the contract bundles.
+    #[arg(long, env = "DEX_ARTIFACTS", default_value = "artifacts")]
+    artifacts: PathBuf,
+    /// Prints the result as JSON.
+    #[arg(long, global = true)]
+    json: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Uploads the pair code and instantiates the factory, wnative and router.
+    Deploy {
+        /// Account allowed to set the protocol fee recipient, the signer by default.
+        #[arg(long)]
+        fee_to_setter: Option<AccountId32>,
+        /// Salt of the instantiations, change it to deploy again from the same account.
+        #[arg(long, default_value = "")]
+        salt: String,
+    },
+    /// Creates the pair of two tokens.
+    CreatePair {
+        #[arg(long, env = "DEX_FACTORY")]
+        factory: AccountId32,
+        token_a: AccountId32,
+        token_b: AccountId32,
+    },
+    /// Sets the recipient of the protocol fee.
+    SetFeeTo {
+        #[arg(long, env = "DEX_FACTORY")]
+        factory: AccountId32,
+        fee_to: AccountId32,
+    },
+    /// Hands over the right to set the recipient of the protocol fee.
+    SetFeeToSetter {
+        #[arg(long, env = "DEX_FACTORY")]
+        factory: AccountId32,
+        fee_to_setter: AccountId32,
+    },
+    /// Lists the pairs created by the factory.
+    ListPairs {
+        #[arg(long, env = "DEX_FACTORY")]
+        factory: AccountId32,
+    },
+    /// Shows the tokens, reserves and liquidity of a pair.
+    PairInfo { pair: AccountId32 },
+    /// Dry-runs `get_amounts_out` of the router along a path of tokens.
+    Quote {
+        #[arg(long, env = "DEX_ROUTER")]
+        router: AccountId32,
+        amount_in: Balance,
+        #[arg(required = true, num_args = 2..)]
+        path: Vec<AccountId32>,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct Deployment {
+    pair_code_hash: H256,
+    factory: AccountId32,
+    wnative: AccountId32,
+    router: AccountId32,
+}
+
+#[derive(Debug, Serialize)]
+struct PairInfo {
+    pair: AccountId32,
+    token_0: AccountId32,
+    token_1: AccountId32,
+    #[serde(serialize_with = "as_string")]
+    reserve_0: Balance,
+    #[serde(serialize_with = "as_string")]
+    reserve_1: Balance,
+    block_timestamp_last: u64,
+    #[serde(serialize_with = "as_string")]
+    total_supply: Balance,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum Output {
+    Deployed(Deployment),
+    PairCreated {
+        pair: AccountId32,
+        #[serde(flatten)]
+        submitted: Submitted,
+    },
+    Submitted(Submitted),
+    Pairs(Vec<PairInfo>),
+    PairInfo(PairInfo),
+    Quote {
+        #[serde(serialize_with = "all_as_string")]
+        amounts: Vec<Balance>,
+    },
+}
+
+impl fmt::Display for PairInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "pair          {}", self.pair)?;
+        writeln!(f, "token 0       {}", self.token_0)?;
+        writeln!(f, "token 1       {}", self.token_1)?;
+        writeln!(f, "reserves      {} / {}", self.reserve_0, self.reserve_1)?;
+        writeln!(f, "last update   {}", self.block_timestamp_last)?;
+        write!(f, "total supply  {}", self.total_supply)
+    }
+}
+
+impl fmt::Display for Output {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Output::Deployed(deployment) => {
+                writeln!(f, "pair code hash  {:?}", deployment.pair_code_hash)?;
+                writeln!(f, "factory         {}", deployment.factory)?;
+                writeln!(f, "wnative         {}", deployment.wnative)?;
+                write!(f, "router          {}", deployment.router)
+            }
+            Output::PairCreated { pair, submitted } => {
+                writeln!(f, "pair       {pair}")?;
+                write!(f, "{}", Output::Submitted(submitted.clone()))
+            }
+            Output::Submitted(submitted) => {
+                writeln!(f, "extrinsic  {:?}", submitted.extrinsic_hash)?;
+                write!(f, "block      {:?}", submitted.block_hash)
+            }
+            Output::Pairs(pairs) => {
+                let pairs: Vec<String> = pairs.iter().map(PairInfo::to_string).collect();
+                write!(f, "{}", pairs.join("\n\n"))
+            }
+            Output::PairInfo(info) => write!(f, "{info}"),
+            Output::Quote { amounts } => {
+                let amounts: Vec<String> = amounts.iter().map(Balance::to_string).collect();
+                write!(f, "{}", amounts.join(" -> "))
+            }
+        }
+    }
+}
+
+fn as_string<S: Serializer>(balance: &Balance, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(balance)
+}
+
+fn all_as_string<S: Serializer>(balances: &[Balance], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(balances.iter().map(Balance::to_string))
+}
+
+struct Artifacts(PathBuf);
+
+impl Artifacts {
+    fn load(&self, contract: &str) -> Result<Artifact, CliError> {
+        Artifact::load(self.0.join(format!("{contract}.contract")))
+    }
+}
+
+async fn pair_info(
+    node: &Node,
+    pair_artifact: &Artifact,
+    pair: AccountId32,
+) -> Result<PairInfo, CliError> {
+    let (reserve_0, reserve_1, block_timestamp_last) = node
+        .read(pair_artifact, &pair, "Pair::get_reserves", ())
+        .await?;
+    Ok(PairInfo {
+        token_0: node
+            .read(pair_artifact, &pair, "Pair::get_token_0", ())
+            .await?,
+        token_1: node
+            .read(pair_artifact, &pair, "Pair::get_token_1", ())
+            .await?,
+        reserve_0,
+        reserve_1,
+        block_timestamp_last,
+        total_supply: node
+            .read(pair_artifact, &pair, "PSP22::total_supply", ())
+            .await?,
+        pair,
+    })
+}
+
+async fn run(cli: Cli) -> Result<Output, CliError> {
+    let signer = cli
+        .suri
+        .map(|suri| {
+            SecretUri::from_str(&suri)
+                .map_err(|error| error.to_string())
+                .and_then(|uri| Keypair::from_uri(&uri).map_err(|error| error.to_string()))
+                .map_err(CliError::InvalidSigner)
+        })
+        .transpose()?;
+    let node = Node::connect(&cli.url, signer).await?;
+    let artifacts = Artifacts(cli.artifacts);
+    let output = match cli.command {
+        Command::Deploy {
+            fee_to_setter,
+            salt,
+        } => {
+            let fee_to_setter = fee_to_setter
+                .or_else(|| node.account())
+                .ok_or(CliError::MissingSigner)?;
+            let salt = salt.as_bytes();
+            let pair_code_hash = node.upload_code(&artifacts.load("pair_contract")?).await?;
+            let factory_artifact = artifacts.load("factory_contract")?;
+            node.upload_code(&factory_artifact).await?;
+            let factory = node
+                .instantiate(&factory_artifact, (&fee_to_setter, pair_code_hash), salt)
+                .await?;
+            let wnative_artifact = artifacts.load("wnative_contract")?;
+            node.upload_code(&wnative_artifact).await?;
+            let wnative = node.instantiate(&wnative_artifact, (), salt).await?;
+            let router_artifact = artifacts.load("router_contract")?;
+            node.upload_code(&router_artifact).await?;
+            let router = node
+                .instantiate(&router_artifact, (&factory, &wnative), salt)
+                .await?;
+            Output::Deployed(Deployment {
+                pair_code_hash,
+                factory,
+                wnative,
+                router,
+            })
+        }
+        Command::CreatePair {
+            factory,
+            token_a,
+            token_b,
+        } => {
+            // Pair addresses are derived from the tokens, the dry run returns the created one.
+            let (pair, submitted) = node
+                .submit(
+                    &artifacts.load("factory_contract")?,
+                    &factory,
+                    "Factory::create_pair",
+                    (token_a, token_b),
+                )
+                .await?;
+            Output::PairCreated { pair, submitted }
+        }
+        Command::SetFeeTo { factory, fee_to } => {
+            let ((), submitted) = node
+                .submit(
+                    &artifacts.load("factory_contract")?,
+                    &factory,
+                    "Factory::set_fee_to",
+                    fee_to,
+                )
+                .await?;
+            Output::Submitted(submitted)
+        }
+        Command::SetFeeToSetter {
+            factory,
+            fee_to_setter,
+        } => {
+            let ((), submitted) = node
+                .submit(
+                    &artifacts.load("factory_contract")?,
+                    &factory,
+                    "Factory::set_fee_to_setter",
+                    fee_to_setter,
+                )
+                .await?;
+            Output::Submitted(submitted)
+        }
+        Command::ListPairs { factory } => {
+            let factory_artifact = artifacts.load("factory_contract")?;
+            let pair_artifact = artifacts.load("pair_contract")?;
+            let length: u64 = node
+                .read(&factory_artifact, &factory, "Factory::all_pairs_length", ())
+                .await?;
+            let mut pairs = Vec::new();
+            for pid in 0..length {
+                let pair: Option<AccountId32> = node
+                    .read(&factory_artifact, &factory, "Factory::all_pairs", pid)
+                    .await?;
+                if let Some(pair) = pair {
+                    pairs.push(pair_info(&node, &pair_artifact, pair).await?);
+                }
+            }
+            Output::Pairs(pairs)
+        }
+        Command::PairInfo { pair } => {
+            Output::PairInfo(pair_info(&node, &artifacts.load("pair_contract")?, pair).await?)
+        }
+        Command::Quote {
+            router,
+            amount_in,
+            path,
+        } => {
+            let amounts = node
+                .read_result(
+                    &artifacts.load("router_contract")?,
+                    &router,
+                    "Router::get_amounts_out",
+                    (amount_in, path),
+                )
+                .await?;
+            Output::Quote { amounts }
+        }
+    };
+    Ok(output)
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let json = cli.json;
+    match run(cli).await {
+        Ok(output) if json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&output).expect("output is serializable")
+            );
+            ExitCode::SUCCESS
+        }
+        Ok(output) => {
+            println!("{output}");
+            ExitCode::SUCCESS
+        }
+        Err(error) if json => {
+            println!("{}", serde_json::json!({ "error": error.to_string() }));
+            ExitCode::FAILURE
+        }
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn parses_commands() {
+        Cli::command().debug_assert();
+        let alice = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+        let bob = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+        let cli = Cli::try_parse_from([
+            "dex-cli", "--json", "quote", "--router", alice, "1000", alice, bob,
+        ])
+        .unwrap();
+        assert!(cli.json);
+        assert!(matches!(
+            cli.command,
+            Command::Quote { amount_in: 1000, ref path, .. } if path.len() == 2
+        ));
+        assert!(
+            Cli::try_parse_from(["dex-cli", "quote", "--router", alice, "1000", alice]).is_err()
+        );
+
+        let output = Output::Quote {
+            amounts: vec![u128::MAX, 1],
+        };
+        assert_eq!(
+            serde_json::to_value(&output).unwrap(),
+            serde_json::json!({ "amounts": [u128::MAX.to_string(), "1"] })
+        );
+        assert_eq!(output.to_string(), format!("{} -> 1", u128::MAX));
+    }
+}
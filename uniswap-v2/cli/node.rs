@@ -0,0 +1,473 @@
+use crate::{
+    artifact::Artifact,
+    error::CliError,
+};
+use scale::{
+    Compact,
+    Decode,
+    Encode,
+    Input,
+};
+use serde::Serialize;
+use subxt::{
+    error::{
+        DispatchError,
+        MetadataError,
+    },
+    tx::TxPayload,
+    utils::{
+        AccountId32,
+        MultiAddress,
+        H256,
+    },
+    Metadata,
+    OnlineClient,
+    PolkadotConfig,
+};
+use subxt_signer::sr25519::Keypair;
+
+pub type Balance = u128;
+
+/// Set in the flags of a contract output when its changes were reverted.
+const REVERT: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode)]
+struct Weight {
+    #[codec(compact)]
+    ref_time: u64,
+    #[codec(compact)]
+    proof_size: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Decode)]
+enum StorageDeposit {
+    Refund(Balance),
+    Charge(Balance),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Decode)]
+struct ExecReturnValue {
+    flags: u32,
+    data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Decode)]
+struct InstantiateReturnValue {
+    result: ExecReturnValue,
+    account_id: AccountId32,
+}
+
+/// Result of a dry run through the contracts runtime API. Later fields, such as the events
+/// of recent runtimes, are not decoded.
+#[derive(Debug, Clone, PartialEq, Eq, Decode)]
+struct ContractResult<R> {
+    gas_consumed: Weight,
+    gas_required: Weight,
+    storage_deposit: StorageDeposit,
+    debug_message: Vec<u8>,
+    result: Result<R, EncodedDispatchError>,
+}
+
+/// `sp_runtime::DispatchError` kept encoded, to be named with the metadata of the node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct EncodedDispatchError(Vec<u8>);
+
+impl Decode for EncodedDispatchError {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, scale::Error> {
+        let variant = input.read_byte()?;
+        // `Module` holds the pallet index and an error of 4 bytes, `Token`, `Arithmetic` and
+        // `Transactional` a fieldless enum.
+        let len = match variant {
+            3 => 5,
+            7..=9 => 1,
+            _ => 0,
+        };
+        let mut bytes = vec![variant; 1 + len];
+        input.read(&mut bytes[1..])?;
+        Ok(EncodedDispatchError(bytes))
+    }
+}
+
+/// Error returned by a contract message, kept encoded to be named from the contract metadata.
+struct EncodedError(Vec<u8>);
+
+impl Decode for EncodedError {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, scale::Error> {
+        let mut bytes = vec![0; input.remaining_len()?.unwrap_or_default()];
+        input.read(&mut bytes)?;
+        Ok(EncodedError(bytes))
+    }
+}
+
+#[derive(Debug, Decode)]
+enum LangError {
+    #[codec(index = 1)]
+    CouldNotReadInput,
+}
+
+#[derive(Encode)]
+enum Code {
+    #[codec(index = 1)]
+    Existing(H256),
+}
+
+/// Call of the contracts pallet, encoded with the indices of the pallet and the call in the
+/// metadata of the node.
+struct ContractsCall<A> {
+    name: &'static str,
+    args: A,
+}
+
+impl<A: Encode> TxPayload for ContractsCall<A> {
+    fn encode_call_data_to(
+        &self,
+        metadata: &Metadata,
+        out: &mut Vec<u8>,
+    ) -> Result<(), subxt::Error> {
+        let pallet = metadata.pallet_by_name_err("Contracts")?;
+        let call = pallet
+            .call_variant_by_name(self.name)
+            .ok_or_else(|| MetadataError::CallNameNotFound(self.name.to_owned()))?;
+        out.push(pallet.index());
+        out.push(call.index);
+        self.args.encode_to(out);
+        Ok(())
+    }
+}
+
+/// Hashes identifying a finalized extrinsic.
+#[derive(Debug, Clone, Serialize)]
+pub struct Submitted {
+    pub block_hash: H256,
+    pub extrinsic_hash: H256,
+}
+
+/// Client of a node running the contracts pallet.
+///
+/// Every call is dry-run first: errors of the contract are reported without submitting
+/// anything, and the gas required by the dry run is the limit of the extrinsic.
+pub struct Node {
+    client: OnlineClient<PolkadotConfig>,
+    signer: Option<Keypair>,
+}
+
+impl Node {
+    pub async fn connect(url: &str, signer: Option<Keypair>) -> Result<Self, CliError> {
+        Ok(Node {
+            client: OnlineClient::from_url(url).await?,
+            signer,
+        })
+    }
+
+    pub fn account(&self) -> Option<AccountId32> {
+        self.signer
+            .as_ref()
+            .map(|signer| signer.public_key().to_account_id())
+    }
+
+    fn signer(&self) -> Result<&Keypair, CliError> {
+        self.signer.as_ref().ok_or(CliError::MissingSigner)
+    }
+
+    /// Origin of dry runs, read only calls do not need a signer.
+    fn origin(&self) -> AccountId32 {
+        self.account().unwrap_or(AccountId32([0; 32]))
+    }
+
+    /// Returns the output of the message `label` of `contract`, which cannot fail.
+    pub async fn read<A: Encode, T: Decode>(
+        &self,
+        artifact: &Artifact,
+        contract: &AccountId32,
+        label: &str,
+        args: A,
+    ) -> Result<T, CliError> {
+        let (output, _) = self.dry_run(artifact, contract, label, args).await?;
+        Result::<T, LangError>::decode(&mut &output[..])?.map_err(|error| {
+            CliError::Contract {
+                call: label.to_owned(),
+                error: format!("{error:?}"),
+            }
+        })
+    }
+
+    /// Returns the `Ok` output of the message `label` of `contract`, which returns a `Result`.
+    pub async fn read_result<A: Encode, T: Decode>(
+        &self,
+        artifact: &Artifact,
+        contract: &AccountId32,
+        label: &str,
+        args: A,
+    ) -> Result<T, CliError> {
+        let (output, _) = self.dry_run(artifact, contract, label, args).await?;
+        message_result(artifact, label, &output)
+    }
+
+    /// Calls the message `label` of `contract`, which returns a `Result`, and waits for the
+    /// call to be finalized. Returns the `Ok` output of the dry run.
+    pub async fn submit<A: Encode, T: Decode>(
+        &self,
+        artifact: &Artifact,
+        contract: &AccountId32,
+        label: &str,
+        args: A,
+    ) -> Result<(T, Submitted), CliError> {
+        let signer = self.signer()?;
+        let data = artifact.message_data(label, args)?;
+        let (output, gas_limit) = self.dry_run_data(contract, label, data.clone()).await?;
+        let output = message_result(artifact, label, &output)?;
+        let call = ContractsCall {
+            name: "call",
+            args: (
+                MultiAddress::<AccountId32, ()>::Id(contract.clone()),
+                Compact(0 as Balance),
+                gas_limit,
+                None::<Compact<Balance>>,
+                data,
+            ),
+        };
+        let events = self
+            .client
+            .tx()
+            .sign_and_submit_then_watch_default(&call, signer)
+            .await?
+            .wait_for_finalized_success()
+            .await?;
+        Ok((
+            output,
+            Submitted {
+                block_hash: events.block_hash(),
+                extrinsic_hash: events.extrinsic_hash(),
+            },
+        ))
+    }
+
+    /// Uploads the code of `artifact` unless the node already stores it, returns its hash.
+    pub async fn upload_code(&self, artifact: &Artifact) -> Result<H256, CliError> {
+        let code_hash = H256(artifact.code_hash()?);
+        let stored = subxt::dynamic::storage(
+            "Contracts",
+            "PristineCode",
+            vec![subxt::dynamic::Value::from_bytes(code_hash)],
+        );
+        if self
+            .client
+            .storage()
+            .at_latest()
+            .await?
+            .fetch(&stored)
+            .await?
+            .is_some()
+        {
+            return Ok(code_hash)
+        }
+        // Deterministic code only, the first variant of `Determinism` in all its versions.
+        let call = ContractsCall {
+            name: "upload_code",
+            args: (artifact.wasm(), None::<Compact<Balance>>, 0u8),
+        };
+        self.client
+            .tx()
+            .sign_and_submit_then_watch_default(&call, self.signer()?)
+            .await?
+            .wait_for_finalized_success()
+            .await?;
+        Ok(code_hash)
+    }
+
+    /// Instantiates the uploaded code of `artifact` with the constructor `new`, returns the
+    /// address of the contract.
+    pub async fn instantiate<A: Encode>(
+        &self,
+        artifact: &Artifact,
+        args: A,
+        salt: &[u8],
+    ) -> Result<AccountId32, CliError> {
+        let signer = self.signer()?;
+        let code_hash = H256(artifact.code_hash()?);
+        let data = artifact.constructor_data("new", args)?;
+        let call = format!("{}::new", artifact.name());
+        let dry_run: ContractResult<InstantiateReturnValue> = self
+            .client
+            .runtime_api()
+            .at_latest()
+            .await?
+            .call_raw(
+                "ContractsApi_instantiate",
+                Some(
+                    &(
+                        self.origin(),
+                        0 as Balance,
+                        None::<Weight>,
+                        None::<Balance>,
+                        Code::Existing(code_hash),
+                        &data,
+                        salt,
+                    )
+                        .encode(),
+                ),
+            )
+            .await?;
+        let result = self.dispatch_result(dry_run.result)?.result;
+        if result.flags & REVERT != 0 {
+            return Err(CliError::Reverted {
+                call,
+                data: result.data,
+            })
+        }
+        let instantiate = ContractsCall {
+            name: "instantiate",
+            args: (
+                Compact(0 as Balance),
+                dry_run.gas_required,
+                None::<Compact<Balance>>,
+                code_hash,
+                data,
+                salt,
+            ),
+        };
+        let events = self
+            .client
+            .tx()
+            .sign_and_submit_then_watch_default(&instantiate, signer)
+            .await?
+            .wait_for_finalized_success()
+            .await?;
+        for event in events.iter() {
+            let event = event?;
+            if event.pallet_name() == "Contracts" && event.variant_name() == "Instantiated" {
+                let (_deployer, contract) =
+                    <(AccountId32, AccountId32)>::decode(&mut event.field_bytes())?;
+                return Ok(contract)
+            }
+        }
+        Err(CliError::MissingEvent("Contracts::Instantiated"))
+    }
+
+    async fn dry_run<A: Encode>(
+        &self,
+        artifact: &Artifact,
+        contract: &AccountId32,
+        label: &str,
+        args: A,
+    ) -> Result<(Vec<u8>, Weight), CliError> {
+        let data = artifact.message_data(label, args)?;
+        self.dry_run_data(contract, label, data).await
+    }
+
+    /// Returns the output of the call and the gas it requires. Outputs of reverted calls are
+    /// returned too, fallible messages revert when they return an `Err`.
+    async fn dry_run_data(
+        &self,
+        contract: &AccountId32,
+        label: &str,
+        data: Vec<u8>,
+    ) -> Result<(Vec<u8>, Weight), CliError> {
+        let dry_run: ContractResult<ExecReturnValue> = self
+            .client
+            .runtime_api()
+            .at_latest()
+            .await?
+            .call_raw(
+                "ContractsApi_call",
+                Some(
+                    &(
+                        self.origin(),
+                        contract,
+                        0 as Balance,
+                        None::<Weight>,
+                        None::<Balance>,
+                        data,
+                    )
+                        .encode(),
+                ),
+            )
+            .await?;
+        let gas_required = dry_run.gas_required;
+        let result = self.dispatch_result(dry_run.result).map_err(|error| {
+            match error {
+                CliError::Dispatch(error) => CliError::Dispatch(format!("{label}: {error}")),
+                error => error,
+            }
+        })?;
+        Ok((result.data, gas_required))
+    }
+
+    fn dispatch_result<R>(&self, result: Result<R, EncodedDispatchError>) -> Result<R, CliError> {
+        result.map_err(|EncodedDispatchError(bytes)| {
+            CliError::Dispatch(
+                DispatchError::decode_from(&bytes[..], self.client.metadata())
+                    .map(|error| error.to_string())
+                    .unwrap_or_else(|_| format!("0x{}", hex::encode(&bytes))),
+            )
+        })
+    }
+}
+
+/// Decodes the output of a message returning a `Result`, naming its error with the metadata.
+fn message_result<T: Decode>(
+    artifact: &Artifact,
+    label: &str,
+    output: &[u8],
+) -> Result<T, CliError> {
+    match Result::<Result<T, EncodedError>, LangError>::decode(&mut &output[..])? {
+        Ok(Ok(output)) => Ok(output),
+        Ok(Err(EncodedError(error))) => {
+            Err(CliError::Contract {
+                call: label.to_owned(),
+                error: artifact.describe_error(label, &error),
+            })
+        }
+        Err(error) => {
+            Err(CliError::Contract {
+                call: label.to_owned(),
+                error: format!("{error:?}"),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_dry_runs() {
+        let weight = Weight {
+            ref_time: 1_000_000,
+            proof_size: 4_096,
+        };
+        let mut encoded = (weight, weight, 1u8, 100 as Balance, Vec::<u8>::new()).encode();
+        let prefix = encoded.len();
+        // Ok(ExecReturnValue { flags: REVERT, data: Ok(Err(FactoryError::PairExists)) })
+        (0u8, REVERT, vec![0u8, 1, 7]).encode_to(&mut encoded);
+        // Events of recent runtimes.
+        encoded.extend_from_slice(&[1, 0]);
+        let dry_run = ContractResult::<ExecReturnValue>::decode(&mut &encoded[..]).unwrap();
+        assert_eq!(dry_run.gas_required, weight);
+        assert_eq!(dry_run.storage_deposit, StorageDeposit::Charge(100));
+        let output = dry_run.result.unwrap();
+        assert_eq!(output.flags, REVERT);
+
+        let factory: Artifact =
+            serde_json::from_str(include_str!("fixtures/factory_contract.contract")).unwrap();
+        assert!(matches!(
+            message_result::<AccountId32>(&factory, "Factory::create_pair", &output.data),
+            Err(CliError::Contract { error, .. }) if error == "PairExists"
+        ));
+        assert!(matches!(
+            message_result::<AccountId32>(&factory, "Factory::create_pair", &[0, 0, 9, 9]),
+            Err(CliError::Decode(_))
+        ));
+
+        // Err(DispatchError::Module { index: 70, error: [5, 0, 0, 0] })
+        encoded.truncate(prefix);
+        encoded.extend_from_slice(&[1, 3, 70, 5, 0, 0, 0, 1, 0]);
+        assert_eq!(
+            ContractResult::<ExecReturnValue>::decode(&mut &encoded[..])
+                .unwrap()
+                .result,
+            Err(EncodedDispatchError(vec![3, 70, 5, 0, 0, 0]))
+        );
+    }
+}
@@ -0,0 +1,89 @@
+//! Runs `dex-cli` against a local node with the contracts pallet, e.g. the swanky node of
+//! the CI, reading the bundles built by `yarn compile`:
+//!
+//! ```sh
+//! DEX_NODE_URL=ws://127.0.0.1:9944 DEX_ARTIFACTS=/path/to/bundles cargo test -- --ignored
+//! ```
+
+use serde_json::Value;
+use std::process::{
+    Command,
+    Output,
+};
+
+const BOB: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+
+fn run(args: &[&str]) -> (Output, Value) {
+    let output = Command::new(env!("CARGO_BIN_EXE_dex-cli"))
+        .args(["--suri", "//Alice", "--json"])
+        .args(args)
+        .output()
+        .expect("dex-cli runs");
+    let value = serde_json::from_slice(&output.stdout).expect("dex-cli prints JSON");
+    (output, value)
+}
+
+fn dex_cli(args: &[&str]) -> Value {
+    let (output, value) = run(args);
+    assert!(output.status.success(), "dex-cli {args:?} failed: {value}");
+    value
+}
+
+fn dex_cli_error(args: &[&str]) -> String {
+    let (output, value) = run(args);
+    assert!(
+        !output.status.success(),
+        "dex-cli {args:?} succeeded: {value}"
+    );
+    value["error"].as_str().expect("error message").to_owned()
+}
+
+#[test]
+#[ignore = "needs a local contracts node and the contract bundles"]
+fn deploys_and_administers_the_factory() {
+    // A new salt per run deploys new contracts on a node that is not restarted.
+    let salt = std::time::UNIX_EPOCH
+        .elapsed()
+        .expect("clock after 1970")
+        .as_nanos()
+        .to_string();
+    let deployed = dex_cli(&["deploy", "--salt", &salt]);
+    let factory = deployed["factory"].as_str().unwrap();
+    let wnative = deployed["wnative"].as_str().unwrap();
+    let router = deployed["router"].as_str().unwrap();
+
+    // Pairs do not call their tokens when created, any two accounts make a pair.
+    let created = dex_cli(&["create-pair", "--factory", factory, wnative, router]);
+    let pair = created["pair"].as_str().unwrap();
+    assert!(created["block_hash"].is_string());
+
+    let pairs = dex_cli(&["list-pairs", "--factory", factory]);
+    assert_eq!(pairs.as_array().unwrap().len(), 1);
+    let info = &pairs[0];
+    assert_eq!(info["pair"], pair);
+    let mut tokens = [
+        info["token_0"].as_str().unwrap(),
+        info["token_1"].as_str().unwrap(),
+    ];
+    tokens.sort_unstable();
+    let mut expected = [wnative, router];
+    expected.sort_unstable();
+    assert_eq!(tokens, expected);
+    assert_eq!(
+        (
+            &info["reserve_0"],
+            &info["reserve_1"],
+            &info["total_supply"]
+        ),
+        (&Value::from("0"), &Value::from("0"), &Value::from("0"))
+    );
+    assert_eq!(dex_cli(&["pair-info", pair]), *info);
+
+    dex_cli_error(&["create-pair", "--factory", factory, router, wnative]);
+    dex_cli_error(&["quote", "--router", router, "1000", wnative, router]);
+
+    dex_cli(&["set-fee-to", "--factory", factory, BOB]);
+    dex_cli(&["set-fee-to-setter", "--factory", factory, BOB]);
+    // Alice handed over the right to set the recipient to Bob.
+    dex_cli_error(&["set-fee-to", "--factory", factory, BOB]);
+}
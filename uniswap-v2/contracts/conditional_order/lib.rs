@@ -0,0 +1,206 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+#![feature(min_specialization)]
+
+#[openbrush::contract]
+pub mod conditional_order {
+    use ink::codegen::{
+        EmitEvent,
+        Env,
+    };
+    use openbrush::{
+        contracts::reentrancy_guard,
+        traits::Storage,
+    };
+    use uniswap_v2::{
+        impls::conditional_order::*,
+        traits::conditional_order::*,
+    };
+
+    #[ink(event)]
+    pub struct OrderPlaced {
+        #[ink(topic)]
+        pub order_id: OrderId,
+        #[ink(topic)]
+        pub owner: AccountId,
+        pub token_in: AccountId,
+        pub token_out: AccountId,
+        pub amount_in: Balance,
+        pub trigger: Trigger,
+        pub trigger_price: Price,
+    }
+
+    #[ink(event)]
+    pub struct OrderExecuted {
+        #[ink(topic)]
+        pub order_id: OrderId,
+        #[ink(topic)]
+        pub keeper: AccountId,
+        pub twap: Price,
+        pub amount_in: Balance,
+        pub amount_out: Balance,
+    }
+
+    #[ink(event)]
+    pub struct OrderCancelled {
+        #[ink(topic)]
+        pub order_id: OrderId,
+    }
+
+    #[ink(storage)]
+    #[derive(Default, Storage)]
+    pub struct ConditionalOrderContract {
+        #[storage_field]
+        guard: reentrancy_guard::Data,
+        #[storage_field]
+        conditional_order: data::Data,
+    }
+
+    impl ConditionalOrder for ConditionalOrderContract {}
+
+    impl conditional_order::Internal for ConditionalOrderContract {
+        fn _emit_order_placed_event(
+            &self,
+            order_id: OrderId,
+            owner: AccountId,
+            token_in: AccountId,
+            token_out: AccountId,
+            amount_in: Balance,
+            trigger: Trigger,
+            trigger_price: Price,
+        ) {
+            EmitEvent::<ConditionalOrderContract>::emit_event(
+                self.env(),
+                OrderPlaced {
+                    order_id,
+                    owner,
+                    token_in,
+                    token_out,
+                    amount_in,
+                    trigger,
+                    trigger_price,
+                },
+            )
+        }
+
+        fn _emit_order_executed_event(
+            &self,
+            order_id: OrderId,
+            keeper: AccountId,
+            twap: Price,
+            amount_in: Balance,
+            amount_out: Balance,
+        ) {
+            EmitEvent::<ConditionalOrderContract>::emit_event(
+                self.env(),
+                OrderExecuted {
+                    order_id,
+                    keeper,
+                    twap,
+                    amount_in,
+                    amount_out,
+                },
+            )
+        }
+
+        fn _emit_order_cancelled_event(&self, order_id: OrderId) {
+            EmitEvent::<ConditionalOrderContract>::emit_event(
+                self.env(),
+                OrderCancelled { order_id },
+            )
+        }
+    }
+
+    impl ConditionalOrderContract {
+        #[ink(constructor)]
+        pub fn new(factory: AccountId, router: AccountId, twap_period: Timestamp) -> Self {
+            let mut instance = Self::default();
+            instance.conditional_order.factory = factory;
+            instance.conditional_order.router = router;
+            instance.conditional_order.twap_period = twap_period;
+            instance
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::env::{
+            test::{
+                default_accounts,
+                set_block_timestamp,
+            },
+            DefaultEnvironment,
+        };
+
+        fn insert_order(conditional_order: &mut ConditionalOrderContract, owner: AccountId) {
+            conditional_order.conditional_order.orders.insert(
+                &0,
+                &ConditionalOrderInfo {
+                    owner,
+                    token_in: AccountId::from([0x05; 32]),
+                    token_out: AccountId::from([0x06; 32]),
+                    amount_in: 1_000,
+                    trigger: Trigger::StopLoss,
+                    trigger_price: 1,
+                    max_slippage_bps: 100,
+                    execution_fee: 10,
+                    expiry: 10,
+                },
+            );
+        }
+
+        #[ink::test]
+        fn initialize_works() {
+            let factory = AccountId::from([0x03; 32]);
+            let router = AccountId::from([0x04; 32]);
+            let conditional_order = ConditionalOrderContract::new(factory, router, 3_600_000);
+            assert_eq!(conditional_order.factory(), factory);
+            assert_eq!(conditional_order.router(), router);
+            assert_eq!(conditional_order.twap_period(), 3_600_000);
+        }
+
+        #[ink::test]
+        fn execute_unknown_order_fails() {
+            let mut conditional_order = ConditionalOrderContract::new(
+                AccountId::from([0x03; 32]),
+                AccountId::from([0x04; 32]),
+                3_600_000,
+            );
+            assert_eq!(
+                conditional_order.execute_order(0),
+                Err(ConditionalOrderError::OrderNotFound)
+            );
+        }
+
+        #[ink::test]
+        fn execute_expired_order_fails() {
+            let accounts = default_accounts::<DefaultEnvironment>();
+            let mut conditional_order = ConditionalOrderContract::new(
+                AccountId::from([0x03; 32]),
+                AccountId::from([0x04; 32]),
+                3_600_000,
+            );
+            insert_order(&mut conditional_order, accounts.bob);
+            set_block_timestamp::<DefaultEnvironment>(11);
+            assert_eq!(
+                conditional_order.execute_order(0),
+                Err(ConditionalOrderError::Expired)
+            );
+        }
+
+        #[ink::test]
+        fn cancel_order_of_other_owner_fails() {
+            let accounts = default_accounts::<DefaultEnvironment>();
+            let mut conditional_order = ConditionalOrderContract::new(
+                AccountId::from([0x03; 32]),
+                AccountId::from([0x04; 32]),
+                3_600_000,
+            );
+            insert_order(&mut conditional_order, accounts.bob);
+            assert_eq!(
+                conditional_order.cancel_order(0),
+                Err(ConditionalOrderError::CallerIsNotOwner)
+            );
+        }
+    }
+}
@@ -0,0 +1,171 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+#![feature(min_specialization)]
+
+#[openbrush::contract]
+pub mod dca {
+    use ink::codegen::{
+        EmitEvent,
+        Env,
+    };
+    use openbrush::{
+        contracts::reentrancy_guard,
+        traits::Storage,
+    };
+    use uniswap_v2::{
+        impls::dca::*,
+        traits::dca::*,
+    };
+
+    #[ink(event)]
+    pub struct PositionCreated {
+        #[ink(topic)]
+        pub position_id: PositionId,
+        #[ink(topic)]
+        pub owner: AccountId,
+        pub token_in: AccountId,
+        pub token_out: AccountId,
+        pub amount_per_interval: Balance,
+        pub number_of_intervals: u32,
+        pub interval: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct Executed {
+        #[ink(topic)]
+        pub position_id: PositionId,
+        #[ink(topic)]
+        pub keeper: AccountId,
+        pub amount_in: Balance,
+        pub amount_out: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Withdrawn {
+        #[ink(topic)]
+        pub position_id: PositionId,
+        pub amount_out: Balance,
+    }
+
+    #[ink(event)]
+    pub struct PositionClosed {
+        #[ink(topic)]
+        pub position_id: PositionId,
+        pub amount_in_refunded: Balance,
+        pub amount_out: Balance,
+    }
+
+    #[ink(storage)]
+    #[derive(Default, Storage)]
+    pub struct DcaContract {
+        #[storage_field]
+        guard: reentrancy_guard::Data,
+        #[storage_field]
+        dca: data::Data,
+    }
+
+    impl Dca for DcaContract {}
+
+    impl dca::Internal for DcaContract {
+        fn _emit_position_created_event(
+            &self,
+            position_id: PositionId,
+            owner: AccountId,
+            token_in: AccountId,
+            token_out: AccountId,
+            amount_per_interval: Balance,
+            number_of_intervals: u32,
+            interval: Timestamp,
+        ) {
+            EmitEvent::<DcaContract>::emit_event(
+                self.env(),
+                PositionCreated {
+                    position_id,
+                    owner,
+                    token_in,
+                    token_out,
+                    amount_per_interval,
+                    number_of_intervals,
+                    interval,
+                },
+            )
+        }
+
+        fn _emit_executed_event(
+            &self,
+            position_id: PositionId,
+            keeper: AccountId,
+            amount_in: Balance,
+            amount_out: Balance,
+        ) {
+            EmitEvent::<DcaContract>::emit_event(
+                self.env(),
+                Executed {
+                    position_id,
+                    keeper,
+                    amount_in,
+                    amount_out,
+                },
+            )
+        }
+
+        fn _emit_withdrawn_event(&self, position_id: PositionId, amount_out: Balance) {
+            EmitEvent::<DcaContract>::emit_event(
+                self.env(),
+                Withdrawn {
+                    position_id,
+                    amount_out,
+                },
+            )
+        }
+
+        fn _emit_position_closed_event(
+            &self,
+            position_id: PositionId,
+            amount_in_refunded: Balance,
+            amount_out: Balance,
+        ) {
+            EmitEvent::<DcaContract>::emit_event(
+                self.env(),
+                PositionClosed {
+                    position_id,
+                    amount_in_refunded,
+                    amount_out,
+                },
+            )
+        }
+    }
+
+    impl DcaContract {
+        #[ink(constructor)]
+        pub fn new(factory: AccountId, router: AccountId, twap_period: Timestamp) -> Self {
+            let mut instance = Self::default();
+            instance.dca.factory = factory;
+            instance.dca.router = router;
+            instance.dca.twap_period = twap_period;
+            instance
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn initialize_works() {
+            let factory = AccountId::from([0x03; 32]);
+            let router = AccountId::from([0x04; 32]);
+            let dca = DcaContract::new(factory, router, 3_600_000);
+            assert_eq!(dca.factory(), factory);
+            assert_eq!(dca.router(), router);
+            assert_eq!(dca.twap_period(), 3_600_000);
+            assert_eq!(dca.next_position_id(), 0);
+        }
+
+        #[ink::test]
+        fn withdraw_unknown_position_fails() {
+            let mut dca =
+                DcaContract::new(AccountId::from([0x03; 32]), AccountId::from([0x04; 32]), 0);
+            assert_eq!(dca.withdraw(0), Err(DcaError::PositionNotFound));
+        }
+    }
+}
@@ -8,13 +8,22 @@ pub mod factory {
             EmitEvent,
             Env,
         },
+        env::call::{
+            build_create,
+            ExecutionInput,
+            Selector,
+        },
+        prelude::vec::Vec,
         ToAccountId,
     };
     use openbrush::traits::Storage;
     use pair_contract::pair::PairContractRef;
     use uniswap_v2::{
         impls::factory::*,
-        traits::factory::*,
+        traits::{
+            errors::instantiate_result,
+            factory::*,
+        },
     };
 
     #[ink(event)]
@@ -25,6 +34,7 @@ pub mod factory {
         pub token_1: AccountId,
         pub pair: AccountId,
         pub pair_len: u64,
+        pub pool_type: PoolTypeId,
     }
 
     #[ink(storage)]
@@ -37,18 +47,39 @@ pub mod factory {
     impl Factory for FactoryContract {}
 
     impl factory::Internal for FactoryContract {
-        fn _instantiate_pair(&mut self, salt_bytes: &[u8]) -> Result<AccountId, FactoryError> {
-            let pair_hash = self.factory.pair_contract_code_hash;
-            let pair = match PairContractRef::new()
-                .endowment(0)
-                .code_hash(pair_hash)
-                .salt_bytes(&salt_bytes[..4])
-                .try_instantiate()
-            {
-                Ok(Ok(res)) => Ok(res),
-                _ => Err(FactoryError::PairInstantiationFailed),
-            }?;
-            Ok(pair.to_account_id())
+        fn _instantiate_pool(
+            &mut self,
+            pool_type: &PoolTypeInfo,
+            salt_bytes: &[u8],
+            params: Vec<u8>,
+        ) -> Result<AccountId, FactoryError> {
+            let result = match pool_type.constructor {
+                ConstructorKind::Default => {
+                    PairContractRef::new()
+                        .endowment(0)
+                        .code_hash(pool_type.code_hash)
+                        .salt_bytes(&salt_bytes[..4])
+                        .try_instantiate()
+                }
+                ConstructorKind::WithParams => {
+                    // Any pool contract exposing `new_with_params` can be instantiated,
+                    // `PairContractRef` is only used to get back its `AccountId`.
+                    build_create::<PairContractRef>()
+                        .code_hash(pool_type.code_hash)
+                        .endowment(0)
+                        .exec_input(
+                            ExecutionInput::new(Selector::new(ink::selector_bytes!(
+                                "new_with_params"
+                            )))
+                            .push_arg(params),
+                        )
+                        .salt_bytes(&salt_bytes[..4])
+                        .returns::<PairContractRef>()
+                        .try_instantiate()
+                }
+            };
+            let pool = instantiate_result(pool_type.code_hash, result)?;
+            Ok(pool.to_account_id())
         }
 
         fn _emit_create_pair_event(
@@ -57,6 +88,7 @@ pub mod factory {
             token_1: AccountId,
             pair: AccountId,
             pair_len: u64,
+            pool_type: PoolTypeId,
         ) {
             EmitEvent::<FactoryContract>::emit_event(
                 self.env(),
@@ -65,6 +97,7 @@ pub mod factory {
                     token_1,
                     pair,
                     pair_len,
+                    pool_type,
                 },
             )
         }
@@ -74,7 +107,12 @@ pub mod factory {
         #[ink(constructor)]
         pub fn new(fee_to_setter: AccountId, pair_code_hash: Hash) -> Self {
             let mut instance = Self::default();
-            instance.factory.pair_contract_code_hash = pair_code_hash;
+            factory::insert_pool_type(
+                &mut instance,
+                CONSTANT_PRODUCT_POOL_TYPE,
+                pair_code_hash,
+                ConstructorKind::Default,
+            );
             instance.factory.fee_to_setter = fee_to_setter;
             instance
         }
@@ -82,10 +120,20 @@ pub mod factory {
     #[cfg(test)]
     mod tests {
         use ink::{
-            env::test::default_accounts,
+            env::test::{
+                default_accounts,
+                set_caller,
+            },
             primitives::Hash,
         };
-        use openbrush::traits::AccountIdExt;
+        use openbrush::traits::{
+            AccountIdExt,
+            ZERO_ADDRESS,
+        };
+        use uniswap_v2::traits::pair::{
+            DynamicFeeConfig,
+            PairError,
+        };
 
         use super::*;
 
@@ -95,5 +143,151 @@ pub mod factory {
             let factory = FactoryContract::new(accounts.alice, Hash::default());
             assert!(factory.factory.fee_to.is_zero());
         }
+
+        #[ink::test]
+        fn constant_product_pool_type_is_registered() {
+            let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+            let pair_hash = Hash::from([0x01; 32]);
+            let factory = FactoryContract::new(accounts.alice, pair_hash);
+            assert_eq!(factory.pool_types(), vec![CONSTANT_PRODUCT_POOL_TYPE]);
+            assert_eq!(
+                factory.pool_type(CONSTANT_PRODUCT_POOL_TYPE),
+                Some(PoolTypeInfo {
+                    code_hash: pair_hash,
+                    constructor: ConstructorKind::Default,
+                    enabled: true,
+                })
+            );
+            assert_eq!(factory.pair_contract_code_hash(), pair_hash);
+        }
+
+        #[ink::test]
+        fn protocol_fee_defaults_to_fee_to() {
+            let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+            let mut factory = FactoryContract::new(accounts.alice, Hash::default());
+            let pair = AccountId::from([0x03; 32]);
+            assert_eq!(factory.set_fee_to(accounts.charlie), Ok(()));
+            assert_eq!(
+                factory.protocol_fee_of(pair),
+                ProtocolFee {
+                    fee_to: accounts.charlie,
+                    creator: ZERO_ADDRESS.into(),
+                    creator_share_bps: 0,
+                }
+            );
+            assert_eq!(
+                factory.set_pair_fee_config(
+                    pair,
+                    Some(PairFeeConfig {
+                        enabled: false,
+                        fee_to: accounts.bob,
+                        creator_share_bps: 0,
+                    })
+                ),
+                Err(FactoryError::PoolNotFound)
+            );
+        }
+
+        #[ink::test]
+        fn set_trusted_router_works() {
+            let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+            let mut factory = FactoryContract::new(accounts.alice, Hash::default());
+            let router = AccountId::from([0x05; 32]);
+            assert!(!factory.is_trusted_router(router));
+            assert_eq!(factory.set_trusted_router(router, true), Ok(()));
+            assert!(factory.is_trusted_router(router));
+            assert_eq!(factory.set_trusted_router(router, false), Ok(()));
+            assert!(!factory.is_trusted_router(router));
+        }
+
+        #[ink::test]
+        fn create_pair_for_only_trusted_router() {
+            let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+            let mut factory = FactoryContract::new(accounts.alice, Hash::default());
+            let (token_a, token_b) = (AccountId::from([0x03; 32]), AccountId::from([0x04; 32]));
+            assert_eq!(
+                factory.create_pair_for(token_a, token_b, accounts.bob),
+                Err(FactoryError::CallerIsNotTrustedRouter)
+            );
+            assert_eq!(factory.set_trusted_router(accounts.alice, true), Ok(()));
+            assert_eq!(
+                factory.create_pair_for(token_a, token_a, accounts.bob),
+                Err(FactoryError::IdenticalAddresses)
+            );
+        }
+
+        #[ink::test]
+        fn pair_contract_code_hash_follows_registry() {
+            let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+            let mut factory = FactoryContract::new(accounts.alice, Hash::from([0x01; 32]));
+            let pair_hash = Hash::from([0x02; 32]);
+            assert_eq!(
+                factory.register_pool_type(
+                    CONSTANT_PRODUCT_POOL_TYPE,
+                    pair_hash,
+                    ConstructorKind::Default
+                ),
+                Ok(())
+            );
+            assert_eq!(factory.pair_contract_code_hash(), pair_hash);
+            assert_eq!(factory.pool_types(), vec![CONSTANT_PRODUCT_POOL_TYPE]);
+            assert_eq!(
+                factory.get_pair(AccountId::from([0x03; 32]), AccountId::from([0x04; 32])),
+                None
+            );
+        }
+
+        #[ink::test]
+        fn fee_setter_can_reassign_pair_creator() {
+            let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+            let mut factory = FactoryContract::new(accounts.alice, Hash::default());
+            let pair = AccountId::from([0x03; 32]);
+            let router = AccountId::from([0x04; 32]);
+            factory.factory.pair_creator.insert(&pair, &router);
+            assert_eq!(factory.set_pair_creator(pair, accounts.bob), Ok(()));
+            assert_eq!(factory.pair_creator(pair), Some(accounts.bob));
+
+            set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(
+                factory.set_pair_creator(pair, accounts.charlie),
+                Err(FactoryError::CallerIsNotCreator)
+            );
+            set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(factory.set_pair_creator(pair, accounts.django), Ok(()));
+            assert_eq!(factory.pair_creator(pair), Some(accounts.django));
+        }
+
+        #[ink::test]
+        fn set_pair_dynamic_fee_rejects_inverted_bounds() {
+            let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+            let mut factory = FactoryContract::new(accounts.alice, Hash::default());
+            let pair = AccountId::from([0x03; 32]);
+            factory
+                .factory
+                .pool_type_of
+                .insert(&pair, &CONSTANT_PRODUCT_POOL_TYPE);
+            assert_eq!(
+                factory.set_pair_dynamic_fee(
+                    pair,
+                    Some(DynamicFeeConfig {
+                        min_fee_bps: 50,
+                        max_fee_bps: 10,
+                        volatility_factor_bps: 1,
+                        window: 60_000,
+                    })
+                ),
+                Err(FactoryError::PairError(PairError::InvalidFeeConfig))
+            );
+        }
+
+        #[ink::test]
+        fn register_pool_type_only_fee_setter() {
+            let accounts = default_accounts::<ink::env::DefaultEnvironment>();
+            let mut factory = FactoryContract::new(accounts.bob, Hash::default());
+            assert_eq!(
+                factory.register_pool_type(1, Hash::from([0x02; 32]), ConstructorKind::WithParams),
+                Err(FactoryError::CallerIsNotFeeSetter)
+            );
+        }
     }
 }
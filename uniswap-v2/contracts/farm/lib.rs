@@ -0,0 +1,258 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+#![feature(min_specialization)]
+
+#[openbrush::contract]
+pub mod farm {
+    use ink::codegen::{
+        EmitEvent,
+        Env,
+    };
+    use openbrush::{
+        contracts::{
+            ownable::*,
+            reentrancy_guard,
+        },
+        traits::Storage,
+    };
+    use uniswap_v2::{
+        impls::farm::*,
+        traits::farm::*,
+    };
+
+    #[ink(event)]
+    pub struct PoolAdded {
+        #[ink(topic)]
+        pub pool_id: PoolId,
+        #[ink(topic)]
+        pub lp_token: AccountId,
+        pub alloc_point: u32,
+    }
+
+    #[ink(event)]
+    pub struct PoolSet {
+        #[ink(topic)]
+        pub pool_id: PoolId,
+        pub alloc_point: u32,
+    }
+
+    #[ink(event)]
+    pub struct RewardPerSecondSet {
+        pub reward_per_second: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Deposit {
+        #[ink(topic)]
+        pub user: AccountId,
+        #[ink(topic)]
+        pub pool_id: PoolId,
+        pub amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Withdraw {
+        #[ink(topic)]
+        pub user: AccountId,
+        #[ink(topic)]
+        pub pool_id: PoolId,
+        pub amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Harvest {
+        #[ink(topic)]
+        pub user: AccountId,
+        #[ink(topic)]
+        pub pool_id: PoolId,
+        pub amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct EmergencyWithdraw {
+        #[ink(topic)]
+        pub user: AccountId,
+        #[ink(topic)]
+        pub pool_id: PoolId,
+        pub amount: Balance,
+    }
+
+    #[ink(storage)]
+    #[derive(Default, Storage)]
+    pub struct FarmContract {
+        #[storage_field]
+        ownable: ownable::Data,
+        #[storage_field]
+        guard: reentrancy_guard::Data,
+        #[storage_field]
+        farm: data::Data,
+    }
+
+    impl Ownable for FarmContract {}
+
+    impl Farm for FarmContract {}
+
+    impl farm::Internal for FarmContract {
+        fn _emit_pool_added_event(&self, pool_id: PoolId, lp_token: AccountId, alloc_point: u32) {
+            EmitEvent::<FarmContract>::emit_event(
+                self.env(),
+                PoolAdded {
+                    pool_id,
+                    lp_token,
+                    alloc_point,
+                },
+            )
+        }
+
+        fn _emit_pool_set_event(&self, pool_id: PoolId, alloc_point: u32) {
+            EmitEvent::<FarmContract>::emit_event(
+                self.env(),
+                PoolSet {
+                    pool_id,
+                    alloc_point,
+                },
+            )
+        }
+
+        fn _emit_reward_per_second_set_event(&self, reward_per_second: Balance) {
+            EmitEvent::<FarmContract>::emit_event(
+                self.env(),
+                RewardPerSecondSet { reward_per_second },
+            )
+        }
+
+        fn _emit_deposit_event(&self, user: AccountId, pool_id: PoolId, amount: Balance) {
+            EmitEvent::<FarmContract>::emit_event(
+                self.env(),
+                Deposit {
+                    user,
+                    pool_id,
+                    amount,
+                },
+            )
+        }
+
+        fn _emit_withdraw_event(&self, user: AccountId, pool_id: PoolId, amount: Balance) {
+            EmitEvent::<FarmContract>::emit_event(
+                self.env(),
+                Withdraw {
+                    user,
+                    pool_id,
+                    amount,
+                },
+            )
+        }
+
+        fn _emit_harvest_event(&self, user: AccountId, pool_id: PoolId, amount: Balance) {
+            EmitEvent::<FarmContract>::emit_event(
+                self.env(),
+                Harvest {
+                    user,
+                    pool_id,
+                    amount,
+                },
+            )
+        }
+
+        fn _emit_emergency_withdraw_event(
+            &self,
+            user: AccountId,
+            pool_id: PoolId,
+            amount: Balance,
+        ) {
+            EmitEvent::<FarmContract>::emit_event(
+                self.env(),
+                EmergencyWithdraw {
+                    user,
+                    pool_id,
+                    amount,
+                },
+            )
+        }
+    }
+
+    impl FarmContract {
+        #[ink(constructor)]
+        pub fn new(reward_token: AccountId, reward_per_second: Balance) -> Self {
+            let mut instance = Self::default();
+            let caller = instance.env().caller();
+            instance._init_with_owner(caller);
+            instance.farm.reward_token = reward_token;
+            instance.farm.reward_per_second = reward_per_second;
+            instance
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::env::{
+            test::{
+                default_accounts,
+                set_block_timestamp,
+                set_caller,
+            },
+            DefaultEnvironment,
+        };
+
+        #[ink::test]
+        fn add_pool_works() {
+            let mut farm = FarmContract::new(AccountId::from([0x03; 32]), 1_000);
+            let lp_token = AccountId::from([0x04; 32]);
+            assert_eq!(farm.add_pool(lp_token, 100), Ok(0));
+            assert_eq!(
+                farm.add_pool(lp_token, 100),
+                Err(FarmError::PoolAlreadyExists)
+            );
+            assert_eq!(
+                farm.add_pool(AccountId::from([0x03; 32]), 100),
+                Err(FarmError::InvalidLpToken)
+            );
+            assert_eq!(farm.pool_id_of(lp_token), Some(0));
+            assert_eq!(farm.pool_length(), 1);
+            assert_eq!(farm.total_alloc_point(), 100);
+
+            assert_eq!(farm.add_pool(AccountId::from([0x05; 32]), 50), Ok(1));
+            assert_eq!(farm.set_pool(0, 20), Ok(()));
+            assert_eq!(farm.total_alloc_point(), 70);
+            assert_eq!(farm.pending_reward(0, AccountId::from([0x06; 32])), Ok(0));
+        }
+
+        #[ink::test]
+        fn pending_reward_accrues_per_second() {
+            let mut farm = FarmContract::new(AccountId::from([0x03; 32]), 1_000);
+            let accounts = default_accounts::<DefaultEnvironment>();
+            assert_eq!(farm.add_pool(AccountId::from([0x04; 32]), 100), Ok(0));
+            let mut pool = farm.pool_info(0).unwrap();
+            pool.total_staked = 100;
+            farm.farm.pools.insert(&0, &pool);
+            farm.farm.users.insert(
+                &(0, accounts.bob),
+                &UserInfo {
+                    amount: 100,
+                    reward_debt: 0,
+                },
+            );
+
+            set_block_timestamp::<DefaultEnvironment>(1_500);
+            assert_eq!(farm.pending_reward(0, accounts.bob), Ok(1_500));
+            assert_eq!(farm.update_pool(0), Ok(()));
+            assert_eq!(farm.pool_info(0).unwrap().last_reward_time, 1_500);
+            assert_eq!(farm.pending_reward(0, accounts.bob), Ok(1_500));
+        }
+
+        #[ink::test]
+        fn only_owner_can_configure() {
+            let mut farm = FarmContract::new(AccountId::from([0x03; 32]), 1_000);
+            let accounts = default_accounts::<DefaultEnvironment>();
+            set_caller::<DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                farm.add_pool(AccountId::from([0x04; 32]), 100),
+                Err(FarmError::OwnableError(OwnableError::CallerIsNotOwner))
+            );
+            assert_eq!(
+                farm.set_reward_per_second(0),
+                Err(FarmError::OwnableError(OwnableError::CallerIsNotOwner))
+            );
+        }
+    }
+}
@@ -0,0 +1,140 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+#![feature(min_specialization)]
+
+#[openbrush::contract]
+pub mod fee_converter {
+    use ink::codegen::{
+        EmitEvent,
+        Env,
+    };
+    use openbrush::{
+        contracts::{
+            ownable::*,
+            reentrancy_guard,
+        },
+        traits::Storage,
+    };
+    use uniswap_v2::{
+        impls::fee_converter::*,
+        traits::fee_converter::*,
+    };
+
+    #[ink(event)]
+    pub struct Convert {
+        #[ink(topic)]
+        pub sender: AccountId,
+        #[ink(topic)]
+        pub token_0: AccountId,
+        #[ink(topic)]
+        pub token_1: AccountId,
+        pub amount_0: Balance,
+        pub amount_1: Balance,
+        pub amount_out: Balance,
+    }
+
+    #[ink(event)]
+    pub struct BridgeSet {
+        #[ink(topic)]
+        pub token: AccountId,
+        #[ink(topic)]
+        pub bridge: AccountId,
+    }
+
+    #[ink(storage)]
+    #[derive(Default, Storage)]
+    pub struct FeeConverterContract {
+        #[storage_field]
+        ownable: ownable::Data,
+        #[storage_field]
+        guard: reentrancy_guard::Data,
+        #[storage_field]
+        fee_converter: data::Data,
+    }
+
+    impl Ownable for FeeConverterContract {}
+
+    impl FeeConverter for FeeConverterContract {}
+
+    impl fee_converter::Internal for FeeConverterContract {
+        fn _emit_convert_event(
+            &self,
+            sender: AccountId,
+            token_0: AccountId,
+            token_1: AccountId,
+            amount_0: Balance,
+            amount_1: Balance,
+            amount_out: Balance,
+        ) {
+            EmitEvent::<FeeConverterContract>::emit_event(
+                self.env(),
+                Convert {
+                    sender,
+                    token_0,
+                    token_1,
+                    amount_0,
+                    amount_1,
+                    amount_out,
+                },
+            )
+        }
+
+        fn _emit_bridge_set_event(&self, token: AccountId, bridge: AccountId) {
+            EmitEvent::<FeeConverterContract>::emit_event(self.env(), BridgeSet { token, bridge })
+        }
+    }
+
+    impl FeeConverterContract {
+        #[ink(constructor)]
+        pub fn new(
+            factory: AccountId,
+            payout_token: AccountId,
+            recipient: AccountId,
+            twap_period: Timestamp,
+            max_slippage_bps: u16,
+        ) -> Self {
+            let mut instance = Self::default();
+            let caller = instance.env().caller();
+            instance._init_with_owner(caller);
+            instance.fee_converter.factory = factory;
+            instance.fee_converter.payout_token = payout_token;
+            instance.fee_converter.recipient = recipient;
+            instance.fee_converter.twap_period = twap_period;
+            instance.fee_converter.max_slippage_bps = max_slippage_bps;
+            instance
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn set_bridge_works() {
+            let payout_token = AccountId::from([0x04; 32]);
+            let mut converter = FeeConverterContract::new(
+                AccountId::from([0x03; 32]),
+                payout_token,
+                AccountId::from([0x05; 32]),
+                3_600_000,
+                100,
+            );
+            let token = AccountId::from([0x06; 32]);
+            let bridge = AccountId::from([0x07; 32]);
+            assert_eq!(converter.bridge_for(token), payout_token);
+            assert_eq!(converter.set_bridge(token, bridge), Ok(()));
+            assert_eq!(converter.bridge_for(token), bridge);
+            assert_eq!(
+                converter.set_bridge(payout_token, bridge),
+                Err(FeeConverterError::InvalidBridge)
+            );
+            assert_eq!(
+                converter.set_bridge(token, token),
+                Err(FeeConverterError::InvalidBridge)
+            );
+            assert_eq!(
+                converter.set_max_slippage_bps(10_001),
+                Err(FeeConverterError::InvalidSlippage)
+            );
+        }
+    }
+}
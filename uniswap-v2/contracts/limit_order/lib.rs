@@ -0,0 +1,249 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+#![feature(min_specialization)]
+
+#[openbrush::contract]
+pub mod limit_order {
+    use ink::codegen::{
+        EmitEvent,
+        Env,
+    };
+    use openbrush::{
+        contracts::reentrancy_guard,
+        traits::Storage,
+    };
+    use uniswap_v2::{
+        impls::limit_order::*,
+        traits::limit_order::*,
+    };
+
+    #[ink(event)]
+    pub struct OrderPlaced {
+        #[ink(topic)]
+        pub order_id: OrderId,
+        #[ink(topic)]
+        pub owner: AccountId,
+        pub token_in: AccountId,
+        pub token_out: AccountId,
+        pub amount_in: Balance,
+        pub amount_out_min: Balance,
+        pub expiry: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct OrderFilled {
+        #[ink(topic)]
+        pub order_id: OrderId,
+        #[ink(topic)]
+        pub keeper: AccountId,
+        pub amount_in: Balance,
+        pub amount_out: Balance,
+        pub reward: Balance,
+    }
+
+    #[ink(event)]
+    pub struct OrderCancelled {
+        #[ink(topic)]
+        pub order_id: OrderId,
+        pub amount_in_refunded: Balance,
+    }
+
+    #[ink(storage)]
+    #[derive(Default, Storage)]
+    pub struct LimitOrderContract {
+        #[storage_field]
+        guard: reentrancy_guard::Data,
+        #[storage_field]
+        limit_order: data::Data,
+    }
+
+    impl LimitOrder for LimitOrderContract {}
+
+    impl limit_order::Internal for LimitOrderContract {
+        fn _emit_order_placed_event(
+            &self,
+            order_id: OrderId,
+            owner: AccountId,
+            token_in: AccountId,
+            token_out: AccountId,
+            amount_in: Balance,
+            amount_out_min: Balance,
+            expiry: Timestamp,
+        ) {
+            EmitEvent::<LimitOrderContract>::emit_event(
+                self.env(),
+                OrderPlaced {
+                    order_id,
+                    owner,
+                    token_in,
+                    token_out,
+                    amount_in,
+                    amount_out_min,
+                    expiry,
+                },
+            )
+        }
+
+        fn _emit_order_filled_event(
+            &self,
+            order_id: OrderId,
+            keeper: AccountId,
+            amount_in: Balance,
+            amount_out: Balance,
+            reward: Balance,
+        ) {
+            EmitEvent::<LimitOrderContract>::emit_event(
+                self.env(),
+                OrderFilled {
+                    order_id,
+                    keeper,
+                    amount_in,
+                    amount_out,
+                    reward,
+                },
+            )
+        }
+
+        fn _emit_order_cancelled_event(&self, order_id: OrderId, amount_in_refunded: Balance) {
+            EmitEvent::<LimitOrderContract>::emit_event(
+                self.env(),
+                OrderCancelled {
+                    order_id,
+                    amount_in_refunded,
+                },
+            )
+        }
+    }
+
+    impl LimitOrderContract {
+        #[ink(constructor)]
+        pub fn new(factory: AccountId) -> Self {
+            let mut instance = Self::default();
+            instance.limit_order.factory = factory;
+            instance
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::env::{
+            test::{
+                default_accounts,
+                set_block_timestamp,
+                set_caller,
+            },
+            DefaultEnvironment,
+        };
+        use uniswap_v2::impls::limit_order::limit_order::Internal;
+
+        fn order(owner: AccountId) -> Order {
+            Order {
+                owner,
+                token_in: AccountId::from([0x04; 32]),
+                token_out: AccountId::from([0x05; 32]),
+                amount_in: 1_000,
+                amount_out_min: 1_900,
+                amount_in_remaining: 1_000,
+                amount_out_filled: 0,
+                expiry: 10,
+            }
+        }
+
+        fn insert_order(limit_order: &mut LimitOrderContract, owner: AccountId) {
+            limit_order.limit_order.orders.insert(&0, &order(owner));
+        }
+
+        #[ink::test]
+        fn initialize_works() {
+            let factory = AccountId::from([0x03; 32]);
+            let limit_order = LimitOrderContract::new(factory);
+            assert_eq!(limit_order.factory(), factory);
+            assert_eq!(limit_order.next_order_id(), 0);
+        }
+
+        #[ink::test]
+        fn cancel_unknown_order_fails() {
+            let mut limit_order = LimitOrderContract::new(AccountId::from([0x03; 32]));
+            assert_eq!(
+                limit_order.cancel_order(0),
+                Err(LimitOrderError::OrderNotFound)
+            );
+        }
+
+        #[ink::test]
+        fn fill_expired_order_fails() {
+            let accounts = default_accounts::<DefaultEnvironment>();
+            let mut limit_order = LimitOrderContract::new(AccountId::from([0x03; 32]));
+            insert_order(&mut limit_order, accounts.bob);
+            set_block_timestamp::<DefaultEnvironment>(11);
+            assert_eq!(
+                limit_order.fill_order(0, 1_000),
+                Err(LimitOrderError::Expired)
+            );
+            assert_eq!(
+                limit_order.quote_fill(0, 1_000),
+                Err(LimitOrderError::Expired)
+            );
+        }
+
+        #[ink::test]
+        fn place_order_rejects_invalid_orders() {
+            let mut limit_order = LimitOrderContract::new(AccountId::from([0x03; 32]));
+            let (token_in, token_out) = (AccountId::from([0x04; 32]), AccountId::from([0x05; 32]));
+            assert_eq!(
+                limit_order.place_order(token_in, token_out, 0, 1_900, 10),
+                Err(LimitOrderError::InsufficientAmount)
+            );
+            set_block_timestamp::<DefaultEnvironment>(10);
+            assert_eq!(
+                limit_order.place_order(token_in, token_out, 1_000, 1_900, 10),
+                Err(LimitOrderError::InvalidExpiry)
+            );
+        }
+
+        #[ink::test]
+        fn order_fills_until_removed() {
+            let accounts = default_accounts::<DefaultEnvironment>();
+            let mut limit_order = LimitOrderContract::new(AccountId::from([0x03; 32]));
+            assert_eq!(limit_order._add_order(&order(accounts.bob)), Ok(0));
+            assert_eq!(limit_order._add_order(&order(accounts.bob)), Ok(1));
+            assert_eq!(limit_order.next_order_id(), 2);
+            assert_eq!(limit_order.orders_of(accounts.bob), vec![0, 1]);
+
+            let mut placed = limit_order.get_order(0).unwrap();
+            assert_eq!(limit_order._record_fill(0, &mut placed, 400, 780), Ok(()));
+            let partially_filled = Order {
+                amount_in_remaining: 600,
+                amount_out_filled: 780,
+                ..order(accounts.bob)
+            };
+            assert_eq!(limit_order.get_order(0), Some(partially_filled.clone()));
+            assert_eq!(limit_order.orders_of(accounts.bob), vec![0, 1]);
+
+            // Keepers cannot fill expired orders.
+            set_block_timestamp::<DefaultEnvironment>(11);
+            set_caller::<DefaultEnvironment>(accounts.charlie);
+            assert_eq!(
+                limit_order.fill_order(0, 600),
+                Err(LimitOrderError::Expired)
+            );
+            assert_eq!(limit_order.get_order(0), Some(partially_filled));
+
+            let mut placed = limit_order.get_order(0).unwrap();
+            assert_eq!(limit_order._record_fill(0, &mut placed, 600, 1_150), Ok(()));
+            assert_eq!(limit_order.get_order(0), None);
+            assert_eq!(limit_order.orders_of(accounts.bob), vec![1]);
+        }
+
+        #[ink::test]
+        fn cancel_order_of_other_owner_fails() {
+            let accounts = default_accounts::<DefaultEnvironment>();
+            let mut limit_order = LimitOrderContract::new(AccountId::from([0x03; 32]));
+            insert_order(&mut limit_order, accounts.bob);
+            assert_eq!(
+                limit_order.cancel_order(0),
+                Err(LimitOrderError::CallerIsNotOwner)
+            );
+        }
+    }
+}
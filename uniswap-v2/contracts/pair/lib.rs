@@ -20,8 +20,17 @@ pub mod pair {
     };
     use uniswap_v2::{
         ensure,
-        impls::pair::*,
-        traits::pair::*,
+        impls::{
+            lp_rewards::lp_rewards::{
+                self,
+                LpRewards,
+            },
+            pair::*,
+        },
+        traits::{
+            lp_rewards::*,
+            pair::*,
+        },
     };
 
     #[ink(event)]
@@ -78,6 +87,28 @@ pub mod pair {
         value: Balance,
     }
 
+    #[ink(event)]
+    pub struct RewardConfigSet {
+        #[ink(topic)]
+        pub reward_token: AccountId,
+        pub reward_distributor: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct RewardAdded {
+        pub amount: Balance,
+        pub duration: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct RewardPaid {
+        #[ink(topic)]
+        pub account: AccountId,
+        #[ink(topic)]
+        pub to: AccountId,
+        pub amount: Balance,
+    }
+
     #[ink(storage)]
     #[derive(Default, Storage)]
     pub struct PairContract {
@@ -89,6 +120,8 @@ pub mod pair {
         guard: reentrancy_guard::Data,
         #[storage_field]
         pair: data::Data,
+        #[storage_field]
+        lp_rewards: lp_rewards::data::Data,
     }
 
     impl PSP22 for PairContract {
@@ -116,6 +149,7 @@ pub mod pair {
     impl psp22::Internal for PairContract {
         // in uniswapv2 no check for zero account
         fn _mint_to(&mut self, account: AccountId, amount: Balance) -> Result<(), PSP22Error> {
+            lp_rewards::Internal::_update_reward(self, Some(account))?;
             let mut new_balance = self._balance_of(&account);
             new_balance += amount;
             self.psp22.balances.insert(&account, &new_balance);
@@ -125,6 +159,7 @@ pub mod pair {
         }
 
         fn _burn_from(&mut self, account: AccountId, amount: Balance) -> Result<(), PSP22Error> {
+            lp_rewards::Internal::_update_reward(self, Some(account))?;
             let mut from_balance = self._balance_of(&account);
 
             ensure!(from_balance >= amount, PSP22Error::InsufficientBalance);
@@ -158,6 +193,8 @@ pub mod pair {
 
             ensure!(from_balance >= amount, PSP22Error::InsufficientBalance);
 
+            lp_rewards::Internal::_update_reward(self, Some(from))?;
+            lp_rewards::Internal::_update_reward(self, Some(to))?;
             self.psp22.balances.insert(&from, &(from_balance - amount));
             let to_balance = self._balance_of(&to);
             self.psp22.balances.insert(&to, &(to_balance + amount));
@@ -243,6 +280,33 @@ pub mod pair {
 
     impl Pair for PairContract {}
 
+    impl LpRewards for PairContract {}
+
+    impl lp_rewards::Internal for PairContract {
+        fn _emit_reward_config_set_event(
+            &self,
+            reward_token: AccountId,
+            reward_distributor: AccountId,
+        ) {
+            self.env().emit_event(RewardConfigSet {
+                reward_token,
+                reward_distributor,
+            })
+        }
+
+        fn _emit_reward_added_event(&self, amount: Balance, duration: Timestamp) {
+            self.env().emit_event(RewardAdded { amount, duration })
+        }
+
+        fn _emit_reward_paid_event(&self, account: AccountId, to: AccountId, amount: Balance) {
+            self.env().emit_event(RewardPaid {
+                account,
+                to,
+                amount,
+            })
+        }
+    }
+
     impl PairContract {
         #[ink(constructor)]
         pub fn new() -> Self {
@@ -256,6 +320,7 @@ pub mod pair {
     #[cfg(test)]
     mod tests {
         use super::*;
+        use openbrush::traits::ZERO_ADDRESS;
 
         #[ink::test]
         fn initialize_works() {
@@ -263,6 +328,116 @@ pub mod pair {
             let token_0 = AccountId::from([0x03; 32]);
             let token_1 = AccountId::from([0x04; 32]);
             assert_eq!(pair.initialize(token_0, token_1), Ok(()));
+            assert_eq!(pair.price_accumulator_version(), PRICE_ACCUMULATOR_VERSION);
+        }
+
+        #[ink::test]
+        fn set_protocol_fee_mode_only_owner() {
+            let mut pair = PairContract::new();
+            assert_eq!(pair.protocol_fee_mode(), ProtocolFeeMode::MintLiquidity);
+            assert_eq!(
+                pair.set_protocol_fee_mode(ProtocolFeeMode::MintLiquidity),
+                Ok(())
+            );
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                pair.set_protocol_fee_mode(ProtocolFeeMode::Tokens),
+                Err(PairError::OwnableError(OwnableError::CallerIsNotOwner))
+            );
+            assert_eq!(pair.protocol_fees(), (0, 0));
+        }
+
+        #[ink::test]
+        fn set_dynamic_fee_config_works() {
+            let mut pair = PairContract::new();
+            assert_eq!(pair.get_fee(), 30);
+            let mut config = DynamicFeeConfig {
+                min_fee_bps: 100,
+                max_fee_bps: 10,
+                volatility_factor_bps: 1_000,
+                window: 60_000,
+            };
+            assert_eq!(
+                pair.set_dynamic_fee_config(Some(config)),
+                Err(PairError::InvalidFeeConfig)
+            );
+            config.max_fee_bps = 200;
+            assert_eq!(pair.set_dynamic_fee_config(Some(config)), Ok(()));
+            assert_eq!(pair.get_fee(), 100);
+            assert_eq!(pair.set_dynamic_fee_config(None), Ok(()));
+            assert_eq!(pair.get_fee(), 30);
+        }
+
+        #[ink::test]
+        fn set_reward_config_works() {
+            let mut pair = PairContract::new();
+            let token_0 = AccountId::from([0x03; 32]);
+            let token_1 = AccountId::from([0x04; 32]);
+            let reward_token = AccountId::from([0x05; 32]);
+            let distributor = AccountId::from([0x06; 32]);
+            assert_eq!(pair.initialize(token_0, token_1), Ok(()));
+            assert_eq!(
+                pair.set_reward_config(token_0, distributor),
+                Err(LpRewardsError::InvalidRewardToken)
+            );
+            assert_eq!(pair.set_reward_config(reward_token, distributor), Ok(()));
+            assert_eq!(pair.reward_token(), reward_token);
+            assert_eq!(pair.reward_distributor(), distributor);
+            assert_eq!(
+                pair.set_reward_config(AccountId::from([0x07; 32]), distributor),
+                Err(LpRewardsError::RewardTokenAlreadySet)
+            );
+        }
+
+        #[ink::test]
+        fn transfers_checkpoint_rewards() {
+            let mut pair = PairContract::new();
+            let holder = AccountId::from([0x08; 32]);
+            let receiver = AccountId::from([0x09; 32]);
+            assert_eq!(
+                pair.set_reward_config(AccountId::from([0x05; 32]), holder),
+                Ok(())
+            );
+            assert_eq!(pair._mint_to(holder, 1_000), Ok(()));
+            // Stream 1 reward token per millisecond without pulling it from the distributor.
+            pair.lp_rewards.reward_rate = (lp_rewards::REWARD_PRECISION as u64).into();
+            pair.lp_rewards.period_finish = 100;
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(10);
+
+            assert_eq!(
+                pair._transfer_from_to(holder, receiver, 500, Vec::new()),
+                Ok(())
+            );
+            assert_eq!(pair.earned(holder), Ok(10));
+            assert_eq!(pair.earned(receiver), Ok(0));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(20);
+            assert_eq!(pair.earned(holder), Ok(15));
+            assert_eq!(pair.earned(receiver), Ok(5));
+        }
+
+        #[ink::test]
+        fn locked_liquidity_earns_no_rewards() {
+            let mut pair = PairContract::new();
+            let holder = AccountId::from([0x08; 32]);
+            let contract = ink::env::account_id::<ink::env::DefaultEnvironment>();
+            assert_eq!(
+                pair.set_reward_config(AccountId::from([0x05; 32]), holder),
+                Ok(())
+            );
+            assert_eq!(pair._mint_to(ZERO_ADDRESS.into(), 1_000), Ok(()));
+            assert_eq!(pair._mint_to(contract, 1_000), Ok(()));
+            assert_eq!(pair._mint_to(holder, 1_000), Ok(()));
+            pair.lp_rewards.reward_rate = (lp_rewards::REWARD_PRECISION as u64).into();
+            pair.lp_rewards.period_finish = 100;
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(10);
+
+            assert_eq!(pair.earned(holder), Ok(10));
+            assert_eq!(pair.earned(ZERO_ADDRESS.into()), Ok(0));
+            assert_eq!(pair.earned(contract), Ok(0));
+            assert_eq!(pair._burn_from(contract, 1_000), Ok(()));
+            assert_eq!(pair.lp_rewards.rewards_owed, 10);
         }
     }
 }
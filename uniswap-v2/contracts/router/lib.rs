@@ -3,12 +3,67 @@
 
 #[openbrush::contract]
 pub mod router {
+    use ink::{
+        codegen::{
+            EmitEvent,
+            Env,
+        },
+        prelude::vec::Vec,
+    };
     use openbrush::traits::Storage;
     use uniswap_v2::{
         impls::router::*,
         traits::router::*,
     };
 
+    #[ink(event)]
+    pub struct Swapped {
+        #[ink(topic)]
+        pub sender: AccountId,
+        pub path: Vec<AccountId>,
+        pub amounts: Vec<Balance>,
+        #[ink(topic)]
+        pub to: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct LiquidityAdded {
+        #[ink(topic)]
+        pub sender: AccountId,
+        #[ink(topic)]
+        pub token_a: AccountId,
+        #[ink(topic)]
+        pub token_b: AccountId,
+        pub amount_a: Balance,
+        pub amount_b: Balance,
+        pub liquidity: Balance,
+        pub to: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct LiquidityRemoved {
+        #[ink(topic)]
+        pub sender: AccountId,
+        #[ink(topic)]
+        pub token_a: AccountId,
+        #[ink(topic)]
+        pub token_b: AccountId,
+        pub amount_a: Balance,
+        pub amount_b: Balance,
+        pub liquidity: Balance,
+        pub to: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct ReferralPaid {
+        #[ink(topic)]
+        pub referrer: AccountId,
+        #[ink(topic)]
+        pub trader: AccountId,
+        pub token: AccountId,
+        pub amount: Balance,
+    }
+
     #[ink(storage)]
     #[derive(Default, Storage)]
     pub struct RouterContract {
@@ -18,6 +73,92 @@ pub mod router {
 
     impl Router for RouterContract {}
 
+    impl router::Internal for RouterContract {
+        fn _emit_swapped_event(
+            &self,
+            sender: AccountId,
+            path: Vec<AccountId>,
+            amounts: Vec<Balance>,
+            to: AccountId,
+        ) {
+            EmitEvent::<RouterContract>::emit_event(
+                self.env(),
+                Swapped {
+                    sender,
+                    path,
+                    amounts,
+                    to,
+                },
+            )
+        }
+
+        fn _emit_liquidity_added_event(
+            &self,
+            sender: AccountId,
+            token_a: AccountId,
+            token_b: AccountId,
+            amount_a: Balance,
+            amount_b: Balance,
+            liquidity: Balance,
+            to: AccountId,
+        ) {
+            EmitEvent::<RouterContract>::emit_event(
+                self.env(),
+                LiquidityAdded {
+                    sender,
+                    token_a,
+                    token_b,
+                    amount_a,
+                    amount_b,
+                    liquidity,
+                    to,
+                },
+            )
+        }
+
+        fn _emit_liquidity_removed_event(
+            &self,
+            sender: AccountId,
+            token_a: AccountId,
+            token_b: AccountId,
+            amount_a: Balance,
+            amount_b: Balance,
+            liquidity: Balance,
+            to: AccountId,
+        ) {
+            EmitEvent::<RouterContract>::emit_event(
+                self.env(),
+                LiquidityRemoved {
+                    sender,
+                    token_a,
+                    token_b,
+                    amount_a,
+                    amount_b,
+                    liquidity,
+                    to,
+                },
+            )
+        }
+
+        fn _emit_referral_paid_event(
+            &self,
+            referrer: AccountId,
+            trader: AccountId,
+            token: AccountId,
+            amount: Balance,
+        ) {
+            EmitEvent::<RouterContract>::emit_event(
+                self.env(),
+                ReferralPaid {
+                    referrer,
+                    trader,
+                    token,
+                    amount,
+                },
+            )
+        }
+    }
+
     impl RouterContract {
         #[ink(constructor)]
         pub fn new(factory: AccountId, wnative: AccountId) -> Self {
@@ -40,5 +181,94 @@ pub mod router {
             assert_eq!(router.factory(), factory);
             assert_eq!(router.wnative(), wnative);
         }
+
+        #[ink::test]
+        fn referral_fee_above_max_fails() {
+            let mut router =
+                RouterContract::new(AccountId::from([0x03; 32]), AccountId::from([0x04; 32]));
+            let path = vec![AccountId::from([0x05; 32]), AccountId::from([0x06; 32])];
+            let referral = Referral {
+                referrer: AccountId::from([0x07; 32]),
+                fee_bps: 10,
+                from_output: false,
+            };
+            assert_eq!(router.max_referral_fee_bps(), 0);
+            assert_eq!(
+                router.swap_exact_tokens_for_tokens_with_referral(
+                    100,
+                    0,
+                    path,
+                    AccountId::from([0x08; 32]),
+                    u64::MAX,
+                    Some(referral),
+                ),
+                Err(RouterError::InvalidReferralFee)
+            );
+        }
+
+        #[ink::test]
+        fn native_referral_fee_above_max_fails() {
+            let wnative = AccountId::from([0x04; 32]);
+            let mut router = RouterContract::new(AccountId::from([0x03; 32]), wnative);
+            let path = vec![AccountId::from([0x05; 32]), wnative];
+            let referral = Referral {
+                referrer: AccountId::from([0x07; 32]),
+                fee_bps: 10,
+                from_output: true,
+            };
+            assert_eq!(
+                router.swap_exact_tokens_for_native_with_referral(
+                    100,
+                    0,
+                    path,
+                    AccountId::from([0x08; 32]),
+                    u64::MAX,
+                    Some(referral),
+                ),
+                Err(RouterError::InvalidReferralFee)
+            );
+        }
+
+        #[ink::test]
+        fn fee_tier_stake_counts_from_next_block() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut router =
+                RouterContract::new(AccountId::from([0x03; 32]), AccountId::from([0x04; 32]));
+            let token = AccountId::from([0x05; 32]);
+            assert_eq!(
+                router.stake_fee_tier_token(100),
+                Err(RouterError::ZeroAddress)
+            );
+            assert_eq!(
+                router.unstake_fee_tier_token(100),
+                Err(RouterError::InsufficientStake)
+            );
+
+            router.router.fee_tier_token = token;
+            router.router.fee_tiers = vec![FeeTier {
+                min_balance: 100,
+                discount_bps: 5_000,
+            }];
+            let stake = FeeTierStake {
+                token,
+                amount: 100,
+                staked_at: 0,
+            };
+            router
+                .router
+                .fee_tier_stakes
+                .insert(&accounts.alice, &stake);
+            assert_eq!(router.fee_discount_of(accounts.alice), 0);
+            assert_eq!(
+                router.unstake_fee_tier_token(101),
+                Err(RouterError::InsufficientStake)
+            );
+
+            router.router.fee_tier_token = AccountId::from([0x06; 32]);
+            assert_eq!(
+                router.stake_fee_tier_token(100),
+                Err(RouterError::StakedTokenChanged)
+            );
+        }
     }
 }
@@ -0,0 +1,275 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+#![feature(min_specialization)]
+
+#[openbrush::contract]
+pub mod twamm {
+    use ink::codegen::{
+        EmitEvent,
+        Env,
+    };
+    use openbrush::{
+        contracts::reentrancy_guard,
+        traits::Storage,
+    };
+    use uniswap_v2::{
+        ensure,
+        helpers::{
+            math::BPS,
+            oracle::update_observation,
+        },
+        impls::twamm::*,
+        traits::{
+            pair::PairRef,
+            twamm::*,
+        },
+    };
+
+    #[ink(event)]
+    pub struct LongTermOrderPlaced {
+        #[ink(topic)]
+        pub order_id: OrderId,
+        #[ink(topic)]
+        pub owner: AccountId,
+        pub sell_token_0: bool,
+        pub sell_rate: Balance,
+        pub expiry: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct LongTermOrderCancelled {
+        #[ink(topic)]
+        pub order_id: OrderId,
+        pub unsold_amount: Balance,
+        pub proceeds: Balance,
+    }
+
+    #[ink(event)]
+    pub struct ProceedsWithdrawn {
+        #[ink(topic)]
+        pub order_id: OrderId,
+        pub proceeds: Balance,
+    }
+
+    #[ink(event)]
+    pub struct VirtualOrdersExecuted {
+        pub amount_0_in: Balance,
+        pub amount_1_in: Balance,
+        pub amount_0_out: Balance,
+        pub amount_1_out: Balance,
+    }
+
+    #[ink(storage)]
+    #[derive(Default, Storage)]
+    pub struct TwammContract {
+        #[storage_field]
+        guard: reentrancy_guard::Data,
+        #[storage_field]
+        twamm: data::Data,
+    }
+
+    impl Twamm for TwammContract {}
+
+    impl twamm::Internal for TwammContract {
+        fn _emit_long_term_order_placed_event(
+            &self,
+            order_id: OrderId,
+            owner: AccountId,
+            sell_token_0: bool,
+            sell_rate: Balance,
+            expiry: Timestamp,
+        ) {
+            EmitEvent::<TwammContract>::emit_event(
+                self.env(),
+                LongTermOrderPlaced {
+                    order_id,
+                    owner,
+                    sell_token_0,
+                    sell_rate,
+                    expiry,
+                },
+            )
+        }
+
+        fn _emit_long_term_order_cancelled_event(
+            &self,
+            order_id: OrderId,
+            unsold_amount: Balance,
+            proceeds: Balance,
+        ) {
+            EmitEvent::<TwammContract>::emit_event(
+                self.env(),
+                LongTermOrderCancelled {
+                    order_id,
+                    unsold_amount,
+                    proceeds,
+                },
+            )
+        }
+
+        fn _emit_proceeds_withdrawn_event(&self, order_id: OrderId, proceeds: Balance) {
+            EmitEvent::<TwammContract>::emit_event(
+                self.env(),
+                ProceedsWithdrawn { order_id, proceeds },
+            )
+        }
+
+        fn _emit_virtual_orders_executed_event(
+            &self,
+            amount_0_in: Balance,
+            amount_1_in: Balance,
+            amount_0_out: Balance,
+            amount_1_out: Balance,
+        ) {
+            EmitEvent::<TwammContract>::emit_event(
+                self.env(),
+                VirtualOrdersExecuted {
+                    amount_0_in,
+                    amount_1_in,
+                    amount_0_out,
+                    amount_1_out,
+                },
+            )
+        }
+    }
+
+    impl TwammContract {
+        #[ink(constructor)]
+        pub fn new(
+            pair: AccountId,
+            order_interval: Timestamp,
+            twap_period: Timestamp,
+            max_slippage_bps: u16,
+        ) -> Result<Self, TwammError> {
+            ensure!(order_interval > 0, TwammError::InvalidInterval);
+            ensure!(max_slippage_bps <= BPS, TwammError::InvalidSlippage);
+            let mut instance = Self::default();
+            let now = instance.env().block_timestamp();
+            instance.twamm.pair = pair;
+            instance.twamm.token_0 = PairRef::get_token_0(&pair);
+            instance.twamm.token_1 = PairRef::get_token_1(&pair);
+            instance.twamm.order_interval = order_interval;
+            instance.twamm.twap_period = twap_period;
+            instance.twamm.max_slippage_bps = max_slippage_bps;
+            instance.twamm.last_virtual_order_timestamp = now;
+            instance.twamm.observation = update_observation(&pair, None, twap_period, now)?;
+            Ok(instance)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::env::{
+            test::{
+                default_accounts,
+                set_block_timestamp,
+            },
+            DefaultEnvironment,
+        };
+
+        const INTERVAL: Timestamp = 100;
+
+        fn twamm() -> TwammContract {
+            let mut twamm = TwammContract::default();
+            twamm.twamm.pair = AccountId::from([0x03; 32]);
+            twamm.twamm.token_0 = AccountId::from([0x04; 32]);
+            twamm.twamm.token_1 = AccountId::from([0x05; 32]);
+            twamm.twamm.order_interval = INTERVAL;
+            twamm
+        }
+
+        fn insert_order(twamm: &mut TwammContract, owner: AccountId) {
+            twamm.twamm.orders.insert(
+                &0,
+                &LongTermOrder {
+                    owner,
+                    sell_token_0: true,
+                    sell_rate: 10,
+                    expiry: 2 * INTERVAL,
+                    reward_factor: Default::default(),
+                },
+            );
+            twamm.twamm.orders_of.insert(&owner, &vec![0]);
+        }
+
+        #[ink::test]
+        fn new_rejects_invalid_config() {
+            let pair = AccountId::from([0x03; 32]);
+            assert_eq!(
+                TwammContract::new(pair, 0, 3_600_000, 100).err(),
+                Some(TwammError::InvalidInterval)
+            );
+            assert_eq!(
+                TwammContract::new(pair, INTERVAL, 3_600_000, BPS + 1).err(),
+                Some(TwammError::InvalidSlippage)
+            );
+        }
+
+        #[ink::test]
+        fn execute_without_orders_skips_to_now() {
+            let mut twamm = twamm();
+            set_block_timestamp::<DefaultEnvironment>(1_000 * INTERVAL);
+            assert_eq!(twamm.execute_virtual_orders(), Ok(()));
+            assert_eq!(twamm.last_virtual_order_timestamp(), 1_000 * INTERVAL);
+            assert_eq!(
+                twamm.execute_virtual_orders_until(2_000 * INTERVAL),
+                Ok(1_000 * INTERVAL)
+            );
+        }
+
+        #[ink::test]
+        fn place_order_checks_token_and_amount() {
+            let mut twamm = twamm();
+            assert_eq!(
+                twamm.place_long_term_order(AccountId::from([0x06; 32]), 1_000, 1),
+                Err(TwammError::InvalidToken)
+            );
+            // Sold over 2 intervals, less than 1 per millisecond.
+            assert_eq!(
+                twamm.place_long_term_order(
+                    AccountId::from([0x04; 32]),
+                    (2 * INTERVAL - 1).into(),
+                    1
+                ),
+                Err(TwammError::InsufficientAmount)
+            );
+        }
+
+        #[ink::test]
+        fn cancel_order_checks_owner_and_expiry() {
+            let accounts = default_accounts::<DefaultEnvironment>();
+            let mut twamm = twamm();
+            assert_eq!(
+                twamm.cancel_long_term_order(0),
+                Err(TwammError::OrderNotFound)
+            );
+            insert_order(&mut twamm, accounts.bob);
+            assert_eq!(
+                twamm.cancel_long_term_order(0),
+                Err(TwammError::CallerIsNotOwner)
+            );
+            insert_order(&mut twamm, accounts.alice);
+            set_block_timestamp::<DefaultEnvironment>(2 * INTERVAL);
+            assert_eq!(
+                twamm.cancel_long_term_order(0),
+                Err(TwammError::OrderExpired)
+            );
+        }
+
+        #[ink::test]
+        fn withdraw_expired_order_removes_it() {
+            let accounts = default_accounts::<DefaultEnvironment>();
+            let mut twamm = twamm();
+            insert_order(&mut twamm, accounts.bob);
+            assert_eq!(
+                twamm.withdraw_proceeds(0),
+                Err(TwammError::CallerIsNotOwner)
+            );
+            insert_order(&mut twamm, accounts.alice);
+            set_block_timestamp::<DefaultEnvironment>(2 * INTERVAL);
+            assert_eq!(twamm.withdraw_proceeds(0), Ok(0));
+            assert_eq!(twamm.get_order(0), None);
+            assert_eq!(twamm.orders_of(accounts.alice), vec![]);
+        }
+    }
+}
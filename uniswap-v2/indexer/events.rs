@@ -0,0 +1,401 @@
+use crate::{
+    metadata::EventSpec,
+    AccountId,
+    Balance,
+    PoolTypeId,
+};
+use scale::DecodeAll;
+use serde::{
+    de::Error as _,
+    Deserialize,
+    Deserializer,
+};
+
+/// Event as emitted by a contract, the topics are only counted.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct RawEvent {
+    #[serde(deserialize_with = "account_from_hex")]
+    pub contract: AccountId,
+    #[serde(deserialize_with = "bytes_from_hex")]
+    pub data: Vec<u8>,
+    #[serde(deserialize_with = "topics_from_hex")]
+    pub topics: Vec<[u8; 32]>,
+}
+
+/// Event of the factory or a pair. Reward events of the pair are not decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DexEvent {
+    PairCreated {
+        token_0: AccountId,
+        token_1: AccountId,
+        pair: AccountId,
+        pair_len: u64,
+        pool_type: PoolTypeId,
+    },
+    Mint {
+        sender: AccountId,
+        amount_0: Balance,
+        amount_1: Balance,
+    },
+    Burn {
+        sender: AccountId,
+        amount_0: Balance,
+        amount_1: Balance,
+        to: AccountId,
+    },
+    Swap {
+        sender: AccountId,
+        amount_0_in: Balance,
+        amount_1_in: Balance,
+        amount_0_out: Balance,
+        amount_1_out: Balance,
+        to: AccountId,
+    },
+    Sync {
+        reserve_0: Balance,
+        reserve_1: Balance,
+    },
+    Transfer {
+        from: Option<AccountId>,
+        to: Option<AccountId>,
+        value: Balance,
+    },
+    Approval {
+        owner: AccountId,
+        spender: AccountId,
+        value: Balance,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The metadata lists an event known by the decoder with other arguments.
+    MetadataMismatch(String),
+    EmptyData,
+    UnknownEventIndex(u8),
+    /// The event has another number of topics than its metadata implies.
+    TopicsMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    Scale(String),
+}
+
+impl From<scale::Error> for DecodeError {
+    fn from(error: scale::Error) -> Self {
+        DecodeError::Scale(error.to_string())
+    }
+}
+
+/// Argument labels of the decoded events, in the order of their fields.
+const KNOWN_EVENTS: [(&str, &[&str]); 7] = [
+    (
+        "PairCreated",
+        &["token_0", "token_1", "pair", "pair_len", "pool_type"],
+    ),
+    ("Mint", &["sender", "amount_0", "amount_1"]),
+    ("Burn", &["sender", "amount_0", "amount_1", "to"]),
+    (
+        "Swap",
+        &[
+            "sender",
+            "amount_0_in",
+            "amount_1_in",
+            "amount_0_out",
+            "amount_1_out",
+            "to",
+        ],
+    ),
+    ("Sync", &["reserve_0", "reserve_1"]),
+    ("Transfer", &["from", "to", "value"]),
+    ("Approval", &["owner", "spender", "value"]),
+];
+
+/// Decodes the events of one contract.
+///
+/// ink! encodes an event as the index of the event in the contract followed by all its
+/// arguments, and publishes the event signature and each indexed argument as topics.
+#[derive(Debug, Clone)]
+pub struct EventDecoder {
+    events: Vec<EventSpec>,
+}
+
+impl EventDecoder {
+    /// Returns a decoder for the events listed in the metadata of a contract.
+    pub fn new(events: Vec<EventSpec>) -> Result<Self, DecodeError> {
+        for event in &events {
+            if let Some((_, labels)) = KNOWN_EVENTS.iter().find(|(label, _)| *label == event.label)
+            {
+                if !event
+                    .args
+                    .iter()
+                    .map(|arg| arg.label.as_str())
+                    .eq(labels.iter().copied())
+                {
+                    return Err(DecodeError::MetadataMismatch(event.label.clone()))
+                }
+            }
+        }
+        Ok(EventDecoder { events })
+    }
+
+    /// Returns the decoded event, `None` for an event of the contract which is not decoded.
+    pub fn decode(&self, event: &RawEvent) -> Result<Option<DexEvent>, DecodeError> {
+        let (index, mut data) = event.data.split_first().ok_or(DecodeError::EmptyData)?;
+        let spec = self
+            .events
+            .get(usize::from(*index))
+            .ok_or(DecodeError::UnknownEventIndex(*index))?;
+        let expected = 1 + spec.args.iter().filter(|arg| arg.indexed).count();
+        if event.topics.len() != expected {
+            return Err(DecodeError::TopicsMismatch {
+                expected,
+                actual: event.topics.len(),
+            })
+        }
+        let data = &mut data;
+        let event = match spec.label.as_str() {
+            "PairCreated" => {
+                let (token_0, token_1, pair, pair_len, pool_type) = DecodeAll::decode_all(data)?;
+                DexEvent::PairCreated {
+                    token_0,
+                    token_1,
+                    pair,
+                    pair_len,
+                    pool_type,
+                }
+            }
+            "Mint" => {
+                let (sender, amount_0, amount_1) = DecodeAll::decode_all(data)?;
+                DexEvent::Mint {
+                    sender,
+                    amount_0,
+                    amount_1,
+                }
+            }
+            "Burn" => {
+                let (sender, amount_0, amount_1, to) = DecodeAll::decode_all(data)?;
+                DexEvent::Burn {
+                    sender,
+                    amount_0,
+                    amount_1,
+                    to,
+                }
+            }
+            "Swap" => {
+                let (sender, amount_0_in, amount_1_in, amount_0_out, amount_1_out, to) =
+                    DecodeAll::decode_all(data)?;
+                DexEvent::Swap {
+                    sender,
+                    amount_0_in,
+                    amount_1_in,
+                    amount_0_out,
+                    amount_1_out,
+                    to,
+                }
+            }
+            "Sync" => {
+                let (reserve_0, reserve_1) = DecodeAll::decode_all(data)?;
+                DexEvent::Sync {
+                    reserve_0,
+                    reserve_1,
+                }
+            }
+            "Transfer" => {
+                let (from, to, value) = DecodeAll::decode_all(data)?;
+                DexEvent::Transfer { from, to, value }
+            }
+            "Approval" => {
+                let (owner, spender, value) = DecodeAll::decode_all(data)?;
+                DexEvent::Approval {
+                    owner,
+                    spender,
+                    value,
+                }
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(event))
+    }
+}
+
+fn bytes_from_hex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    hex::decode(hex.trim_start_matches("0x")).map_err(D::Error::custom)
+}
+
+fn account_from_hex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+    bytes_from_hex(deserializer)?
+        .try_into()
+        .map_err(|_| D::Error::custom("expected 32 bytes"))
+}
+
+fn topics_from_hex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<[u8; 32]>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .into_iter()
+        .map(|hex| {
+            hex::decode(hex.trim_start_matches("0x"))
+                .map_err(D::Error::custom)?
+                .try_into()
+                .map_err(|_| D::Error::custom("expected 32 bytes"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::events_from_metadata;
+
+    const FACTORY: AccountId = [0xf0; 32];
+    const PAIR: AccountId = [0x70; 32];
+    const ROUTER: AccountId = [0x50; 32];
+    const USER_1: AccountId = [0x01; 32];
+    const USER_2: AccountId = [0x02; 32];
+
+    fn pair_decoder() -> EventDecoder {
+        EventDecoder::new(
+            events_from_metadata(include_str!("fixtures/pair_metadata.json")).unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn decodes_recorded_events() {
+        let factory_decoder = EventDecoder::new(
+            events_from_metadata(include_str!("fixtures/factory_metadata.json")).unwrap(),
+        )
+        .unwrap();
+        let events: Vec<RawEvent> =
+            serde_json::from_str(include_str!("fixtures/events.json")).unwrap();
+        // Events of other contracts are skipped by the indexer.
+        let decoded: Vec<_> = events
+            .iter()
+            .filter_map(|event| {
+                match event.contract {
+                    FACTORY => Some(factory_decoder.decode(event)),
+                    PAIR => Some(pair_decoder().decode(event)),
+                    _ => None,
+                }
+            })
+            .map(|event| event.unwrap().unwrap())
+            .collect();
+        assert_eq!(
+            decoded,
+            vec![
+                DexEvent::PairCreated {
+                    token_0: [0xa0; 32],
+                    token_1: [0xb0; 32],
+                    pair: PAIR,
+                    pair_len: 1,
+                    pool_type: 0,
+                },
+                DexEvent::Transfer {
+                    from: None,
+                    to: Some([0; 32]),
+                    value: 1_000,
+                },
+                DexEvent::Transfer {
+                    from: None,
+                    to: Some(USER_1),
+                    value: 1_999_000,
+                },
+                DexEvent::Sync {
+                    reserve_0: 1_000_000,
+                    reserve_1: 4_000_000,
+                },
+                DexEvent::Mint {
+                    sender: ROUTER,
+                    amount_0: 1_000_000,
+                    amount_1: 4_000_000,
+                },
+                DexEvent::Sync {
+                    reserve_0: 1_010_000,
+                    reserve_1: 3_960_514,
+                },
+                DexEvent::Swap {
+                    sender: ROUTER,
+                    amount_0_in: 10_000,
+                    amount_1_in: 0,
+                    amount_0_out: 0,
+                    amount_1_out: 39_486,
+                    to: USER_2,
+                },
+                DexEvent::Transfer {
+                    from: Some(USER_1),
+                    to: Some(USER_2),
+                    value: 500_000,
+                },
+                DexEvent::Approval {
+                    owner: USER_2,
+                    spender: ROUTER,
+                    value: 500_000,
+                },
+                DexEvent::Transfer {
+                    from: Some(USER_2),
+                    to: Some(PAIR),
+                    value: 500_000,
+                },
+                DexEvent::Transfer {
+                    from: Some(PAIR),
+                    to: None,
+                    value: 500_000,
+                },
+                DexEvent::Sync {
+                    reserve_0: 757_500,
+                    reserve_1: 2_970_386,
+                },
+                DexEvent::Burn {
+                    sender: ROUTER,
+                    amount_0: 252_500,
+                    amount_1: 990_128,
+                    to: USER_2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_events() {
+        let decoder = pair_decoder();
+        let sync = |data: Vec<u8>, topics: usize| {
+            RawEvent {
+                contract: [0x70; 32],
+                data,
+                topics: vec![[0; 32]; topics],
+            }
+        };
+        let mut data = vec![3];
+        data.extend_from_slice(&[0; 32]);
+        assert_eq!(
+            decoder.decode(&sync(data.clone(), 1)).map(|e| e.is_some()),
+            Ok(true)
+        );
+        assert_eq!(
+            decoder.decode(&sync(data.clone(), 2)),
+            Err(DecodeError::TopicsMismatch {
+                expected: 1,
+                actual: 2,
+            })
+        );
+        data.push(0);
+        assert!(matches!(
+            decoder.decode(&sync(data, 1)),
+            Err(DecodeError::Scale(_))
+        ));
+        assert_eq!(
+            decoder.decode(&sync(vec![9], 1)),
+            Err(DecodeError::UnknownEventIndex(9))
+        );
+    }
+
+    #[test]
+    fn rejects_mismatched_metadata() {
+        let mut events = events_from_metadata(include_str!("fixtures/pair_metadata.json")).unwrap();
+        events[3].args.swap(0, 1);
+        assert_eq!(
+            EventDecoder::new(events).err(),
+            Some(DecodeError::MetadataMismatch("Sync".into()))
+        );
+    }
+}
@@ -0,0 +1,274 @@
+use crate::{
+    events::{
+        DecodeError,
+        DexEvent,
+        EventDecoder,
+        RawEvent,
+    },
+    store::{
+        PairState,
+        Store,
+    },
+    AccountId,
+    Balance,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndexerError<E> {
+    Decode(DecodeError),
+    Store(E),
+    /// A transfer of more liquidity than its sender holds, the log is incomplete.
+    InsufficientLpBalance {
+        pair: AccountId,
+        holder: AccountId,
+    },
+}
+
+impl<E> From<DecodeError> for IndexerError<E> {
+    fn from(error: DecodeError) -> Self {
+        IndexerError::Decode(error)
+    }
+}
+
+/// Folds the events of a factory and of the pairs it created into a [`Store`].
+///
+/// Events must be processed in the order they were emitted, from the creation of the
+/// factory. Events of other contracts are skipped.
+pub struct Indexer<S> {
+    factory: AccountId,
+    factory_decoder: EventDecoder,
+    pair_decoder: EventDecoder,
+    store: S,
+}
+
+impl<S: Store> Indexer<S> {
+    pub fn new(
+        factory: AccountId,
+        factory_decoder: EventDecoder,
+        pair_decoder: EventDecoder,
+        store: S,
+    ) -> Self {
+        Indexer {
+            factory,
+            factory_decoder,
+            pair_decoder,
+            store,
+        }
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    pub fn into_store(self) -> S {
+        self.store
+    }
+
+    /// Decodes `event` and applies it to the index, returns the decoded event if any.
+    pub fn process(
+        &mut self,
+        event: &RawEvent,
+    ) -> Result<Option<DexEvent>, IndexerError<S::Error>> {
+        if event.contract == self.factory {
+            let decoded = self.factory_decoder.decode(event)?;
+            if let Some(DexEvent::PairCreated {
+                token_0,
+                token_1,
+                pair,
+                pool_type,
+                ..
+            }) = decoded
+            {
+                self.store
+                    .put_pair(&PairState {
+                        pair,
+                        token_0,
+                        token_1,
+                        pool_type,
+                        ..Default::default()
+                    })
+                    .map_err(IndexerError::Store)?;
+            }
+            return Ok(decoded)
+        }
+
+        let mut state = match self
+            .store
+            .pair(&event.contract)
+            .map_err(IndexerError::Store)?
+        {
+            Some(state) => state,
+            None => return Ok(None),
+        };
+        let decoded = self.pair_decoder.decode(event)?;
+        match decoded {
+            Some(DexEvent::Sync {
+                reserve_0,
+                reserve_1,
+            }) => {
+                state.reserve_0 = reserve_0;
+                state.reserve_1 = reserve_1;
+            }
+            Some(DexEvent::Swap {
+                amount_0_in,
+                amount_1_in,
+                amount_0_out,
+                amount_1_out,
+                ..
+            }) => {
+                state.volume_0 = state
+                    .volume_0
+                    .saturating_add(amount_0_in)
+                    .saturating_add(amount_0_out);
+                state.volume_1 = state
+                    .volume_1
+                    .saturating_add(amount_1_in)
+                    .saturating_add(amount_1_out);
+                state.swap_count += 1;
+            }
+            Some(DexEvent::Transfer { from, to, value }) => {
+                match from {
+                    Some(from) => self.debit(&state.pair, &from, value)?,
+                    None => state.total_supply = state.total_supply.saturating_add(value),
+                }
+                match to {
+                    Some(to) => self.credit(&state.pair, &to, value)?,
+                    None => state.total_supply = state.total_supply.saturating_sub(value),
+                }
+            }
+            _ => return Ok(decoded),
+        }
+        self.store.put_pair(&state).map_err(IndexerError::Store)?;
+        Ok(decoded)
+    }
+
+    fn credit(
+        &mut self,
+        pair: &AccountId,
+        holder: &AccountId,
+        value: Balance,
+    ) -> Result<(), IndexerError<S::Error>> {
+        let balance = self
+            .store
+            .lp_balance(pair, holder)
+            .map_err(IndexerError::Store)?;
+        self.store
+            .put_lp_balance(pair, holder, balance.saturating_add(value))
+            .map_err(IndexerError::Store)
+    }
+
+    fn debit(
+        &mut self,
+        pair: &AccountId,
+        holder: &AccountId,
+        value: Balance,
+    ) -> Result<(), IndexerError<S::Error>> {
+        let balance = self
+            .store
+            .lp_balance(pair, holder)
+            .map_err(IndexerError::Store)?
+            .checked_sub(value)
+            .ok_or(IndexerError::InsufficientLpBalance {
+                pair: *pair,
+                holder: *holder,
+            })?;
+        self.store
+            .put_lp_balance(pair, holder, balance)
+            .map_err(IndexerError::Store)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        metadata::events_from_metadata,
+        store::MemoryStore,
+    };
+
+    const FACTORY: AccountId = [0xf0; 32];
+    const PAIR: AccountId = [0x70; 32];
+    const USER_1: AccountId = [0x01; 32];
+
+    fn index<S: Store>(store: S) -> Result<Indexer<S>, IndexerError<S::Error>> {
+        let decoder = |json| EventDecoder::new(events_from_metadata(json).unwrap()).unwrap();
+        let mut indexer = Indexer::new(
+            FACTORY,
+            decoder(include_str!("fixtures/factory_metadata.json")),
+            decoder(include_str!("fixtures/pair_metadata.json")),
+            store,
+        );
+        let events: Vec<RawEvent> =
+            serde_json::from_str(include_str!("fixtures/events.json")).unwrap();
+        for event in &events {
+            indexer.process(event)?;
+        }
+        Ok(indexer)
+    }
+
+    fn assert_indexed<S: Store>(store: &S)
+    where
+        S::Error: core::fmt::Debug,
+    {
+        assert_eq!(
+            store.pairs().unwrap(),
+            vec![PairState {
+                pair: PAIR,
+                token_0: [0xa0; 32],
+                token_1: [0xb0; 32],
+                pool_type: 0,
+                reserve_0: 757_500,
+                reserve_1: 2_970_386,
+                total_supply: 1_500_000,
+                volume_0: 10_000,
+                volume_1: 39_486,
+                swap_count: 1,
+            }]
+        );
+        // Minimum liquidity is held by the zero address.
+        assert_eq!(
+            store.lp_holders(&PAIR).unwrap(),
+            vec![([0; 32], 1_000), (USER_1, 1_499_000)]
+        );
+    }
+
+    #[test]
+    fn indexes_recorded_events() {
+        let indexer = index(MemoryStore::default()).unwrap();
+        assert_indexed(indexer.store());
+    }
+
+    #[test]
+    fn incomplete_log_fails() {
+        let decoder = |json| EventDecoder::new(events_from_metadata(json).unwrap()).unwrap();
+        let mut indexer = Indexer::new(
+            FACTORY,
+            decoder(include_str!("fixtures/factory_metadata.json")),
+            decoder(include_str!("fixtures/pair_metadata.json")),
+            MemoryStore::default(),
+        );
+        let events: Vec<RawEvent> =
+            serde_json::from_str(include_str!("fixtures/events.json")).unwrap();
+        indexer.process(&events[0]).unwrap();
+        // The LP transfer from user 1 without the mint before it.
+        assert_eq!(
+            indexer.process(&events[7]),
+            Err(IndexerError::InsufficientLpBalance {
+                pair: PAIR,
+                holder: USER_1,
+            })
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_store_persists_index() {
+        use crate::sqlite::SqliteStore;
+
+        let path = std::env::temp_dir().join(format!("dex_indexer_{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        index(SqliteStore::open(&path).unwrap()).unwrap();
+        assert_indexed(&SqliteStore::open(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,16 @@
+//! Decoding of the events of the factory and pair contracts and an index of the pairs
+//! folded from them.
+//!
+//! [`events::EventDecoder`] decodes raw contract events with the event list of the ink!
+//! metadata of a contract, [`index::Indexer`] folds them into a [`store::Store`].
+
+pub mod events;
+pub mod index;
+pub mod metadata;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod store;
+
+pub type AccountId = [u8; 32];
+pub type Balance = u128;
+pub type PoolTypeId = u32;
@@ -0,0 +1,32 @@
+use serde::Deserialize;
+
+/// Argument of an event in the metadata of a contract.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct EventArg {
+    pub label: String,
+    /// Whether the argument is published as a topic.
+    pub indexed: bool,
+}
+
+/// Event of a contract, in the order of the metadata which is its index in the event data.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct EventSpec {
+    pub label: String,
+    pub args: Vec<EventArg>,
+}
+
+#[derive(Deserialize)]
+struct Spec {
+    events: Vec<EventSpec>,
+}
+
+#[derive(Deserialize)]
+struct Metadata {
+    spec: Spec,
+}
+
+/// Returns the events listed in the ink! 4 metadata of a contract, as generated by
+/// `cargo contract build`.
+pub fn events_from_metadata(json: &str) -> Result<Vec<EventSpec>, serde_json::Error> {
+    Ok(serde_json::from_str::<Metadata>(json)?.spec.events)
+}
@@ -0,0 +1,175 @@
+use crate::{
+    store::{
+        PairState,
+        Store,
+    },
+    AccountId,
+    Balance,
+};
+use rusqlite::{
+    params,
+    Connection,
+    OptionalExtension,
+    Row,
+};
+use std::path::Path;
+
+/// Store keeping the index in a SQLite database. Balances are stored as decimal text, which
+/// holds any `u128`.
+pub struct SqliteStore {
+    connection: Connection,
+}
+
+impl SqliteStore {
+    /// Opens or creates the database at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> rusqlite::Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> rusqlite::Result<Self> {
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS pairs (
+                pair BLOB PRIMARY KEY,
+                token_0 BLOB NOT NULL,
+                token_1 BLOB NOT NULL,
+                pool_type INTEGER NOT NULL,
+                reserve_0 TEXT NOT NULL,
+                reserve_1 TEXT NOT NULL,
+                total_supply TEXT NOT NULL,
+                volume_0 TEXT NOT NULL,
+                volume_1 TEXT NOT NULL,
+                swap_count INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS lp_balances (
+                pair BLOB NOT NULL,
+                holder BLOB NOT NULL,
+                balance TEXT NOT NULL,
+                PRIMARY KEY (pair, holder)
+            );",
+        )?;
+        Ok(SqliteStore { connection })
+    }
+}
+
+fn pair_from_row(row: &Row) -> rusqlite::Result<PairState> {
+    Ok(PairState {
+        pair: account(row, 0)?,
+        token_0: account(row, 1)?,
+        token_1: account(row, 2)?,
+        pool_type: row.get(3)?,
+        reserve_0: balance(row, 4)?,
+        reserve_1: balance(row, 5)?,
+        total_supply: balance(row, 6)?,
+        volume_0: balance(row, 7)?,
+        volume_1: balance(row, 8)?,
+        swap_count: row.get::<_, i64>(9)? as u64,
+    })
+}
+
+fn account(row: &Row, index: usize) -> rusqlite::Result<AccountId> {
+    row.get::<_, Vec<u8>>(index)?.try_into().map_err(|_| {
+        rusqlite::Error::InvalidColumnType(index, "account".into(), rusqlite::types::Type::Blob)
+    })
+}
+
+fn balance(row: &Row, index: usize) -> rusqlite::Result<Balance> {
+    row.get::<_, String>(index)?.parse().map_err(|error| {
+        rusqlite::Error::FromSqlConversionFailure(
+            index,
+            rusqlite::types::Type::Text,
+            Box::new(error),
+        )
+    })
+}
+
+const PAIR_COLUMNS: &str = "pair, token_0, token_1, pool_type, reserve_0, reserve_1, \
+     total_supply, volume_0, volume_1, swap_count";
+
+impl Store for SqliteStore {
+    type Error = rusqlite::Error;
+
+    fn put_pair(&mut self, state: &PairState) -> Result<(), Self::Error> {
+        self.connection.execute(
+            &format!("INSERT OR REPLACE INTO pairs ({PAIR_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)"),
+            params![
+                &state.pair[..],
+                &state.token_0[..],
+                &state.token_1[..],
+                state.pool_type,
+                state.reserve_0.to_string(),
+                state.reserve_1.to_string(),
+                state.total_supply.to_string(),
+                state.volume_0.to_string(),
+                state.volume_1.to_string(),
+                state.swap_count as i64,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn pair(&self, pair: &AccountId) -> Result<Option<PairState>, Self::Error> {
+        self.connection
+            .query_row(
+                &format!("SELECT {PAIR_COLUMNS} FROM pairs WHERE pair = ?1"),
+                params![&pair[..]],
+                pair_from_row,
+            )
+            .optional()
+    }
+
+    fn pairs(&self) -> Result<Vec<PairState>, Self::Error> {
+        let mut statement = self
+            .connection
+            .prepare(&format!("SELECT {PAIR_COLUMNS} FROM pairs ORDER BY pair"))?;
+        let pairs = statement.query_map([], pair_from_row)?.collect();
+        pairs
+    }
+
+    fn put_lp_balance(
+        &mut self,
+        pair: &AccountId,
+        holder: &AccountId,
+        balance: Balance,
+    ) -> Result<(), Self::Error> {
+        if balance == 0 {
+            self.connection.execute(
+                "DELETE FROM lp_balances WHERE pair = ?1 AND holder = ?2",
+                params![&pair[..], &holder[..]],
+            )?;
+        } else {
+            self.connection.execute(
+                "INSERT OR REPLACE INTO lp_balances (pair, holder, balance) VALUES (?1, ?2, ?3)",
+                params![&pair[..], &holder[..], balance.to_string()],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn lp_balance(&self, pair: &AccountId, holder: &AccountId) -> Result<Balance, Self::Error> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT balance FROM lp_balances WHERE pair = ?1 AND holder = ?2",
+                params![&pair[..], &holder[..]],
+                |row| balance(row, 0),
+            )
+            .optional()?
+            .unwrap_or_default())
+    }
+
+    fn lp_holders(&self, pair: &AccountId) -> Result<Vec<(AccountId, Balance)>, Self::Error> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT holder, balance FROM lp_balances WHERE pair = ?1 ORDER BY holder")?;
+        let holders = statement
+            .query_map(params![&pair[..]], |row| {
+                Ok((account(row, 0)?, balance(row, 1)?))
+            })?
+            .collect();
+        holders
+    }
+}
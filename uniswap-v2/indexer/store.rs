@@ -0,0 +1,118 @@
+use crate::{
+    AccountId,
+    Balance,
+    PoolTypeId,
+};
+use std::{
+    collections::{
+        BTreeMap,
+        HashMap,
+    },
+    convert::Infallible,
+};
+
+/// State of a pair folded from its events.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PairState {
+    pub pair: AccountId,
+    pub token_0: AccountId,
+    pub token_1: AccountId,
+    /// Pool type the factory created the pair with, only constant-product pairs are quoted
+    /// by the router.
+    pub pool_type: PoolTypeId,
+    pub reserve_0: Balance,
+    pub reserve_1: Balance,
+    pub total_supply: Balance,
+    /// Amounts of each token swapped in and out.
+    pub volume_0: Balance,
+    pub volume_1: Balance,
+    pub swap_count: u64,
+}
+
+/// Storage backend of the index.
+pub trait Store {
+    type Error;
+
+    /// Inserts or replaces the state of `state.pair`.
+    fn put_pair(&mut self, state: &PairState) -> Result<(), Self::Error>;
+
+    fn pair(&self, pair: &AccountId) -> Result<Option<PairState>, Self::Error>;
+
+    /// Returns all pairs in the order of their addresses.
+    fn pairs(&self) -> Result<Vec<PairState>, Self::Error>;
+
+    /// Sets the liquidity of `holder` in `pair`, a balance of 0 removes the holder.
+    fn put_lp_balance(
+        &mut self,
+        pair: &AccountId,
+        holder: &AccountId,
+        balance: Balance,
+    ) -> Result<(), Self::Error>;
+
+    fn lp_balance(&self, pair: &AccountId, holder: &AccountId) -> Result<Balance, Self::Error>;
+
+    /// Returns the holders of liquidity of `pair` with their balance, in the order of their
+    /// addresses.
+    fn lp_holders(&self, pair: &AccountId) -> Result<Vec<(AccountId, Balance)>, Self::Error>;
+}
+
+/// Store keeping the index in memory.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    pairs: BTreeMap<AccountId, PairState>,
+    lp_balances: HashMap<AccountId, BTreeMap<AccountId, Balance>>,
+}
+
+impl Store for MemoryStore {
+    type Error = Infallible;
+
+    fn put_pair(&mut self, state: &PairState) -> Result<(), Self::Error> {
+        self.pairs.insert(state.pair, state.clone());
+        Ok(())
+    }
+
+    fn pair(&self, pair: &AccountId) -> Result<Option<PairState>, Self::Error> {
+        Ok(self.pairs.get(pair).cloned())
+    }
+
+    fn pairs(&self) -> Result<Vec<PairState>, Self::Error> {
+        Ok(self.pairs.values().cloned().collect())
+    }
+
+    fn put_lp_balance(
+        &mut self,
+        pair: &AccountId,
+        holder: &AccountId,
+        balance: Balance,
+    ) -> Result<(), Self::Error> {
+        let balances = self.lp_balances.entry(*pair).or_default();
+        if balance == 0 {
+            balances.remove(holder);
+        } else {
+            balances.insert(*holder, balance);
+        }
+        Ok(())
+    }
+
+    fn lp_balance(&self, pair: &AccountId, holder: &AccountId) -> Result<Balance, Self::Error> {
+        Ok(self
+            .lp_balances
+            .get(pair)
+            .and_then(|balances| balances.get(holder))
+            .copied()
+            .unwrap_or_default())
+    }
+
+    fn lp_holders(&self, pair: &AccountId) -> Result<Vec<(AccountId, Balance)>, Self::Error> {
+        Ok(self
+            .lp_balances
+            .get(pair)
+            .map(|balances| {
+                balances
+                    .iter()
+                    .map(|(holder, balance)| (*holder, *balance))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+}
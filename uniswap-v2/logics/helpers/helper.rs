@@ -1,11 +1,30 @@
 use crate::{
-    helpers::math::casted_mul,
+    helpers::math::{
+        BPS,
+        DEFAULT_FEE_BPS,
+    },
     traits::{
-        factory::FactoryRef,
+        errors::MathOp,
+        factory::{
+            FactoryRef,
+            CONSTANT_PRODUCT_POOL_TYPE,
+        },
         pair::PairRef,
+        router::{
+            HopQuote,
+            SwapQuote,
+        },
+        types::{
+            Price,
+            PRICE_ONE,
+        },
     },
 };
-use ink::prelude::vec::Vec;
+use amm_math::Rounding;
+use ink::prelude::{
+    vec,
+    vec::Vec,
+};
 use openbrush::traits::{
     AccountId,
     AccountIdExt,
@@ -41,13 +60,16 @@ pub fn sort_tokens(
     Ok((token_0, token_1))
 }
 
-#[inline]
+/// Returns the constant-product pair of `token_a` and `token_b`.
+///
+/// Pools of other types are not returned: the helpers quote swaps with the constant-product
+/// formula, which would misprice them.
 pub fn pair_for_on_chain(
     factory: &AccountId,
     token_a: AccountId,
     token_b: AccountId,
 ) -> Option<AccountId> {
-    FactoryRef::get_pair(factory, token_a, token_b)
+    FactoryRef::get_pool(factory, CONSTANT_PRODUCT_POOL_TYPE, token_a, token_b)
 }
 
 pub fn get_reserves(
@@ -77,76 +99,136 @@ pub fn quote(
         HelperError::InsufficientLiquidity
     );
 
-    let amount_b: Balance = casted_mul(amount_a, reserve_b)
-        .checked_div(reserve_a.into())
-        .ok_or(HelperError::DivByZero)?
-        .try_into()
-        .map_err(|_| HelperError::CastOverflow)?;
-
-    Ok(amount_b)
+    amm_math::quote(amount_a, reserve_a, reserve_b, Rounding::Down)
+        .map_err(|error| HelperError::Arithmetic(error.into(), HelperLocation::Quote))
 }
 
 pub fn get_amount_out(
     amount_in: Balance,
     reserve_in: Balance,
     reserve_out: Balance,
+) -> Result<Balance, HelperError> {
+    get_amount_out_with_fee(amount_in, reserve_in, reserve_out, DEFAULT_FEE_BPS)
+}
+
+/// Same as `get_amount_out` for a pair charging `fee_bps` on the input.
+pub fn get_amount_out_with_fee(
+    amount_in: Balance,
+    reserve_in: Balance,
+    reserve_out: Balance,
+    fee_bps: u16,
 ) -> Result<Balance, HelperError> {
     ensure!(amount_in > 0, HelperError::InsufficientAmount);
     ensure!(
         reserve_in > 0 && reserve_out > 0,
         HelperError::InsufficientLiquidity
     );
+    ensure!(fee_bps < BPS, HelperError::InvalidFee);
 
-    let amount_in_with_fee = casted_mul(amount_in, 997);
-
-    let numerator = amount_in_with_fee
-        .checked_mul(reserve_out.into())
-        .ok_or(HelperError::MulOverFlow)?;
-
-    let denominator = casted_mul(reserve_in, 1000)
-        .checked_add(amount_in_with_fee)
-        .ok_or(HelperError::AddOverFlow)?;
-
-    let amount_out: Balance = numerator
-        .checked_div(denominator)
-        .ok_or(HelperError::DivByZero2)?
-        .try_into()
-        .map_err(|_| HelperError::CastOverflow2)?;
-
-    Ok(amount_out)
+    amm_math::amount_out(amount_in, reserve_in, reserve_out, fee_bps)
+        .map_err(|error| HelperError::Arithmetic(error.into(), HelperLocation::AmountOut))
 }
 
 pub fn get_amount_in(
     amount_out: Balance,
     reserve_in: Balance,
     reserve_out: Balance,
+) -> Result<Balance, HelperError> {
+    get_amount_in_with_fee(amount_out, reserve_in, reserve_out, DEFAULT_FEE_BPS)
+}
+
+/// Same as `get_amount_in` for a pair charging `fee_bps` on the input.
+pub fn get_amount_in_with_fee(
+    amount_out: Balance,
+    reserve_in: Balance,
+    reserve_out: Balance,
+    fee_bps: u16,
 ) -> Result<Balance, HelperError> {
     ensure!(amount_out > 0, HelperError::InsufficientAmount);
     ensure!(
         reserve_in > 0 && reserve_out > 0,
         HelperError::InsufficientLiquidity
     );
+    ensure!(fee_bps < BPS, HelperError::InvalidFee);
 
-    let numerator = casted_mul(reserve_in, amount_out)
-        .checked_mul(1000.into())
-        .ok_or(HelperError::MulOverFlow)?;
+    amm_math::amount_in(amount_out, reserve_in, reserve_out, fee_bps)
+        .map_err(|error| HelperError::Arithmetic(error.into(), HelperLocation::AmountIn))
+}
 
-    let denominator = casted_mul(
-        reserve_out
-            .checked_sub(amount_out)
-            .ok_or(HelperError::SubUnderFlow)?,
-        997,
-    );
+/// Pair swapped through by a hop of a path, with its reserves ordered from the input token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hop {
+    pub pair: AccountId,
+    pub reserve_in: Balance,
+    pub reserve_out: Balance,
+    pub fee_bps: u16,
+}
 
-    let amount_in: Balance = numerator
-        .checked_div(denominator)
-        .ok_or(HelperError::DivByZero)?
-        .checked_add(1.into())
-        .ok_or(HelperError::AddOverFlow)?
-        .try_into()
-        .map_err(|_| HelperError::CastOverflow)?;
+/// Returns the pair of `token_in` and `token_out` with its reserves and current swap fee.
+pub fn get_hop(
+    factory: &AccountId,
+    token_in: AccountId,
+    token_out: AccountId,
+) -> Result<Hop, HelperError> {
+    let pair = pair_for_on_chain(factory, token_in, token_out).ok_or(HelperError::PairNotFound)?;
+    let (reserve_0, reserve_1, _) = PairRef::get_reserves(&pair);
+    hop_at(
+        pair,
+        token_in,
+        token_out,
+        reserve_0,
+        reserve_1,
+        PairRef::get_fee(&pair),
+    )
+}
 
-    Ok(amount_in)
+/// Returns the hop through `pair` at `reserve_0` and `reserve_1`, in the order of the tokens
+/// of the pair.
+pub fn hop_at(
+    pair: AccountId,
+    token_in: AccountId,
+    token_out: AccountId,
+    reserve_0: Balance,
+    reserve_1: Balance,
+    fee_bps: u16,
+) -> Result<Hop, HelperError> {
+    let (token_0, _) = sort_tokens(token_in, token_out)?;
+    let (reserve_in, reserve_out) = if token_in == token_0 {
+        (reserve_0, reserve_1)
+    } else {
+        (reserve_1, reserve_0)
+    };
+    Ok(Hop {
+        pair,
+        reserve_in,
+        reserve_out,
+        fee_bps,
+    })
+}
+
+/// Returns the hops between consecutive tokens of `path`.
+pub fn get_hops(factory: &AccountId, path: &[AccountId]) -> Result<Vec<Hop>, HelperError> {
+    ensure!(path.len() >= 2, HelperError::InvalidPath);
+
+    path.windows(2)
+        .map(|tokens| get_hop(factory, tokens[0], tokens[1]))
+        .collect()
+}
+
+/// Returns the reserves of `token_a` and `token_b` in their pair and its current swap fee.
+pub fn get_reserves_and_fee(
+    factory: &AccountId,
+    token_a: AccountId,
+    token_b: AccountId,
+) -> Result<(Balance, Balance, u16), HelperError> {
+    let hop = get_hop(factory, token_a, token_b)?;
+    Ok((hop.reserve_in, hop.reserve_out, hop.fee_bps))
+}
+
+/// Returns `fee_bps` reduced by `discount_bps` of itself.
+pub fn discounted_fee(fee_bps: u16, discount_bps: u16) -> u16 {
+    let discount = u32::from(fee_bps) * u32::from(discount_bps.min(BPS)) / u32::from(BPS);
+    fee_bps - discount as u16
 }
 
 pub fn get_amounts_out(
@@ -154,16 +236,17 @@ pub fn get_amounts_out(
     amount_in: Balance,
     path: &Vec<AccountId>,
 ) -> Result<Vec<Balance>, HelperError> {
-    ensure!(path.len() >= 2, HelperError::InvalidPath);
-
-    let mut amounts = Vec::with_capacity(path.len());
-    amounts.push(amount_in);
-    for i in 0..path.len() - 1 {
-        let (reserve_in, reserve_out) = get_reserves(factory, path[i], path[i + 1])?;
-        amounts.push(get_amount_out(amounts[i], reserve_in, reserve_out)?);
-    }
+    get_amounts_out_with_discount(factory, amount_in, path, 0)
+}
 
-    Ok(amounts)
+/// Same as `get_amounts_out` with the fee of each pair reduced by `discount_bps`.
+pub fn get_amounts_out_with_discount(
+    factory: &AccountId,
+    amount_in: Balance,
+    path: &Vec<AccountId>,
+    discount_bps: u16,
+) -> Result<Vec<Balance>, HelperError> {
+    swap_amounts_out(amount_in, &get_hops(factory, path)?, discount_bps)
 }
 
 pub fn get_amounts_in(
@@ -171,21 +254,238 @@ pub fn get_amounts_in(
     amount_out: Balance,
     path: &Vec<AccountId>,
 ) -> Result<Vec<Balance>, HelperError> {
+    get_amounts_in_with_discount(factory, amount_out, path, 0)
+}
+
+/// Same as `get_amounts_in` with the fee of each pair reduced by `discount_bps`.
+pub fn get_amounts_in_with_discount(
+    factory: &AccountId,
+    amount_out: Balance,
+    path: &Vec<AccountId>,
+    discount_bps: u16,
+) -> Result<Vec<Balance>, HelperError> {
+    swap_amounts_in(amount_out, &get_hops(factory, path)?, discount_bps)
+}
+
+/// Same as `get_amounts_out_with_discount` with the reserves of the pairs in `overrides`
+/// replaced.
+pub fn get_amounts_out_with_overrides(
+    factory: &AccountId,
+    amount_in: Balance,
+    path: &[AccountId],
+    overrides: &[(AccountId, Balance, Balance)],
+    discount_bps: u16,
+) -> Result<Vec<Balance>, HelperError> {
+    swap_amounts_out(
+        amount_in,
+        &get_hops_with_overrides(factory, path, overrides)?,
+        discount_bps,
+    )
+}
+
+/// Same as `get_amounts_in_with_discount` with the reserves of the pairs in `overrides`
+/// replaced.
+pub fn get_amounts_in_with_overrides(
+    factory: &AccountId,
+    amount_out: Balance,
+    path: &[AccountId],
+    overrides: &[(AccountId, Balance, Balance)],
+    discount_bps: u16,
+) -> Result<Vec<Balance>, HelperError> {
+    swap_amounts_in(
+        amount_out,
+        &get_hops_with_overrides(factory, path, overrides)?,
+        discount_bps,
+    )
+}
+
+/// Same as `get_hops` with the reserves of the pairs in `overrides`, given as
+/// `(pair, reserve_0, reserve_1)` in the order of the tokens of the pair. The reserves of
+/// overridden pairs are not read and their fee is the one charged at the given reserves.
+/// Pairs out of the path are ignored.
+pub fn get_hops_with_overrides(
+    factory: &AccountId,
+    path: &[AccountId],
+    overrides: &[(AccountId, Balance, Balance)],
+) -> Result<Vec<Hop>, HelperError> {
     ensure!(path.len() >= 2, HelperError::InvalidPath);
+    ensure_unique_overrides(overrides)?;
+
+    path.windows(2)
+        .map(|tokens| {
+            let pair = pair_for_on_chain(factory, tokens[0], tokens[1])
+                .ok_or(HelperError::PairNotFound)?;
+            let (reserve_0, reserve_1) = match overrides.iter().find(|(p, ..)| *p == pair) {
+                Some(&(_, reserve_0, reserve_1)) => (reserve_0, reserve_1),
+                None => {
+                    let (reserve_0, reserve_1, _) = PairRef::get_reserves(&pair);
+                    (reserve_0, reserve_1)
+                }
+            };
+            hop_at(
+                pair,
+                tokens[0],
+                tokens[1],
+                reserve_0,
+                reserve_1,
+                PairRef::get_fee_at(&pair, reserve_0, reserve_1),
+            )
+        })
+        .collect()
+}
 
-    let mut amounts = Vec::with_capacity(path.len());
-    unsafe {
-        amounts.set_len(path.len());
+/// Fails if a pair is overridden more than once.
+pub fn ensure_unique_overrides(
+    overrides: &[(AccountId, Balance, Balance)],
+) -> Result<(), HelperError> {
+    for (i, (pair, ..)) in overrides.iter().enumerate() {
+        ensure!(
+            overrides[..i].iter().all(|(other, ..)| other != pair),
+            HelperError::DuplicateOverride
+        );
     }
-    amounts[path.len() - 1] = amount_out;
-    for i in (0..path.len() - 1).rev() {
-        let (reserve_in, reserve_out) = get_reserves(factory, path[i], path[i + 1])?;
-        amounts[i] = get_amount_in(amounts[i + 1], reserve_in, reserve_out)?;
+    Ok(())
+}
+
+/// Returns the amounts of each token swapping `amount_in` through `hops`, with the fee of
+/// each pair reduced by `discount_bps`.
+pub fn swap_amounts_out(
+    amount_in: Balance,
+    hops: &[Hop],
+    discount_bps: u16,
+) -> Result<Vec<Balance>, HelperError> {
+    let mut amounts = Vec::with_capacity(hops.len() + 1);
+    amounts.push(amount_in);
+    for (i, hop) in hops.iter().enumerate() {
+        amounts.push(get_amount_out_with_fee(
+            amounts[i],
+            hop.reserve_in,
+            hop.reserve_out,
+            discounted_fee(hop.fee_bps, discount_bps),
+        )?);
+    }
+
+    Ok(amounts)
+}
+
+/// Returns the amounts of each token swapping through `hops` for `amount_out`, with the fee
+/// of each pair reduced by `discount_bps`.
+pub fn swap_amounts_in(
+    amount_out: Balance,
+    hops: &[Hop],
+    discount_bps: u16,
+) -> Result<Vec<Balance>, HelperError> {
+    let mut amounts = vec![0; hops.len() + 1];
+    amounts[hops.len()] = amount_out;
+    for (i, hop) in hops.iter().enumerate().rev() {
+        amounts[i] = get_amount_in_with_fee(
+            amounts[i + 1],
+            hop.reserve_in,
+            hop.reserve_out,
+            discounted_fee(hop.fee_bps, discount_bps),
+        )?;
     }
 
     Ok(amounts)
 }
 
+/// Breaks down the swap of `amounts` along `path` through `hops`, as returned by
+/// `swap_amounts_out` or `swap_amounts_in` with the same `discount_bps`.
+///
+/// The price impact compares the execution price of each hop to its mid price, which is
+/// exact whatever the decimals of the tokens, and compounds them.
+pub fn quote_swap(
+    path: &[AccountId],
+    hops: &[Hop],
+    amounts: &[Balance],
+    discount_bps: u16,
+) -> Result<SwapQuote, HelperError> {
+    ensure!(
+        path.len() == hops.len() + 1 && amounts.len() == path.len(),
+        HelperError::InvalidPath
+    );
+
+    let mut quotes = Vec::with_capacity(hops.len());
+    let mut mid_price_before = PRICE_ONE;
+    let mut mid_price_after = PRICE_ONE;
+    // Product of the execution prices of the hops over their mid prices, rounded up so that
+    // an exact impact is not rounded up further.
+    let mut execution_over_mid = PRICE_ONE;
+    for (i, hop) in hops.iter().enumerate() {
+        let (amount_in, amount_out) = (amounts[i], amounts[i + 1]);
+        let reserve_in_after =
+            hop.reserve_in
+                .checked_add(amount_in)
+                .ok_or(HelperError::Arithmetic(
+                    MathOp::Add,
+                    HelperLocation::SwapQuote,
+                ))?;
+        let reserve_out_after =
+            hop.reserve_out
+                .checked_sub(amount_out)
+                .ok_or(HelperError::Arithmetic(
+                    MathOp::Sub,
+                    HelperLocation::SwapQuote,
+                ))?;
+        let fee_bps = discounted_fee(hop.fee_bps, discount_bps);
+        let quote = HopQuote {
+            pair: hop.pair,
+            token_in: path[i],
+            token_out: path[i + 1],
+            amount_in,
+            amount_out,
+            fee_bps,
+            fee_amount: amm_math::fee_amount(amount_in, fee_bps, Rounding::Down).map_err(
+                |error| HelperError::Arithmetic(error.into(), HelperLocation::SwapQuote),
+            )?,
+            mid_price_before: ratio(hop.reserve_out, hop.reserve_in)?,
+            mid_price_after: ratio(reserve_out_after, reserve_in_after)?,
+        };
+        mid_price_before = price_mul(mid_price_before, quote.mid_price_before, Rounding::Down)?;
+        mid_price_after = price_mul(mid_price_after, quote.mid_price_after, Rounding::Down)?;
+        let hop_execution_over_mid = amm_math::mul_div(
+            amm_math::full_mul(amount_out, hop.reserve_in),
+            PRICE_ONE.into(),
+            amm_math::full_mul(amount_in, hop.reserve_out),
+            Rounding::Up,
+        )
+        .and_then(amm_math::to_u128)
+        .map_err(|error| HelperError::Arithmetic(error.into(), HelperLocation::SwapQuote))?;
+        execution_over_mid = price_mul(execution_over_mid, hop_execution_over_mid, Rounding::Up)?;
+        quotes.push(quote);
+    }
+
+    let (amount_in, amount_out) = (amounts[0], amounts[hops.len()]);
+    let price_impact_bps = amm_math::mul_div_u128(
+        PRICE_ONE.saturating_sub(execution_over_mid),
+        BPS.into(),
+        PRICE_ONE.into(),
+        Rounding::Up,
+    )
+    .map_err(|error| HelperError::Arithmetic(error.into(), HelperLocation::SwapQuote))?;
+    Ok(SwapQuote {
+        amount_in,
+        amount_out,
+        hops: quotes,
+        mid_price_before,
+        mid_price_after,
+        execution_price: ratio(amount_out, amount_in)?,
+        price_impact_bps: price_impact_bps as u16,
+    })
+}
+
+/// Returns `numerator / denominator` as a `Price`, rounded down.
+fn ratio(numerator: Balance, denominator: Balance) -> Result<Price, HelperError> {
+    amm_math::mul_div_u128(numerator, PRICE_ONE, denominator.into(), Rounding::Down)
+        .map_err(|error| HelperError::Arithmetic(error.into(), HelperLocation::SwapQuote))
+}
+
+/// Returns `a * b` rounded as `rounding`.
+fn price_mul(a: Price, b: Price, rounding: Rounding) -> Result<Price, HelperError> {
+    amm_math::mul_div_u128(a, b, PRICE_ONE.into(), rounding)
+        .map_err(|error| HelperError::Arithmetic(error.into(), HelperLocation::SwapQuote))
+}
+
 #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
 pub enum HelperError {
@@ -193,13 +493,155 @@ pub enum HelperError {
     ZeroAddress,
     InsufficientAmount,
     InsufficientLiquidity,
-    DivByZero,
-    CastOverflow,
-    MulOverFlow,
-    AddOverFlow,
-    DivByZero2,
-    CastOverflow2,
     InvalidPath,
-    SubUnderFlow,
     PairNotFound,
+    TwapNotReady,
+    InvalidSlippage,
+    InvalidFee,
+    Arithmetic(MathOp, HelperLocation),
+    /// The pair accumulates prices in an encoding the oracles do not read.
+    UnsupportedPriceAccumulator,
+    /// A pair is given more than one reserve override.
+    DuplicateOverride,
+}
+
+/// Helper in which an arithmetic error occurred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum HelperLocation {
+    Quote,
+    AmountOut,
+    AmountIn,
+    Observation,
+    MinAmountOut,
+    AveragePrice,
+    LogPrice,
+    SwapQuote,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hops() -> Vec<Hop> {
+        vec![
+            Hop {
+                pair: AccountId::from([0x10; 32]),
+                reserve_in: 1_000_000,
+                reserve_out: 4_000_000,
+                fee_bps: 30,
+            },
+            Hop {
+                pair: AccountId::from([0x11; 32]),
+                reserve_in: 8_000_000,
+                reserve_out: 2_000_000,
+                fee_bps: 100,
+            },
+        ]
+    }
+
+    fn path() -> Vec<AccountId> {
+        vec![
+            AccountId::from([0x01; 32]),
+            AccountId::from([0x02; 32]),
+            AccountId::from([0x03; 32]),
+        ]
+    }
+
+    #[ink::test]
+    fn quote_one_hop() {
+        let hops = &hops()[..1];
+        let path = &path()[..2];
+        let amounts = swap_amounts_out(10_000, hops, 0).unwrap();
+        assert_eq!(amounts, vec![10_000, 39_486]);
+        assert_eq!(
+            quote_swap(path, hops, &amounts, 0),
+            Ok(SwapQuote {
+                amount_in: 10_000,
+                amount_out: 39_486,
+                hops: vec![HopQuote {
+                    pair: hops[0].pair,
+                    token_in: path[0],
+                    token_out: path[1],
+                    amount_in: 10_000,
+                    amount_out: 39_486,
+                    fee_bps: 30,
+                    fee_amount: 30,
+                    mid_price_before: 4 * PRICE_ONE,
+                    mid_price_after: 3_921_300_990_099_009_900,
+                }],
+                mid_price_before: 4 * PRICE_ONE,
+                mid_price_after: 3_921_300_990_099_009_900,
+                execution_price: 3_948_600_000_000_000_000,
+                // 1 - 3.9486 / 4
+                price_impact_bps: 129,
+            })
+        );
+    }
+
+    #[ink::test]
+    fn quote_two_hops() {
+        let hops = hops();
+        let path = path();
+        let amounts = swap_amounts_out(10_000, &hops, 0).unwrap();
+        assert_eq!(amounts, vec![10_000, 39_486, 9_725]);
+        let quote = quote_swap(&path, &hops, &amounts, 0).unwrap();
+        assert_eq!(quote.hops[1].fee_amount, 394);
+        assert_eq!(quote.mid_price_before, PRICE_ONE);
+        assert_eq!(quote.mid_price_after, 970_766_953_020_293_448);
+        assert_eq!(quote.execution_price, 972_500_000_000_000_000);
+        assert_eq!(quote.price_impact_bps, 275);
+
+        let amounts = swap_amounts_in(1_000, &hops, 0).unwrap();
+        assert_eq!(amounts, vec![1_015, 4_043, 1_000]);
+        let quote = quote_swap(&path, &hops, &amounts, 0).unwrap();
+        assert_eq!(
+            (quote.amount_in, quote.amount_out, quote.price_impact_bps),
+            (1_015, 1_000, 148)
+        );
+
+        assert_eq!(
+            quote_swap(&path[..2], &hops, &amounts, 0),
+            Err(HelperError::InvalidPath)
+        );
+    }
+
+    #[ink::test]
+    fn quote_discounted_fee() {
+        let hops = &hops()[..1];
+        let path = &path()[..2];
+        let amounts = swap_amounts_out(10_000, hops, 5_000).unwrap();
+        assert_eq!(amounts, vec![10_000, 39_545]);
+        let quote = quote_swap(path, hops, &amounts, 5_000).unwrap();
+        assert_eq!((quote.hops[0].fee_bps, quote.hops[0].fee_amount), (15, 15));
+        // 1 - 3.9545 / 4, lower than the 129 of the undiscounted `quote_one_hop`.
+        assert_eq!(quote.price_impact_bps, 114);
+    }
+
+    #[ink::test]
+    fn override_reserves_of_pending_swap() {
+        let path = path();
+        let pair = hops()[0].pair;
+        // Reserves of the first pair after swapping 10_000 in, as in `quote_one_hop`.
+        let hop = hop_at(pair, path[0], path[1], 1_010_000, 3_960_514, 30).unwrap();
+        assert_eq!((hop.reserve_in, hop.reserve_out), (1_010_000, 3_960_514));
+        assert_eq!(
+            swap_amounts_out(10_000, &[hop], 0),
+            Ok(vec![10_000, 38_713])
+        );
+
+        // Reserves are given in the order of the tokens of the pair.
+        let hop = hop_at(pair, path[1], path[0], 1_010_000, 3_960_514, 30).unwrap();
+        assert_eq!((hop.reserve_in, hop.reserve_out), (3_960_514, 1_010_000));
+
+        let overrides = [
+            (AccountId::from([0x12; 32]), 1, 1),
+            (pair, 1_010_000, 3_960_514),
+        ];
+        assert_eq!(ensure_unique_overrides(&overrides), Ok(()));
+        assert_eq!(
+            ensure_unique_overrides(&[overrides[1], overrides[0], overrides[1]]),
+            Err(HelperError::DuplicateOverride)
+        );
+    }
 }
@@ -1,5 +1,99 @@
-use primitive_types::U256;
+use crate::traits::{
+    errors::MathOp,
+    types::UQ128x128,
+};
+use amm_math::Rounding;
+use primitive_types::{
+    U256,
+    U512,
+};
+
+/// Basis points in one.
+pub const BPS: u16 = amm_math::BPS;
+
+/// Swap fee of pairs without dynamic fee, 0.3%.
+pub const DEFAULT_FEE_BPS: u16 = 30;
+
+/// Upper bound of the swap fee of a pair, 10%.
+pub const MAX_FEE_BPS: u16 = 1_000;
 
 pub fn casted_mul(a: u128, b: u128) -> U256 {
-    U256::from(a) * U256::from(b)
+    amm_math::full_mul(a, b)
+}
+
+/// Returns `a * b / c` rounded down, fails with `MathOp::Div` if `c` is 0 and
+/// `MathOp::Overflow` if the result exceeds `u128`.
+pub fn mul_div(a: u128, b: u128, c: U256) -> Result<u128, MathOp> {
+    Ok(amm_math::mul_div_u128(a, b, c, Rounding::Down)?)
+}
+
+/// Returns the square root of `a * b` rounded down, which always fits in `u128`.
+pub fn sqrt_mul(a: u128, b: u128) -> u128 {
+    amm_math::sqrt(casted_mul(a, b), Rounding::Down).low_u128()
+}
+
+/// log2(1.0001) as a signed 64.64 fixed-point number.
+const LOG2_TICK_BASE_X64: i128 = 2_661_169_563_308_229;
+
+/// Bounds of the ticks of prices representable as `UQ128x128`.
+pub const MIN_TICK: i32 = -887_272;
+pub const MAX_TICK: i32 = 887_272;
+
+/// Returns log2 of `x` as a signed 64.64 fixed-point number, rounded down. `x` must not be 0.
+fn log2_x64(x: UQ128x128) -> i128 {
+    let x = x.into_inner();
+    let msb = x.bits() - 1;
+    // Normalizes x in [1, 2) with 127 fractional bits.
+    let mut r = if msb >= 127 {
+        x >> (msb - 127)
+    } else {
+        x << (127 - msb)
+    };
+    let mut log2 = (msb as i128 - UQ128x128::FRACTIONAL_BITS as i128) << 64;
+    for bit in (0..64).rev() {
+        r = (r * r) >> 127;
+        if r.bit(128) {
+            r >>= 1;
+            log2 |= 1 << bit;
+        }
+    }
+    log2
+}
+
+/// Returns the tick of `price`, the largest `tick` with 1.0001^`tick` <= `price` up to
+/// rounding, `None` if `price` is 0. The smallest price, 2^-128, has tick `MIN_TICK`.
+pub fn tick_at_price(price: UQ128x128) -> Option<i32> {
+    if price.into_inner().is_zero() {
+        return None
+    }
+    let tick = log2_x64(price).div_euclid(LOG2_TICK_BASE_X64);
+    Some(tick.clamp(MIN_TICK.into(), MAX_TICK.into()) as i32)
+}
+
+/// Returns 1.0001^`tick`, `None` out of [`MIN_TICK`, `MAX_TICK`].
+pub fn price_at_tick(tick: i32) -> Option<UQ128x128> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return None
+    }
+    let one = U256::one() << UQ128x128::FRACTIONAL_BITS;
+    let mut base = (U256::from(10_001u32) << UQ128x128::FRACTIONAL_BITS) / U256::from(10_000u32);
+    let mut result = one;
+    let mut exponent = tick.unsigned_abs();
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_x128(result, base)?;
+        }
+        exponent >>= 1;
+        if exponent > 0 {
+            base = mul_x128(base, base)?;
+        }
+    }
+    if tick < 0 {
+        result = U256::try_from((U512::one() << 256) / U512::from(result)).ok()?;
+    }
+    Some(UQ128x128::from_inner(result))
+}
+
+fn mul_x128(a: U256, b: U256) -> Option<U256> {
+    U256::try_from(a.full_mul(b) >> UQ128x128::FRACTIONAL_BITS).ok()
 }
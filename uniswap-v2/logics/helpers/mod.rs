@@ -1,3 +1,4 @@
 pub mod helper;
 pub mod math;
+pub mod oracle;
 pub mod transfer_helper;
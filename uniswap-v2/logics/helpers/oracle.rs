@@ -0,0 +1,257 @@
+use crate::{
+    ensure,
+    helpers::{
+        helper::{
+            sort_tokens,
+            HelperError,
+            HelperLocation,
+        },
+        math::{
+            casted_mul,
+            price_at_tick,
+            BPS,
+        },
+    },
+    traits::{
+        errors::MathOp,
+        pair::{
+            PairRef,
+            PRICE_ACCUMULATOR_VERSION,
+        },
+        types::{
+            Observation,
+            Price,
+            UQ128x128,
+        },
+    },
+};
+use openbrush::traits::{
+    AccountId,
+    Balance,
+    Timestamp,
+};
+use primitive_types::U256;
+use sp_arithmetic::{
+    FixedPointNumber,
+    FixedU128,
+};
+
+/// Returns the price accumulators of `pair` at `now`, extrapolating the last values written
+/// by the pair at its current reserves, as Uniswap's `currentCumulativePrices`.
+pub fn current_cumulative_prices(pair: &AccountId, now: Timestamp) -> (U256, U256) {
+    let (reserve_0, reserve_1, block_timestamp_last) = PairRef::get_reserves(pair);
+    amm_math::update_cumulative(
+        PairRef::price_0_cumulative_last(pair).into(),
+        PairRef::price_1_cumulative_last(pair).into(),
+        now.saturating_sub(block_timestamp_last).into(),
+        reserve_0,
+        reserve_1,
+    )
+}
+
+/// Checkpoints the price accumulators of `pair` at `now`.
+///
+/// Returns `None` if less than `period` elapsed since `last`, otherwise the new observation
+/// holding the average prices since `last`. The first observation of `pair` fails if its
+/// accumulators are not of `PRICE_ACCUMULATOR_VERSION`, the version of a pair never changes.
+pub fn update_observation(
+    pair: &AccountId,
+    last: Option<Observation>,
+    period: Timestamp,
+    now: Timestamp,
+) -> Result<Option<Observation>, HelperError> {
+    if last.is_none() {
+        ensure!(
+            price_accumulator_version(pair) == Some(PRICE_ACCUMULATOR_VERSION),
+            HelperError::UnsupportedPriceAccumulator
+        );
+    }
+    let (price_0_cumulative, price_1_cumulative) = current_cumulative_prices(pair, now);
+    next_observation(last, price_0_cumulative, price_1_cumulative, now, period)
+}
+
+/// Returns the version of the price accumulators of `pair`, `None` if it predates the message.
+fn price_accumulator_version(pair: &AccountId) -> Option<u8> {
+    PairRef::price_accumulator_version_builder(pair)
+        .try_invoke()
+        .ok()?
+        .ok()
+}
+
+/// Returns the observation of the accumulators at `now` following `last`, `None` if less
+/// than `period` elapsed since `last`.
+pub fn next_observation(
+    last: Option<Observation>,
+    price_0_cumulative: U256,
+    price_1_cumulative: U256,
+    now: Timestamp,
+    period: Timestamp,
+) -> Result<Option<Observation>, HelperError> {
+    let mut observation = match last {
+        Some(last) => {
+            let time_elapsed = now
+                .checked_sub(last.timestamp)
+                .ok_or(HelperError::Arithmetic(
+                    MathOp::Sub,
+                    HelperLocation::Observation,
+                ))?;
+            if time_elapsed == 0 || time_elapsed < period {
+                return Ok(None)
+            }
+            Observation {
+                price_0_average: average_price(
+                    last.price_0_cumulative.into(),
+                    price_0_cumulative,
+                    time_elapsed,
+                )?,
+                price_1_average: average_price(
+                    last.price_1_cumulative.into(),
+                    price_1_cumulative,
+                    time_elapsed,
+                )?,
+                has_average: true,
+                ..Default::default()
+            }
+        }
+        None => Default::default(),
+    };
+    observation.price_0_cumulative = price_0_cumulative.into();
+    observation.price_1_cumulative = price_1_cumulative.into();
+    observation.timestamp = now;
+    Ok(Some(observation))
+}
+
+/// Returns the average price of `token_in` in `token_out` held by `observation`.
+pub fn consult(
+    observation: &Observation,
+    token_in: AccountId,
+    token_out: AccountId,
+) -> Result<Price, HelperError> {
+    ensure!(observation.has_average, HelperError::TwapNotReady);
+    let (token_0, _) = sort_tokens(token_in, token_out)?;
+    if token_in == token_0 {
+        Ok(observation.price_0_average)
+    } else {
+        Ok(observation.price_1_average)
+    }
+}
+
+/// Returns the value of `amount_in` at `price` minus `max_slippage_bps`.
+pub fn min_amount_out(
+    amount_in: Balance,
+    price: Price,
+    max_slippage_bps: u16,
+) -> Result<Balance, HelperError> {
+    ensure!(max_slippage_bps <= BPS, HelperError::InvalidSlippage);
+    casted_mul(amount_in, price)
+        .checked_mul((BPS - max_slippage_bps).into())
+        .ok_or(HelperError::Arithmetic(
+            MathOp::Mul,
+            HelperLocation::MinAmountOut,
+        ))?
+        .checked_div(casted_mul(FixedU128::DIV, BPS.into()))
+        .ok_or(HelperError::Arithmetic(
+            MathOp::Div,
+            HelperLocation::MinAmountOut,
+        ))?
+        .try_into()
+        .map_err(|_| HelperError::Arithmetic(MathOp::Cast, HelperLocation::MinAmountOut))
+}
+
+/// Returns the growth of a price accumulator between two of its values.
+///
+/// Accumulators wrap around `U256::MAX` as in Uniswap V2, the difference is taken modulo
+/// 2^256 and stays exact across an overflow as long as the accumulator did not grow by more
+/// than `U256::MAX` in between.
+pub fn cumulative_delta(price_cumulative_start: U256, price_cumulative_end: U256) -> U256 {
+    price_cumulative_end
+        .overflowing_sub(price_cumulative_start)
+        .0
+}
+
+/// Returns the average price between two values of a price accumulator.
+///
+/// Accumulators sum `UQ128x128` prices times the milliseconds elapsed, the average is
+/// returned as a `Price`.
+pub fn average_price(
+    price_cumulative_start: U256,
+    price_cumulative_end: U256,
+    time_elapsed: Timestamp,
+) -> Result<Price, HelperError> {
+    let average = cumulative_delta(price_cumulative_start, price_cumulative_end)
+        .checked_div(time_elapsed.into())
+        .ok_or(HelperError::Arithmetic(
+            MathOp::Div,
+            HelperLocation::AveragePrice,
+        ))?;
+    UQ128x128::from_inner(average)
+        .to_price()
+        .ok_or(HelperError::Arithmetic(
+            MathOp::Cast,
+            HelperLocation::AveragePrice,
+        ))
+}
+
+/// Returns the average tick of the price of token 0 between two values of a log-price
+/// accumulator, rounded down. The average tick of the price of token 1 is its opposite.
+pub fn average_tick(
+    log_price_cumulative_start: i128,
+    log_price_cumulative_end: i128,
+    time_elapsed: Timestamp,
+) -> Result<i32, HelperError> {
+    ensure!(
+        time_elapsed > 0,
+        HelperError::Arithmetic(MathOp::Div, HelperLocation::LogPrice)
+    );
+    log_price_cumulative_end
+        .wrapping_sub(log_price_cumulative_start)
+        .div_euclid(time_elapsed.into())
+        .try_into()
+        .map_err(|_| HelperError::Arithmetic(MathOp::Cast, HelperLocation::LogPrice))
+}
+
+/// Returns the price at `tick`, the geometric mean price over a window for its average tick.
+pub fn tick_price(tick: i32) -> Result<Price, HelperError> {
+    price_at_tick(tick)
+        .and_then(UQ128x128::to_price)
+        .ok_or(HelperError::Arithmetic(
+            MathOp::Cast,
+            HelperLocation::LogPrice,
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::types::PRICE_ONE;
+
+    #[ink::test]
+    fn next_observation_is_taken_at_now() {
+        let first = next_observation(None, 0.into(), 0.into(), 100, 10)
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.timestamp, 100);
+        assert!(!first.has_average);
+
+        // Prices of 2 and 0.5 over 10 milliseconds.
+        let price_0_cumulative = U256::from(20) << 128;
+        let price_1_cumulative = U256::from(5) << 128;
+        assert_eq!(
+            next_observation(
+                Some(first.clone()),
+                price_0_cumulative,
+                price_1_cumulative,
+                109,
+                10
+            ),
+            Ok(None)
+        );
+        let second = next_observation(Some(first), price_0_cumulative, price_1_cumulative, 110, 10)
+            .unwrap()
+            .unwrap();
+        assert_eq!(second.timestamp, 110);
+        assert!(second.has_average);
+        assert_eq!(second.price_0_average, 2 * PRICE_ONE);
+        assert_eq!(second.price_1_average, PRICE_ONE / 2);
+    }
+}
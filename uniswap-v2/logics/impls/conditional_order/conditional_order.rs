@@ -0,0 +1,416 @@
+use crate::{
+    ensure,
+    helpers::{
+        helper::pair_for_on_chain,
+        math::BPS,
+        oracle::{
+            consult,
+            min_amount_out,
+            update_observation,
+        },
+        transfer_helper::{
+            safe_transfer,
+            safe_transfer_from,
+        },
+    },
+    traits::{
+        errors::MathOp,
+        router::RouterRef,
+    },
+};
+pub use crate::{
+    impls::conditional_order::*,
+    traits::conditional_order::*,
+};
+use ink::prelude::{
+    vec,
+    vec::Vec,
+};
+use openbrush::{
+    contracts::{
+        reentrancy_guard::*,
+        traits::psp22::PSP22Ref,
+    },
+    modifiers,
+    traits::{
+        AccountId,
+        Balance,
+        Storage,
+        Timestamp,
+    },
+};
+
+pub trait Internal {
+    /// Checkpoints the accumulators of `pair`, returns `false` if the period has not elapsed.
+    fn _update_observation(&mut self, pair: AccountId) -> Result<bool, ConditionalOrderError>;
+
+    fn _twap(
+        &self,
+        token_in: AccountId,
+        token_out: AccountId,
+    ) -> Result<Price, ConditionalOrderError>;
+
+    fn _remove_order(&mut self, order_id: OrderId, owner: AccountId);
+
+    fn _emit_order_placed_event(
+        &self,
+        _order_id: OrderId,
+        _owner: AccountId,
+        _token_in: AccountId,
+        _token_out: AccountId,
+        _amount_in: Balance,
+        _trigger: Trigger,
+        _trigger_price: Price,
+    );
+
+    fn _emit_order_executed_event(
+        &self,
+        _order_id: OrderId,
+        _keeper: AccountId,
+        _twap: Price,
+        _amount_in: Balance,
+        _amount_out: Balance,
+    );
+
+    fn _emit_order_cancelled_event(&self, _order_id: OrderId);
+}
+
+impl<T: Storage<data::Data> + Storage<reentrancy_guard::Data>> ConditionalOrder for T {
+    default fn factory(&self) -> AccountId {
+        self.data::<data::Data>().factory
+    }
+
+    default fn router(&self) -> AccountId {
+        self.data::<data::Data>().router
+    }
+
+    default fn twap_period(&self) -> Timestamp {
+        self.data::<data::Data>().twap_period
+    }
+
+    #[modifiers(non_reentrant)]
+    default fn place_order(
+        &mut self,
+        token_in: AccountId,
+        token_out: AccountId,
+        amount_in: Balance,
+        trigger: Trigger,
+        trigger_price: Price,
+        max_slippage_bps: u16,
+        execution_fee: Balance,
+        expiry: Timestamp,
+    ) -> Result<OrderId, ConditionalOrderError> {
+        ensure!(
+            amount_in > 0 && trigger_price > 0,
+            ConditionalOrderError::InsufficientAmount
+        );
+        ensure!(
+            max_slippage_bps <= BPS,
+            ConditionalOrderError::InvalidSlippage
+        );
+        ensure!(
+            expiry > Self::env().block_timestamp(),
+            ConditionalOrderError::InvalidExpiry
+        );
+        pair_for_on_chain(&self.data::<data::Data>().factory, token_in, token_out)
+            .ok_or(ConditionalOrderError::PairNotFound)?;
+
+        let owner = Self::env().caller();
+        let deposit =
+            amount_in
+                .checked_add(execution_fee)
+                .ok_or(ConditionalOrderError::Arithmetic(
+                    MathOp::Add,
+                    ConditionalOrderLocation::Deposit,
+                ))?;
+        safe_transfer_from(token_in, owner, Self::env().account_id(), deposit)?;
+
+        let order_id = self.data::<data::Data>().next_order_id;
+        self.data::<data::Data>().next_order_id =
+            order_id
+                .checked_add(1)
+                .ok_or(ConditionalOrderError::Arithmetic(
+                    MathOp::Add,
+                    ConditionalOrderLocation::OrderId,
+                ))?;
+        self.data::<data::Data>().orders.insert(
+            &order_id,
+            &ConditionalOrderInfo {
+                owner,
+                token_in,
+                token_out,
+                amount_in,
+                trigger,
+                trigger_price,
+                max_slippage_bps,
+                execution_fee,
+                expiry,
+            },
+        );
+        let mut owner_orders = self.orders_of(owner);
+        owner_orders.push(order_id);
+        self.data::<data::Data>()
+            .orders_of
+            .insert(&owner, &owner_orders);
+
+        self._emit_order_placed_event(
+            order_id,
+            owner,
+            token_in,
+            token_out,
+            amount_in,
+            trigger,
+            trigger_price,
+        );
+
+        Ok(order_id)
+    }
+
+    #[modifiers(non_reentrant)]
+    default fn cancel_order(
+        &mut self,
+        order_id: OrderId,
+    ) -> Result<Balance, ConditionalOrderError> {
+        let order = self
+            .data::<data::Data>()
+            .orders
+            .get(&order_id)
+            .ok_or(ConditionalOrderError::OrderNotFound)?;
+        ensure!(
+            order.owner == Self::env().caller(),
+            ConditionalOrderError::CallerIsNotOwner
+        );
+
+        self._remove_order(order_id, order.owner);
+        let refund = order.amount_in.checked_add(order.execution_fee).ok_or(
+            ConditionalOrderError::Arithmetic(MathOp::Add, ConditionalOrderLocation::Deposit),
+        )?;
+        safe_transfer(order.token_in, order.owner, refund)?;
+
+        self._emit_order_cancelled_event(order_id);
+
+        Ok(refund)
+    }
+
+    #[modifiers(non_reentrant)]
+    default fn execute_order(
+        &mut self,
+        order_id: OrderId,
+    ) -> Result<Vec<Balance>, ConditionalOrderError> {
+        let order = self
+            .data::<data::Data>()
+            .orders
+            .get(&order_id)
+            .ok_or(ConditionalOrderError::OrderNotFound)?;
+        let now = Self::env().block_timestamp();
+        ensure!(now <= order.expiry, ConditionalOrderError::Expired);
+
+        let pair = pair_for_on_chain(
+            &self.data::<data::Data>().factory,
+            order.token_in,
+            order.token_out,
+        )
+        .ok_or(ConditionalOrderError::PairNotFound)?;
+        self._update_observation(pair)?;
+        let twap = self._twap(order.token_in, order.token_out)?;
+        let amount_out_min = execution_amount_out_min(&order, twap)?;
+
+        self._remove_order(order_id, order.owner);
+
+        let router = self.data::<data::Data>().router;
+        PSP22Ref::approve(&order.token_in, router, order.amount_in)?;
+        let amounts = RouterRef::swap_exact_tokens_for_tokens(
+            &router,
+            order.amount_in,
+            amount_out_min,
+            vec![order.token_in, order.token_out],
+            order.owner,
+            now,
+        )?;
+        let keeper = Self::env().caller();
+        if order.execution_fee > 0 {
+            safe_transfer(order.token_in, keeper, order.execution_fee)?;
+        }
+
+        self._emit_order_executed_event(
+            order_id,
+            keeper,
+            twap,
+            order.amount_in,
+            amounts[amounts.len() - 1],
+        );
+
+        Ok(amounts)
+    }
+
+    default fn update_observation(&mut self, pair: AccountId) -> Result<(), ConditionalOrderError> {
+        ensure!(
+            self._update_observation(pair)?,
+            ConditionalOrderError::ObservationNotDue
+        );
+        Ok(())
+    }
+
+    default fn observation(&self, pair: AccountId) -> Option<Observation> {
+        self.data::<data::Data>().observations.get(&pair)
+    }
+
+    default fn twap(
+        &self,
+        token_in: AccountId,
+        token_out: AccountId,
+    ) -> Result<Price, ConditionalOrderError> {
+        self._twap(token_in, token_out)
+    }
+
+    default fn get_order(&self, order_id: OrderId) -> Option<ConditionalOrderInfo> {
+        self.data::<data::Data>().orders.get(&order_id)
+    }
+
+    default fn orders_of(&self, owner: AccountId) -> Vec<OrderId> {
+        self.data::<data::Data>()
+            .orders_of
+            .get(&owner)
+            .unwrap_or_default()
+    }
+
+    default fn next_order_id(&self) -> OrderId {
+        self.data::<data::Data>().next_order_id
+    }
+}
+
+/// Returns the minimum output of `order` executed at `twap`, fails if `twap` did not reach
+/// the trigger price.
+pub fn execution_amount_out_min(
+    order: &ConditionalOrderInfo,
+    twap: Price,
+) -> Result<Balance, ConditionalOrderError> {
+    let triggered = match order.trigger {
+        Trigger::StopLoss => twap <= order.trigger_price,
+        Trigger::TakeProfit => twap >= order.trigger_price,
+    };
+    ensure!(triggered, ConditionalOrderError::TriggerNotReached);
+    Ok(min_amount_out(
+        order.amount_in,
+        twap,
+        order.max_slippage_bps,
+    )?)
+}
+
+impl<T: Storage<data::Data>> Internal for T {
+    default fn _update_observation(
+        &mut self,
+        pair: AccountId,
+    ) -> Result<bool, ConditionalOrderError> {
+        let last = self.data::<data::Data>().observations.get(&pair);
+        match update_observation(
+            &pair,
+            last,
+            self.data::<data::Data>().twap_period,
+            Self::env().block_timestamp(),
+        )? {
+            Some(observation) => {
+                self.data::<data::Data>()
+                    .observations
+                    .insert(&pair, &observation);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    default fn _twap(
+        &self,
+        token_in: AccountId,
+        token_out: AccountId,
+    ) -> Result<Price, ConditionalOrderError> {
+        let pair = pair_for_on_chain(&self.data::<data::Data>().factory, token_in, token_out)
+            .ok_or(ConditionalOrderError::PairNotFound)?;
+        let observation = self
+            .data::<data::Data>()
+            .observations
+            .get(&pair)
+            .ok_or(ConditionalOrderError::TwapNotReady)?;
+        Ok(consult(&observation, token_in, token_out)?)
+    }
+
+    default fn _remove_order(&mut self, order_id: OrderId, owner: AccountId) {
+        self.data::<data::Data>().orders.remove(&order_id);
+        let mut owner_orders = self
+            .data::<data::Data>()
+            .orders_of
+            .get(&owner)
+            .unwrap_or_default();
+        owner_orders.retain(|id| *id != order_id);
+        self.data::<data::Data>()
+            .orders_of
+            .insert(&owner, &owner_orders);
+    }
+
+    default fn _emit_order_placed_event(
+        &self,
+        _order_id: OrderId,
+        _owner: AccountId,
+        _token_in: AccountId,
+        _token_out: AccountId,
+        _amount_in: Balance,
+        _trigger: Trigger,
+        _trigger_price: Price,
+    ) {
+    }
+
+    default fn _emit_order_executed_event(
+        &self,
+        _order_id: OrderId,
+        _keeper: AccountId,
+        _twap: Price,
+        _amount_in: Balance,
+        _amount_out: Balance,
+    ) {
+    }
+
+    default fn _emit_order_cancelled_event(&self, _order_id: OrderId) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::types::PRICE_ONE;
+
+    fn order(trigger: Trigger) -> ConditionalOrderInfo {
+        ConditionalOrderInfo {
+            owner: AccountId::from([0x01; 32]),
+            token_in: AccountId::from([0x02; 32]),
+            token_out: AccountId::from([0x03; 32]),
+            amount_in: 1_000,
+            trigger,
+            trigger_price: 2 * PRICE_ONE,
+            max_slippage_bps: 100,
+            execution_fee: 10,
+            expiry: 10,
+        }
+    }
+
+    #[ink::test]
+    fn stop_loss_triggers_at_or_below_price() {
+        let order = order(Trigger::StopLoss);
+        assert_eq!(
+            execution_amount_out_min(&order, 2 * PRICE_ONE + 1),
+            Err(ConditionalOrderError::TriggerNotReached)
+        );
+        // 1_000 * 2 * 99%
+        assert_eq!(execution_amount_out_min(&order, 2 * PRICE_ONE), Ok(1_980));
+        assert_eq!(execution_amount_out_min(&order, PRICE_ONE), Ok(990));
+    }
+
+    #[ink::test]
+    fn take_profit_triggers_at_or_above_price() {
+        let order = order(Trigger::TakeProfit);
+        assert_eq!(
+            execution_amount_out_min(&order, 2 * PRICE_ONE - 1),
+            Err(ConditionalOrderError::TriggerNotReached)
+        );
+        assert_eq!(execution_amount_out_min(&order, 2 * PRICE_ONE), Ok(1_980));
+        assert_eq!(execution_amount_out_min(&order, 3 * PRICE_ONE), Ok(2_970));
+    }
+}
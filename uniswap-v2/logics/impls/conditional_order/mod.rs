@@ -0,0 +1,2 @@
+pub mod conditional_order;
+pub mod data;
@@ -0,0 +1,42 @@
+use crate::traits::dca::{
+    Observation,
+    Position,
+    PositionId,
+};
+use ink::prelude::vec::Vec;
+use openbrush::{
+    storage::Mapping,
+    traits::{
+        AccountId,
+        Timestamp,
+        ZERO_ADDRESS,
+    },
+};
+
+pub const STORAGE_KEY: u32 = openbrush::storage_unique_key!(Data);
+
+#[derive(Debug)]
+#[openbrush::upgradeable_storage(STORAGE_KEY)]
+pub struct Data {
+    pub factory: AccountId,
+    pub router: AccountId,
+    pub twap_period: Timestamp,
+    pub observations: Mapping<AccountId, Observation>,
+    pub positions: Mapping<PositionId, Position>,
+    pub positions_of: Mapping<AccountId, Vec<PositionId>>,
+    pub next_position_id: PositionId,
+}
+
+impl Default for Data {
+    fn default() -> Self {
+        Self {
+            factory: ZERO_ADDRESS.into(),
+            router: ZERO_ADDRESS.into(),
+            twap_period: 0,
+            observations: Default::default(),
+            positions: Default::default(),
+            positions_of: Default::default(),
+            next_position_id: 0,
+        }
+    }
+}
@@ -0,0 +1,430 @@
+use crate::{
+    ensure,
+    helpers::{
+        helper::pair_for_on_chain,
+        math::BPS,
+        oracle::{
+            consult,
+            min_amount_out,
+            update_observation,
+        },
+        transfer_helper::{
+            safe_transfer,
+            safe_transfer_from,
+        },
+    },
+    traits::{
+        errors::{
+            call_result,
+            MathOp,
+        },
+        router::RouterRef,
+    },
+};
+pub use crate::{
+    impls::dca::*,
+    traits::dca::*,
+};
+use ink::{
+    env::CallFlags,
+    prelude::{
+        vec,
+        vec::Vec,
+    },
+};
+use openbrush::{
+    contracts::{
+        reentrancy_guard::*,
+        traits::psp22::PSP22Ref,
+    },
+    modifiers,
+    traits::{
+        AccountId,
+        Balance,
+        Storage,
+        Timestamp,
+    },
+};
+
+pub trait Internal {
+    /// Checkpoints the accumulators of `pair`, returns `false` if the period has not elapsed.
+    fn _update_observation(&mut self, pair: AccountId) -> Result<bool, DcaError>;
+
+    /// Swaps `amount_in` of `token_in` through the router, the output is kept by the contract.
+    fn _swap(
+        &self,
+        token_in: AccountId,
+        token_out: AccountId,
+        amount_in: Balance,
+        amount_out_min: Balance,
+    ) -> Result<Vec<Balance>, DcaError>;
+
+    fn _remove_position(&mut self, position_id: PositionId, owner: AccountId);
+
+    fn _emit_position_created_event(
+        &self,
+        _position_id: PositionId,
+        _owner: AccountId,
+        _token_in: AccountId,
+        _token_out: AccountId,
+        _amount_per_interval: Balance,
+        _number_of_intervals: u32,
+        _interval: Timestamp,
+    );
+
+    fn _emit_executed_event(
+        &self,
+        _position_id: PositionId,
+        _keeper: AccountId,
+        _amount_in: Balance,
+        _amount_out: Balance,
+    );
+
+    fn _emit_withdrawn_event(&self, _position_id: PositionId, _amount_out: Balance);
+
+    fn _emit_position_closed_event(
+        &self,
+        _position_id: PositionId,
+        _amount_in_refunded: Balance,
+        _amount_out: Balance,
+    );
+}
+
+impl<T: Storage<data::Data> + Storage<reentrancy_guard::Data>> Dca for T {
+    default fn factory(&self) -> AccountId {
+        self.data::<data::Data>().factory
+    }
+
+    default fn router(&self) -> AccountId {
+        self.data::<data::Data>().router
+    }
+
+    default fn twap_period(&self) -> Timestamp {
+        self.data::<data::Data>().twap_period
+    }
+
+    #[modifiers(non_reentrant)]
+    default fn create_position(
+        &mut self,
+        token_in: AccountId,
+        token_out: AccountId,
+        amount_per_interval: Balance,
+        number_of_intervals: u32,
+        interval: Timestamp,
+        execution_fee: Balance,
+        max_slippage_bps: u16,
+    ) -> Result<PositionId, DcaError> {
+        ensure!(
+            amount_per_interval > 0 && number_of_intervals > 0,
+            DcaError::InsufficientAmount
+        );
+        ensure!(interval > 0, DcaError::InvalidInterval);
+        ensure!(max_slippage_bps <= BPS, DcaError::InvalidSlippage);
+        pair_for_on_chain(&self.data::<data::Data>().factory, token_in, token_out)
+            .ok_or(DcaError::PairNotFound)?;
+
+        let owner = Self::env().caller();
+        let deposit = amount_per_interval
+            .checked_add(execution_fee)
+            .ok_or(DcaError::Arithmetic(MathOp::Add, DcaLocation::Deposit))?
+            .checked_mul(number_of_intervals.into())
+            .ok_or(DcaError::Arithmetic(MathOp::Mul, DcaLocation::Deposit))?;
+        safe_transfer_from(token_in, owner, Self::env().account_id(), deposit)?;
+
+        let position_id = self.data::<data::Data>().next_position_id;
+        self.data::<data::Data>().next_position_id = position_id
+            .checked_add(1)
+            .ok_or(DcaError::Arithmetic(MathOp::Add, DcaLocation::PositionId))?;
+        self.data::<data::Data>().positions.insert(
+            &position_id,
+            &Position {
+                owner,
+                token_in,
+                token_out,
+                amount_per_interval,
+                execution_fee,
+                interval,
+                intervals_remaining: number_of_intervals,
+                next_execution: Self::env().block_timestamp(),
+                max_slippage_bps,
+                amount_out_accumulated: 0,
+            },
+        );
+        let mut owner_positions = self.positions_of(owner);
+        owner_positions.push(position_id);
+        self.data::<data::Data>()
+            .positions_of
+            .insert(&owner, &owner_positions);
+
+        self._emit_position_created_event(
+            position_id,
+            owner,
+            token_in,
+            token_out,
+            amount_per_interval,
+            number_of_intervals,
+            interval,
+        );
+
+        Ok(position_id)
+    }
+
+    #[modifiers(non_reentrant)]
+    default fn execute(&mut self, position_id: PositionId) -> Result<Balance, DcaError> {
+        let mut position = self
+            .data::<data::Data>()
+            .positions
+            .get(&position_id)
+            .ok_or(DcaError::PositionNotFound)?;
+        ensure!(
+            position.intervals_remaining > 0,
+            DcaError::PositionCompleted
+        );
+        let now = Self::env().block_timestamp();
+        ensure!(now >= position.next_execution, DcaError::NotDue);
+
+        let pair = pair_for_on_chain(
+            &self.data::<data::Data>().factory,
+            position.token_in,
+            position.token_out,
+        )
+        .ok_or(DcaError::PairNotFound)?;
+        self._update_observation(pair)?;
+        let observation = self
+            .data::<data::Data>()
+            .observations
+            .get(&pair)
+            .ok_or(DcaError::TwapNotReady)?;
+        let twap = consult(&observation, position.token_in, position.token_out)?;
+        let amount_out_min = min_amount_out(
+            position.amount_per_interval,
+            twap,
+            position.max_slippage_bps,
+        )?;
+
+        let amounts = self._swap(
+            position.token_in,
+            position.token_out,
+            position.amount_per_interval,
+            amount_out_min,
+        )?;
+        let amount_out = amounts[amounts.len() - 1];
+
+        position.intervals_remaining -= 1;
+        position.next_execution =
+            now.checked_add(position.interval)
+                .ok_or(DcaError::Arithmetic(
+                    MathOp::Add,
+                    DcaLocation::NextExecution,
+                ))?;
+        position.amount_out_accumulated = position
+            .amount_out_accumulated
+            .checked_add(amount_out)
+            .ok_or(DcaError::Arithmetic(
+            MathOp::Add,
+            DcaLocation::AmountOutAccumulated,
+        ))?;
+        self.data::<data::Data>()
+            .positions
+            .insert(&position_id, &position);
+
+        let keeper = Self::env().caller();
+        if position.execution_fee > 0 {
+            safe_transfer(position.token_in, keeper, position.execution_fee)?;
+        }
+
+        self._emit_executed_event(
+            position_id,
+            keeper,
+            position.amount_per_interval,
+            amount_out,
+        );
+
+        Ok(amount_out)
+    }
+
+    #[modifiers(non_reentrant)]
+    default fn withdraw(&mut self, position_id: PositionId) -> Result<Balance, DcaError> {
+        let mut position = self
+            .data::<data::Data>()
+            .positions
+            .get(&position_id)
+            .ok_or(DcaError::PositionNotFound)?;
+        ensure!(
+            position.owner == Self::env().caller(),
+            DcaError::CallerIsNotOwner
+        );
+
+        let amount_out = position.amount_out_accumulated;
+        if position.intervals_remaining == 0 {
+            self._remove_position(position_id, position.owner);
+        } else {
+            position.amount_out_accumulated = 0;
+            self.data::<data::Data>()
+                .positions
+                .insert(&position_id, &position);
+        }
+        if amount_out > 0 {
+            safe_transfer(position.token_out, position.owner, amount_out)?;
+        }
+
+        self._emit_withdrawn_event(position_id, amount_out);
+
+        Ok(amount_out)
+    }
+
+    #[modifiers(non_reentrant)]
+    default fn close_position(
+        &mut self,
+        position_id: PositionId,
+    ) -> Result<(Balance, Balance), DcaError> {
+        let position = self
+            .data::<data::Data>()
+            .positions
+            .get(&position_id)
+            .ok_or(DcaError::PositionNotFound)?;
+        ensure!(
+            position.owner == Self::env().caller(),
+            DcaError::CallerIsNotOwner
+        );
+
+        let amount_in_refunded = position
+            .amount_per_interval
+            .checked_add(position.execution_fee)
+            .ok_or(DcaError::Arithmetic(MathOp::Add, DcaLocation::Deposit))?
+            .checked_mul(position.intervals_remaining.into())
+            .ok_or(DcaError::Arithmetic(MathOp::Mul, DcaLocation::Deposit))?;
+        self._remove_position(position_id, position.owner);
+        if amount_in_refunded > 0 {
+            safe_transfer(position.token_in, position.owner, amount_in_refunded)?;
+        }
+        if position.amount_out_accumulated > 0 {
+            safe_transfer(
+                position.token_out,
+                position.owner,
+                position.amount_out_accumulated,
+            )?;
+        }
+
+        self._emit_position_closed_event(
+            position_id,
+            amount_in_refunded,
+            position.amount_out_accumulated,
+        );
+
+        Ok((amount_in_refunded, position.amount_out_accumulated))
+    }
+
+    default fn update_observation(&mut self, pair: AccountId) -> Result<(), DcaError> {
+        ensure!(self._update_observation(pair)?, DcaError::ObservationNotDue);
+        Ok(())
+    }
+
+    default fn observation(&self, pair: AccountId) -> Option<Observation> {
+        self.data::<data::Data>().observations.get(&pair)
+    }
+
+    default fn get_position(&self, position_id: PositionId) -> Option<Position> {
+        self.data::<data::Data>().positions.get(&position_id)
+    }
+
+    default fn positions_of(&self, owner: AccountId) -> Vec<PositionId> {
+        self.data::<data::Data>()
+            .positions_of
+            .get(&owner)
+            .unwrap_or_default()
+    }
+
+    default fn next_position_id(&self) -> PositionId {
+        self.data::<data::Data>().next_position_id
+    }
+}
+
+impl<T: Storage<data::Data>> Internal for T {
+    default fn _update_observation(&mut self, pair: AccountId) -> Result<bool, DcaError> {
+        let last = self.data::<data::Data>().observations.get(&pair);
+        match update_observation(
+            &pair,
+            last,
+            self.data::<data::Data>().twap_period,
+            Self::env().block_timestamp(),
+        )? {
+            Some(observation) => {
+                self.data::<data::Data>()
+                    .observations
+                    .insert(&pair, &observation);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    default fn _swap(
+        &self,
+        token_in: AccountId,
+        token_out: AccountId,
+        amount_in: Balance,
+        amount_out_min: Balance,
+    ) -> Result<Vec<Balance>, DcaError> {
+        let router = self.data::<data::Data>().router;
+        PSP22Ref::approve(&token_in, router, amount_in)?;
+        // The pair sends the output to this contract, which must allow the reentry.
+        Ok(call_result(
+            router,
+            RouterRef::swap_exact_tokens_for_tokens_builder(
+                &router,
+                amount_in,
+                amount_out_min,
+                vec![token_in, token_out],
+                Self::env().account_id(),
+                Self::env().block_timestamp(),
+            )
+            .call_flags(CallFlags::default().set_allow_reentry(true))
+            .try_invoke(),
+        )??)
+    }
+
+    default fn _remove_position(&mut self, position_id: PositionId, owner: AccountId) {
+        self.data::<data::Data>().positions.remove(&position_id);
+        let mut owner_positions = self
+            .data::<data::Data>()
+            .positions_of
+            .get(&owner)
+            .unwrap_or_default();
+        owner_positions.retain(|id| *id != position_id);
+        self.data::<data::Data>()
+            .positions_of
+            .insert(&owner, &owner_positions);
+    }
+
+    default fn _emit_position_created_event(
+        &self,
+        _position_id: PositionId,
+        _owner: AccountId,
+        _token_in: AccountId,
+        _token_out: AccountId,
+        _amount_per_interval: Balance,
+        _number_of_intervals: u32,
+        _interval: Timestamp,
+    ) {
+    }
+
+    default fn _emit_executed_event(
+        &self,
+        _position_id: PositionId,
+        _keeper: AccountId,
+        _amount_in: Balance,
+        _amount_out: Balance,
+    ) {
+    }
+
+    default fn _emit_withdrawn_event(&self, _position_id: PositionId, _amount_out: Balance) {}
+
+    default fn _emit_position_closed_event(
+        &self,
+        _position_id: PositionId,
+        _amount_in_refunded: Balance,
+        _amount_out: Balance,
+    ) {
+    }
+}
@@ -1,7 +1,9 @@
-use ink::{
-    prelude::vec::Vec,
-    primitives::Hash,
+use crate::traits::factory::{
+    PairFeeConfig,
+    PoolTypeId,
+    PoolTypeInfo,
 };
+use ink::prelude::vec::Vec;
 use openbrush::{
     storage::Mapping,
     traits::{
@@ -17,9 +19,15 @@ pub const STORAGE_KEY: u32 = openbrush::storage_unique_key!(Data);
 pub struct Data {
     pub fee_to: AccountId,
     pub fee_to_setter: AccountId,
-    pub get_pair: Mapping<(AccountId, AccountId), AccountId>,
     pub all_pairs: Vec<AccountId>,
-    pub pair_contract_code_hash: Hash,
+    pub pool_types: Mapping<PoolTypeId, PoolTypeInfo>,
+    pub pool_type_ids: Vec<PoolTypeId>,
+    pub pool_type_of: Mapping<AccountId, PoolTypeId>,
+    pub get_pool: Mapping<(PoolTypeId, AccountId, AccountId), AccountId>,
+    pub pair_creator: Mapping<AccountId, AccountId>,
+    pub pair_fee_configs: Mapping<AccountId, PairFeeConfig>,
+    /// Routers allowed to swap with a discounted fee through `Pair::swap_with_fee`.
+    pub trusted_routers: Mapping<AccountId, ()>,
 }
 
 impl Default for Data {
@@ -27,9 +35,14 @@ impl Default for Data {
         Self {
             fee_to: ZERO_ADDRESS.into(),
             fee_to_setter: ZERO_ADDRESS.into(),
-            get_pair: Default::default(),
             all_pairs: Vec::new(),
-            pair_contract_code_hash: Default::default(),
+            pool_types: Default::default(),
+            pool_type_ids: Vec::new(),
+            pool_type_of: Default::default(),
+            get_pool: Default::default(),
+            pair_creator: Default::default(),
+            pair_fee_configs: Default::default(),
+            trusted_routers: Default::default(),
         }
     }
 }
@@ -1,11 +1,30 @@
-use crate::traits::pair::PairRef;
 pub use crate::{
     ensure,
     impls::factory::*,
     traits::factory::*,
 };
+use crate::{
+    helpers::math::BPS,
+    traits::{
+        errors::call_result,
+        lp_rewards::LpRewardsRef,
+        pair::{
+            DynamicFeeConfig,
+            PairError,
+            PairRef,
+            ProtocolFeeMode,
+        },
+    },
+};
 use ink::{
-    env::hash::Blake2x256,
+    env::{
+        hash::Blake2x256,
+        CallFlags,
+    },
+    prelude::{
+        vec,
+        vec::Vec,
+    },
     primitives::Hash,
 };
 use openbrush::{
@@ -14,7 +33,9 @@ use openbrush::{
     traits::{
         AccountId,
         AccountIdExt,
+        Balance,
         Storage,
+        ZERO_ADDRESS,
     },
 };
 
@@ -35,7 +56,11 @@ where
     }
 
     default fn pair_contract_code_hash(&self) -> Hash {
-        self.data::<data::Data>().pair_contract_code_hash
+        self.data::<data::Data>()
+            .pool_types
+            .get(&CONSTANT_PRODUCT_POOL_TYPE)
+            .map(|info| info.code_hash)
+            .unwrap_or_default()
     }
 
     default fn create_pair(
@@ -43,42 +68,281 @@ where
         token_a: AccountId,
         token_b: AccountId,
     ) -> Result<AccountId, FactoryError> {
-        ensure!(token_a != token_b, FactoryError::IdenticalAddresses);
-        let token_pair = if token_a < token_b {
-            (token_a, token_b)
-        } else {
-            (token_b, token_a)
-        };
-        ensure!(!token_pair.0.is_zero(), FactoryError::ZeroAddress);
+        self.create_pool(
+            CONSTANT_PRODUCT_POOL_TYPE,
+            vec![token_a, token_b],
+            Vec::new(),
+        )
+    }
+
+    default fn create_pool(
+        &mut self,
+        pool_type: PoolTypeId,
+        tokens: Vec<AccountId>,
+        params: Vec<u8>,
+    ) -> Result<AccountId, FactoryError> {
+        let creator = Self::env().caller();
+        create_pool_for(self, pool_type, tokens, params, creator)
+    }
+
+    default fn create_pair_for(
+        &mut self,
+        token_a: AccountId,
+        token_b: AccountId,
+        creator: AccountId,
+    ) -> Result<AccountId, FactoryError> {
         ensure!(
-            self.data::<data::Data>()
-                .get_pair
-                .get(&token_pair)
-                .is_none(),
-            FactoryError::PairExists
+            self.is_trusted_router(Self::env().caller()),
+            FactoryError::CallerIsNotTrustedRouter
         );
+        create_pool_for(
+            self,
+            CONSTANT_PRODUCT_POOL_TYPE,
+            vec![token_a, token_b],
+            Vec::new(),
+            creator,
+        )
+    }
+
+    #[modifiers(only_fee_setter)]
+    default fn register_pool_type(
+        &mut self,
+        pool_type: PoolTypeId,
+        code_hash: Hash,
+        constructor: ConstructorKind,
+    ) -> Result<(), FactoryError> {
+        insert_pool_type(self, pool_type, code_hash, constructor);
+        Ok(())
+    }
+
+    #[modifiers(only_fee_setter)]
+    default fn set_pool_type_enabled(
+        &mut self,
+        pool_type: PoolTypeId,
+        enabled: bool,
+    ) -> Result<(), FactoryError> {
+        let mut pool_type_info = self
+            .data::<data::Data>()
+            .pool_types
+            .get(&pool_type)
+            .ok_or(FactoryError::PoolTypeNotFound)?;
+        pool_type_info.enabled = enabled;
+        self.data::<data::Data>()
+            .pool_types
+            .insert(&pool_type, &pool_type_info);
+        Ok(())
+    }
 
-        let salt = Self::env().hash_encoded::<Blake2x256, _>(&token_pair);
-        let pair_contract = self._instantiate_pair(salt.as_ref())?;
+    default fn pool_type(&self, pool_type: PoolTypeId) -> Option<PoolTypeInfo> {
+        self.data::<data::Data>().pool_types.get(&pool_type)
+    }
 
-        PairRef::initialize(&pair_contract, token_pair.0, token_pair.1)?;
+    default fn pool_types(&self) -> Vec<PoolTypeId> {
+        self.data::<data::Data>().pool_type_ids.clone()
+    }
+
+    default fn pool_type_of(&self, pool: AccountId) -> Option<PoolTypeId> {
+        self.data::<data::Data>().pool_type_of.get(&pool)
+    }
 
+    default fn get_pool(
+        &self,
+        pool_type: PoolTypeId,
+        token_a: AccountId,
+        token_b: AccountId,
+    ) -> Option<AccountId> {
         self.data::<data::Data>()
-            .get_pair
-            .insert(&(token_pair.0, token_pair.1), &pair_contract);
+            .get_pool
+            .get(&(pool_type, token_a, token_b))
+    }
+
+    #[modifiers(only_fee_setter)]
+    default fn set_lp_rewards(
+        &mut self,
+        pool: AccountId,
+        reward_token: AccountId,
+        reward_distributor: AccountId,
+    ) -> Result<(), FactoryError> {
+        ensure!(
+            self.data::<data::Data>().pool_type_of.get(&pool).is_some(),
+            FactoryError::PoolNotFound
+        );
+        LpRewardsRef::set_reward_config(&pool, reward_token, reward_distributor)?;
+        Ok(())
+    }
+
+    default fn pair_creator(&self, pair: AccountId) -> Option<AccountId> {
+        self.data::<data::Data>().pair_creator.get(&pair)
+    }
+
+    default fn set_pair_creator(
+        &mut self,
+        pair: AccountId,
+        creator: AccountId,
+    ) -> Result<(), FactoryError> {
+        let current = self
+            .data::<data::Data>()
+            .pair_creator
+            .get(&pair)
+            .ok_or(FactoryError::PoolNotFound)?;
+        let caller = Self::env().caller();
+        ensure!(
+            caller == current || caller == self.data::<data::Data>().fee_to_setter,
+            FactoryError::CallerIsNotCreator
+        );
         self.data::<data::Data>()
-            .get_pair
-            .insert(&(token_pair.1, token_pair.0), &pair_contract);
-        self.data::<data::Data>().all_pairs.push(pair_contract);
-
-        self._emit_create_pair_event(
-            token_pair.0,
-            token_pair.1,
-            pair_contract,
-            self.all_pairs_length(),
+            .pair_creator
+            .insert(&pair, &creator);
+        Ok(())
+    }
+
+    default fn pair_fee_config(&self, pair: AccountId) -> Option<PairFeeConfig> {
+        self.data::<data::Data>().pair_fee_configs.get(&pair)
+    }
+
+    #[modifiers(only_fee_setter)]
+    default fn set_pair_fee_config(
+        &mut self,
+        pair: AccountId,
+        config: Option<PairFeeConfig>,
+    ) -> Result<(), FactoryError> {
+        ensure!(
+            self.data::<data::Data>().pool_type_of.get(&pair).is_some(),
+            FactoryError::PoolNotFound
+        );
+        match config {
+            Some(config) => {
+                ensure!(
+                    config.creator_share_bps <= BPS,
+                    FactoryError::InvalidCreatorShare
+                );
+                self.data::<data::Data>()
+                    .pair_fee_configs
+                    .insert(&pair, &config);
+            }
+            None => self.data::<data::Data>().pair_fee_configs.remove(&pair),
+        }
+        Ok(())
+    }
+
+    default fn protocol_fee_of(&self, pair: AccountId) -> ProtocolFee {
+        let global_fee_to = self.data::<data::Data>().fee_to;
+        let creator = self
+            .data::<data::Data>()
+            .pair_creator
+            .get(&pair)
+            .unwrap_or(ZERO_ADDRESS.into());
+        match self.data::<data::Data>().pair_fee_configs.get(&pair) {
+            Some(config) => {
+                let fee_to = if !config.enabled {
+                    ZERO_ADDRESS.into()
+                } else if config.fee_to.is_zero() {
+                    global_fee_to
+                } else {
+                    config.fee_to
+                };
+                ProtocolFee {
+                    fee_to,
+                    creator,
+                    creator_share_bps: config.creator_share_bps,
+                }
+            }
+            None => {
+                ProtocolFee {
+                    fee_to: global_fee_to,
+                    creator,
+                    creator_share_bps: 0,
+                }
+            }
+        }
+    }
+
+    #[modifiers(only_fee_setter)]
+    default fn set_pair_dynamic_fee(
+        &mut self,
+        pair: AccountId,
+        config: Option<DynamicFeeConfig>,
+    ) -> Result<(), FactoryError> {
+        ensure!(
+            self.data::<data::Data>().pool_type_of.get(&pair).is_some(),
+            FactoryError::PoolNotFound
+        );
+        if let Some(config) = config {
+            ensure!(
+                config.is_valid(),
+                FactoryError::PairError(PairError::InvalidFeeConfig)
+            );
+        }
+        Ok(PairRef::set_dynamic_fee_config(&pair, config)?)
+    }
+
+    #[modifiers(only_fee_setter)]
+    default fn set_pair_log_price_oracle(
+        &mut self,
+        pair: AccountId,
+        enabled: bool,
+    ) -> Result<(), FactoryError> {
+        ensure!(
+            self.data::<data::Data>().pool_type_of.get(&pair).is_some(),
+            FactoryError::PoolNotFound
+        );
+        Ok(PairRef::set_log_price_oracle_enabled(&pair, enabled)?)
+    }
+
+    #[modifiers(only_fee_setter)]
+    default fn set_protocol_fee_mode(
+        &mut self,
+        pair: AccountId,
+        mode: ProtocolFeeMode,
+    ) -> Result<(), FactoryError> {
+        ensure!(
+            self.data::<data::Data>().pool_type_of.get(&pair).is_some(),
+            FactoryError::PoolNotFound
+        );
+        // The pair settles the fee accrued as liquidity, reading back `protocol_fee_of`.
+        Ok(call_result(
+            pair,
+            PairRef::set_protocol_fee_mode_builder(&pair, mode)
+                .call_flags(CallFlags::default().set_allow_reentry(true))
+                .try_invoke(),
+        )??)
+    }
+
+    default fn collect_protocol_fees(
+        &mut self,
+        pair: AccountId,
+    ) -> Result<(Balance, Balance), FactoryError> {
+        ensure!(
+            self.data::<data::Data>().pool_type_of.get(&pair).is_some(),
+            FactoryError::PoolNotFound
         );
+        let protocol_fee = self.protocol_fee_of(pair);
+        ensure!(!protocol_fee.fee_to.is_zero(), FactoryError::ProtocolFeeOff);
+        Ok(PairRef::collect_protocol_fees(&pair, protocol_fee)?)
+    }
+
+    default fn is_trusted_router(&self, router: AccountId) -> bool {
+        self.data::<data::Data>()
+            .trusted_routers
+            .get(&router)
+            .is_some()
+    }
 
-        Ok(pair_contract)
+    #[modifiers(only_fee_setter)]
+    default fn set_trusted_router(
+        &mut self,
+        router: AccountId,
+        trusted: bool,
+    ) -> Result<(), FactoryError> {
+        ensure!(!router.is_zero(), FactoryError::ZeroAddress);
+        if trusted {
+            self.data::<data::Data>()
+                .trusted_routers
+                .insert(&router, &());
+        } else {
+            self.data::<data::Data>().trusted_routers.remove(&router);
+        }
+        Ok(())
     }
 
     #[modifiers(only_fee_setter)]
@@ -102,7 +366,7 @@ where
     }
 
     default fn get_pair(&self, token_a: AccountId, token_b: AccountId) -> Option<AccountId> {
-        self.data::<data::Data>().get_pair.get(&(token_a, token_b))
+        self.get_pool(CONSTANT_PRODUCT_POOL_TYPE, token_a, token_b)
     }
 }
 
@@ -113,9 +377,114 @@ pub trait Internal {
         _token_1: AccountId,
         _pair: AccountId,
         _pair_len: u64,
+        _pool_type: PoolTypeId,
+    );
+
+    /// Instantiates a pool of `pool_type`. Implemented by the factory contract, which is the
+    /// only one depending on the pool contracts.
+    fn _instantiate_pool(
+        &mut self,
+        pool_type: &PoolTypeInfo,
+        salt_bytes: &[u8],
+        params: Vec<u8>,
+    ) -> Result<AccountId, FactoryError>;
+}
+
+/// Registers or replaces `pool_type`, enabled.
+pub fn insert_pool_type<T: Storage<data::Data>>(
+    instance: &mut T,
+    pool_type: PoolTypeId,
+    code_hash: Hash,
+    constructor: ConstructorKind,
+) {
+    if instance
+        .data::<data::Data>()
+        .pool_types
+        .get(&pool_type)
+        .is_none()
+    {
+        instance.data::<data::Data>().pool_type_ids.push(pool_type);
+    }
+    instance.data::<data::Data>().pool_types.insert(
+        &pool_type,
+        &PoolTypeInfo {
+            code_hash,
+            constructor,
+            enabled: true,
+        },
+    );
+}
+
+/// Instantiates a pool of a registered and enabled `pool_type` for `tokens`, recording
+/// `creator` as its creator.
+pub fn create_pool_for<T: Storage<data::Data> + Internal>(
+    instance: &mut T,
+    pool_type: PoolTypeId,
+    tokens: Vec<AccountId>,
+    params: Vec<u8>,
+    creator: AccountId,
+) -> Result<AccountId, FactoryError> {
+    ensure!(tokens.len() == 2, FactoryError::InvalidTokensLength);
+    let (token_a, token_b) = (tokens[0], tokens[1]);
+    ensure!(token_a != token_b, FactoryError::IdenticalAddresses);
+    let token_pair = if token_a < token_b {
+        (token_a, token_b)
+    } else {
+        (token_b, token_a)
+    };
+    ensure!(!token_pair.0.is_zero(), FactoryError::ZeroAddress);
+    let pool_type_info = instance
+        .data::<data::Data>()
+        .pool_types
+        .get(&pool_type)
+        .ok_or(FactoryError::PoolTypeNotFound)?;
+    ensure!(pool_type_info.enabled, FactoryError::PoolTypeDisabled);
+    ensure!(
+        instance
+            .data::<data::Data>()
+            .get_pool
+            .get(&(pool_type, token_pair.0, token_pair.1))
+            .is_none(),
+        FactoryError::PairExists
+    );
+
+    let salt = if pool_type == CONSTANT_PRODUCT_POOL_TYPE {
+        T::env().hash_encoded::<Blake2x256, _>(&token_pair)
+    } else {
+        T::env().hash_encoded::<Blake2x256, _>(&(pool_type, token_pair))
+    };
+    let pair_contract = instance._instantiate_pool(&pool_type_info, salt.as_ref(), params)?;
+
+    PairRef::initialize(&pair_contract, token_pair.0, token_pair.1)?;
+
+    instance
+        .data::<data::Data>()
+        .get_pool
+        .insert(&(pool_type, token_pair.0, token_pair.1), &pair_contract);
+    instance
+        .data::<data::Data>()
+        .get_pool
+        .insert(&(pool_type, token_pair.1, token_pair.0), &pair_contract);
+    instance
+        .data::<data::Data>()
+        .pool_type_of
+        .insert(&pair_contract, &pool_type);
+    instance
+        .data::<data::Data>()
+        .pair_creator
+        .insert(&pair_contract, &creator);
+    instance.data::<data::Data>().all_pairs.push(pair_contract);
+    let pair_len = instance.data::<data::Data>().all_pairs.len() as u64;
+
+    instance._emit_create_pair_event(
+        token_pair.0,
+        token_pair.1,
+        pair_contract,
+        pair_len,
+        pool_type,
     );
 
-    fn _instantiate_pair(&mut self, salt_bytes: &[u8]) -> Result<AccountId, FactoryError>;
+    Ok(pair_contract)
 }
 
 #[modifier_definition]
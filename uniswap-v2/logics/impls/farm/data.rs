@@ -0,0 +1,41 @@
+use crate::traits::farm::{
+    PoolId,
+    PoolInfo,
+    UserInfo,
+};
+use openbrush::{
+    storage::Mapping,
+    traits::{
+        AccountId,
+        Balance,
+        ZERO_ADDRESS,
+    },
+};
+
+pub const STORAGE_KEY: u32 = openbrush::storage_unique_key!(Data);
+
+#[derive(Debug)]
+#[openbrush::upgradeable_storage(STORAGE_KEY)]
+pub struct Data {
+    pub reward_token: AccountId,
+    pub reward_per_second: Balance,
+    pub total_alloc_point: u32,
+    pub pools: Mapping<PoolId, PoolInfo>,
+    pub pool_length: PoolId,
+    pub pool_id_of: Mapping<AccountId, PoolId>,
+    pub users: Mapping<(PoolId, AccountId), UserInfo>,
+}
+
+impl Default for Data {
+    fn default() -> Self {
+        Self {
+            reward_token: ZERO_ADDRESS.into(),
+            reward_per_second: 0,
+            total_alloc_point: 0,
+            pools: Default::default(),
+            pool_length: 0,
+            pool_id_of: Default::default(),
+            users: Default::default(),
+        }
+    }
+}
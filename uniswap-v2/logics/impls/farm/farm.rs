@@ -0,0 +1,540 @@
+use crate::{
+    ensure,
+    helpers::{
+        math::casted_mul,
+        transfer_helper::{
+            safe_transfer,
+            safe_transfer_from,
+        },
+    },
+    traits::{
+        errors::MathOp,
+        types::WrappedU256,
+    },
+};
+pub use crate::{
+    impls::farm::*,
+    traits::farm::*,
+};
+use openbrush::{
+    contracts::{
+        ownable::*,
+        reentrancy_guard::*,
+        traits::psp22::PSP22Ref,
+    },
+    modifiers,
+    traits::{
+        AccountId,
+        Balance,
+        Storage,
+        Timestamp,
+    },
+};
+use primitive_types::U256;
+
+/// Precision of `PoolInfo::acc_reward_per_share`.
+pub const ACC_REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+pub trait Internal {
+    /// Returns `pool` with its rewards accrued up to `now`, without writing it.
+    fn _accrue(&self, pool: PoolInfo, now: Timestamp) -> Result<PoolInfo, FarmError>;
+
+    fn _update_pool(&mut self, pool_id: PoolId) -> Result<PoolInfo, FarmError>;
+
+    fn _mass_update_pools(&mut self) -> Result<(), FarmError>;
+
+    /// Updates the pool, pays the pending rewards of `user` and applies `deposit`
+    /// and `withdrawal` to its stake. Returns the rewards paid.
+    fn _update_user(
+        &mut self,
+        pool_id: PoolId,
+        user: AccountId,
+        deposit: Balance,
+        withdrawal: Balance,
+    ) -> Result<Balance, FarmError>;
+
+    fn _emit_pool_added_event(&self, _pool_id: PoolId, _lp_token: AccountId, _alloc_point: u32);
+
+    fn _emit_pool_set_event(&self, _pool_id: PoolId, _alloc_point: u32);
+
+    fn _emit_reward_per_second_set_event(&self, _reward_per_second: Balance);
+
+    fn _emit_deposit_event(&self, _user: AccountId, _pool_id: PoolId, _amount: Balance);
+
+    fn _emit_withdraw_event(&self, _user: AccountId, _pool_id: PoolId, _amount: Balance);
+
+    fn _emit_harvest_event(&self, _user: AccountId, _pool_id: PoolId, _amount: Balance);
+
+    fn _emit_emergency_withdraw_event(&self, _user: AccountId, _pool_id: PoolId, _amount: Balance);
+}
+
+/// Returns the rewards accrued by `amount` staked LP tokens since the pool was added.
+pub fn accrued_reward(
+    amount: Balance,
+    acc_reward_per_share: WrappedU256,
+) -> Result<Balance, FarmError> {
+    let acc_reward_per_share: U256 = acc_reward_per_share.into();
+    U256::from(amount)
+        .checked_mul(acc_reward_per_share)
+        .ok_or(FarmError::Arithmetic(
+            MathOp::Mul,
+            FarmLocation::AccruedReward,
+        ))?
+        .checked_div(ACC_REWARD_PRECISION.into())
+        .ok_or(FarmError::Arithmetic(
+            MathOp::Div,
+            FarmLocation::AccruedReward,
+        ))?
+        .try_into()
+        .map_err(|_| FarmError::Arithmetic(MathOp::Cast, FarmLocation::AccruedReward))
+}
+
+/// Returns `acc_reward_per_share` grown by the rewards emitted to a pool over `elapsed`
+/// milliseconds, `reward_per_second` being shared by allocation points.
+pub fn accrue_reward_per_share(
+    acc_reward_per_share: WrappedU256,
+    elapsed: Timestamp,
+    reward_per_second: Balance,
+    alloc_point: u32,
+    total_alloc_point: u32,
+    total_staked: Balance,
+) -> Result<WrappedU256, FarmError> {
+    if total_staked == 0 || total_alloc_point == 0 {
+        return Ok(acc_reward_per_share)
+    }
+    // Timestamps are in milliseconds.
+    let reward = casted_mul(elapsed.into(), reward_per_second)
+        .checked_mul(alloc_point.into())
+        .ok_or(FarmError::Arithmetic(
+            MathOp::Mul,
+            FarmLocation::AccRewardPerShare,
+        ))?
+        / (U256::from(1000) * U256::from(total_alloc_point));
+    Ok(reward
+        .checked_mul(ACC_REWARD_PRECISION.into())
+        .ok_or(FarmError::Arithmetic(
+            MathOp::Mul,
+            FarmLocation::AccRewardPerShare,
+        ))?
+        .checked_div(total_staked.into())
+        .ok_or(FarmError::Arithmetic(
+            MathOp::Div,
+            FarmLocation::AccRewardPerShare,
+        ))?
+        .checked_add(acc_reward_per_share.into())
+        .ok_or(FarmError::Arithmetic(
+            MathOp::Add,
+            FarmLocation::AccRewardPerShare,
+        ))?
+        .into())
+}
+
+/// Applies `deposit` and `withdrawal` to the stake of `user_info`. Returns the new stake, its
+/// reward debt set at `acc_reward_per_share`, and the rewards pending before the update.
+pub fn update_stake(
+    mut user_info: UserInfo,
+    acc_reward_per_share: WrappedU256,
+    deposit: Balance,
+    withdrawal: Balance,
+) -> Result<(UserInfo, Balance), FarmError> {
+    ensure!(user_info.amount >= withdrawal, FarmError::InsufficientStake);
+    let pending = accrued_reward(user_info.amount, acc_reward_per_share)?
+        .checked_sub(user_info.reward_debt)
+        .ok_or(FarmError::Arithmetic(
+            MathOp::Sub,
+            FarmLocation::PendingReward,
+        ))?;
+    user_info.amount = user_info
+        .amount
+        .checked_add(deposit)
+        .ok_or(FarmError::Arithmetic(MathOp::Add, FarmLocation::Stake))?
+        - withdrawal;
+    user_info.reward_debt = accrued_reward(user_info.amount, acc_reward_per_share)?;
+    Ok((user_info, pending))
+}
+
+impl<T: Storage<data::Data> + Storage<ownable::Data> + Storage<reentrancy_guard::Data>> Farm for T {
+    default fn reward_token(&self) -> AccountId {
+        self.data::<data::Data>().reward_token
+    }
+
+    default fn reward_per_second(&self) -> Balance {
+        self.data::<data::Data>().reward_per_second
+    }
+
+    default fn total_alloc_point(&self) -> u32 {
+        self.data::<data::Data>().total_alloc_point
+    }
+
+    default fn pool_length(&self) -> PoolId {
+        self.data::<data::Data>().pool_length
+    }
+
+    default fn pool_info(&self, pool_id: PoolId) -> Option<PoolInfo> {
+        self.data::<data::Data>().pools.get(&pool_id)
+    }
+
+    default fn pool_id_of(&self, lp_token: AccountId) -> Option<PoolId> {
+        self.data::<data::Data>().pool_id_of.get(&lp_token)
+    }
+
+    default fn user_info(&self, pool_id: PoolId, user: AccountId) -> UserInfo {
+        self.data::<data::Data>()
+            .users
+            .get(&(pool_id, user))
+            .unwrap_or_default()
+    }
+
+    default fn pending_reward(
+        &self,
+        pool_id: PoolId,
+        user: AccountId,
+    ) -> Result<Balance, FarmError> {
+        let pool = self
+            .data::<data::Data>()
+            .pools
+            .get(&pool_id)
+            .ok_or(FarmError::PoolNotFound)?;
+        let pool = self._accrue(pool, Self::env().block_timestamp())?;
+        let user_info = self.user_info(pool_id, user);
+        accrued_reward(user_info.amount, pool.acc_reward_per_share)?
+            .checked_sub(user_info.reward_debt)
+            .ok_or(FarmError::Arithmetic(
+                MathOp::Sub,
+                FarmLocation::PendingReward,
+            ))
+    }
+
+    #[modifiers(only_owner)]
+    default fn add_pool(
+        &mut self,
+        lp_token: AccountId,
+        alloc_point: u32,
+    ) -> Result<PoolId, FarmError> {
+        ensure!(
+            lp_token != self.data::<data::Data>().reward_token,
+            FarmError::InvalidLpToken
+        );
+        ensure!(
+            self.data::<data::Data>()
+                .pool_id_of
+                .get(&lp_token)
+                .is_none(),
+            FarmError::PoolAlreadyExists
+        );
+        self._mass_update_pools()?;
+
+        let pool_id = self.data::<data::Data>().pool_length;
+        self.data::<data::Data>().pool_length = pool_id
+            .checked_add(1)
+            .ok_or(FarmError::Arithmetic(MathOp::Add, FarmLocation::PoolId))?;
+        self.data::<data::Data>().total_alloc_point = self
+            .data::<data::Data>()
+            .total_alloc_point
+            .checked_add(alloc_point)
+            .ok_or(FarmError::Arithmetic(
+                MathOp::Add,
+                FarmLocation::TotalAllocPoint,
+            ))?;
+        self.data::<data::Data>().pools.insert(
+            &pool_id,
+            &PoolInfo {
+                lp_token,
+                alloc_point,
+                last_reward_time: Self::env().block_timestamp(),
+                acc_reward_per_share: Default::default(),
+                total_staked: 0,
+            },
+        );
+        self.data::<data::Data>()
+            .pool_id_of
+            .insert(&lp_token, &pool_id);
+
+        self._emit_pool_added_event(pool_id, lp_token, alloc_point);
+
+        Ok(pool_id)
+    }
+
+    #[modifiers(only_owner)]
+    default fn set_pool(&mut self, pool_id: PoolId, alloc_point: u32) -> Result<(), FarmError> {
+        self._mass_update_pools()?;
+        let mut pool = self
+            .data::<data::Data>()
+            .pools
+            .get(&pool_id)
+            .ok_or(FarmError::PoolNotFound)?;
+
+        self.data::<data::Data>().total_alloc_point = self
+            .data::<data::Data>()
+            .total_alloc_point
+            .checked_sub(pool.alloc_point)
+            .ok_or(FarmError::Arithmetic(
+                MathOp::Sub,
+                FarmLocation::TotalAllocPoint,
+            ))?
+            .checked_add(alloc_point)
+            .ok_or(FarmError::Arithmetic(
+                MathOp::Add,
+                FarmLocation::TotalAllocPoint,
+            ))?;
+        pool.alloc_point = alloc_point;
+        self.data::<data::Data>().pools.insert(&pool_id, &pool);
+
+        self._emit_pool_set_event(pool_id, alloc_point);
+
+        Ok(())
+    }
+
+    #[modifiers(only_owner)]
+    default fn set_reward_per_second(
+        &mut self,
+        reward_per_second: Balance,
+    ) -> Result<(), FarmError> {
+        self._mass_update_pools()?;
+        self.data::<data::Data>().reward_per_second = reward_per_second;
+
+        self._emit_reward_per_second_set_event(reward_per_second);
+
+        Ok(())
+    }
+
+    default fn update_pool(&mut self, pool_id: PoolId) -> Result<(), FarmError> {
+        self._update_pool(pool_id)?;
+        Ok(())
+    }
+
+    default fn mass_update_pools(&mut self) -> Result<(), FarmError> {
+        self._mass_update_pools()
+    }
+
+    #[modifiers(non_reentrant)]
+    default fn deposit(&mut self, pool_id: PoolId, amount: Balance) -> Result<(), FarmError> {
+        let user = Self::env().caller();
+        self._update_user(pool_id, user, amount, 0)?;
+        if amount > 0 {
+            let lp_token = self
+                .pool_info(pool_id)
+                .ok_or(FarmError::PoolNotFound)?
+                .lp_token;
+            safe_transfer_from(lp_token, user, Self::env().account_id(), amount)?;
+        }
+
+        self._emit_deposit_event(user, pool_id, amount);
+
+        Ok(())
+    }
+
+    #[modifiers(non_reentrant)]
+    default fn withdraw(&mut self, pool_id: PoolId, amount: Balance) -> Result<(), FarmError> {
+        let user = Self::env().caller();
+        self._update_user(pool_id, user, 0, amount)?;
+        if amount > 0 {
+            let lp_token = self
+                .pool_info(pool_id)
+                .ok_or(FarmError::PoolNotFound)?
+                .lp_token;
+            safe_transfer(lp_token, user, amount)?;
+        }
+
+        self._emit_withdraw_event(user, pool_id, amount);
+
+        Ok(())
+    }
+
+    #[modifiers(non_reentrant)]
+    default fn harvest(&mut self, pool_id: PoolId) -> Result<Balance, FarmError> {
+        self._update_user(pool_id, Self::env().caller(), 0, 0)
+    }
+
+    #[modifiers(non_reentrant)]
+    default fn emergency_withdraw(&mut self, pool_id: PoolId) -> Result<Balance, FarmError> {
+        let user = Self::env().caller();
+        let mut pool = self
+            .data::<data::Data>()
+            .pools
+            .get(&pool_id)
+            .ok_or(FarmError::PoolNotFound)?;
+        let amount = self.user_info(pool_id, user).amount;
+        ensure!(amount > 0, FarmError::InsufficientStake);
+
+        pool.total_staked = pool
+            .total_staked
+            .checked_sub(amount)
+            .ok_or(FarmError::Arithmetic(MathOp::Sub, FarmLocation::Stake))?;
+        self.data::<data::Data>().pools.insert(&pool_id, &pool);
+        self.data::<data::Data>().users.remove(&(pool_id, user));
+        safe_transfer(pool.lp_token, user, amount)?;
+
+        self._emit_emergency_withdraw_event(user, pool_id, amount);
+
+        Ok(amount)
+    }
+}
+
+impl<T: Storage<data::Data>> Internal for T {
+    default fn _accrue(&self, mut pool: PoolInfo, now: Timestamp) -> Result<PoolInfo, FarmError> {
+        if now <= pool.last_reward_time {
+            return Ok(pool)
+        }
+        pool.acc_reward_per_share = accrue_reward_per_share(
+            pool.acc_reward_per_share,
+            now - pool.last_reward_time,
+            self.data::<data::Data>().reward_per_second,
+            pool.alloc_point,
+            self.data::<data::Data>().total_alloc_point,
+            pool.total_staked,
+        )?;
+        pool.last_reward_time = now;
+        Ok(pool)
+    }
+
+    default fn _update_pool(&mut self, pool_id: PoolId) -> Result<PoolInfo, FarmError> {
+        let pool = self
+            .data::<data::Data>()
+            .pools
+            .get(&pool_id)
+            .ok_or(FarmError::PoolNotFound)?;
+        let pool = self._accrue(pool, Self::env().block_timestamp())?;
+        self.data::<data::Data>().pools.insert(&pool_id, &pool);
+        Ok(pool)
+    }
+
+    default fn _mass_update_pools(&mut self) -> Result<(), FarmError> {
+        for pool_id in 0..self.data::<data::Data>().pool_length {
+            self._update_pool(pool_id)?;
+        }
+        Ok(())
+    }
+
+    default fn _update_user(
+        &mut self,
+        pool_id: PoolId,
+        user: AccountId,
+        deposit: Balance,
+        withdrawal: Balance,
+    ) -> Result<Balance, FarmError> {
+        let mut pool = self._update_pool(pool_id)?;
+        let user_info = self
+            .data::<data::Data>()
+            .users
+            .get(&(pool_id, user))
+            .unwrap_or_default();
+        let (user_info, pending) =
+            update_stake(user_info, pool.acc_reward_per_share, deposit, withdrawal)?;
+        pool.total_staked = pool
+            .total_staked
+            .checked_add(deposit)
+            .ok_or(FarmError::Arithmetic(MathOp::Add, FarmLocation::Stake))?
+            .checked_sub(withdrawal)
+            .ok_or(FarmError::Arithmetic(MathOp::Sub, FarmLocation::Stake))?;
+        self.data::<data::Data>().pools.insert(&pool_id, &pool);
+        if user_info.amount == 0 {
+            self.data::<data::Data>().users.remove(&(pool_id, user));
+        } else {
+            self.data::<data::Data>()
+                .users
+                .insert(&(pool_id, user), &user_info);
+        }
+
+        if pending > 0 {
+            let reward_token = self.data::<data::Data>().reward_token;
+            ensure!(
+                PSP22Ref::balance_of(&reward_token, Self::env().account_id()) >= pending,
+                FarmError::InsufficientRewardBalance
+            );
+            safe_transfer(reward_token, user, pending)?;
+            self._emit_harvest_event(user, pool_id, pending);
+        }
+
+        Ok(pending)
+    }
+
+    default fn _emit_pool_added_event(
+        &self,
+        _pool_id: PoolId,
+        _lp_token: AccountId,
+        _alloc_point: u32,
+    ) {
+    }
+
+    default fn _emit_pool_set_event(&self, _pool_id: PoolId, _alloc_point: u32) {}
+
+    default fn _emit_reward_per_second_set_event(&self, _reward_per_second: Balance) {}
+
+    default fn _emit_deposit_event(&self, _user: AccountId, _pool_id: PoolId, _amount: Balance) {}
+
+    default fn _emit_withdraw_event(&self, _user: AccountId, _pool_id: PoolId, _amount: Balance) {}
+
+    default fn _emit_harvest_event(&self, _user: AccountId, _pool_id: PoolId, _amount: Balance) {}
+
+    default fn _emit_emergency_withdraw_event(
+        &self,
+        _user: AccountId,
+        _pool_id: PoolId,
+        _amount: Balance,
+    ) {
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn acc(value: u128) -> WrappedU256 {
+        U256::from(value).into()
+    }
+
+    #[ink::test]
+    fn accrual_converts_milliseconds_to_seconds() {
+        // 1_000 per second over 1.5s, half of the allocation points, shared by 3 LP tokens.
+        let acc_reward_per_share =
+            accrue_reward_per_share(acc(0), 1_500, 1_000, 50, 100, 3).unwrap();
+        assert_eq!(acc_reward_per_share, acc(250 * ACC_REWARD_PRECISION));
+        assert_eq!(accrued_reward(3, acc_reward_per_share), Ok(750));
+
+        let acc_reward_per_share =
+            accrue_reward_per_share(acc_reward_per_share, 600, 1_000, 50, 100, 3).unwrap();
+        assert_eq!(accrued_reward(3, acc_reward_per_share), Ok(1_050));
+        // Nothing accrues without stake.
+        assert_eq!(
+            accrue_reward_per_share(acc(1), 1_500, 1_000, 50, 100, 0),
+            Ok(acc(1))
+        );
+    }
+
+    #[ink::test]
+    fn deposit_withdraw_and_harvest_settle_reward_debt() {
+        let (user_info, pending) = update_stake(UserInfo::default(), acc(0), 100, 0).unwrap();
+        assert_eq!(pending, 0);
+        assert_eq!(
+            user_info,
+            UserInfo {
+                amount: 100,
+                reward_debt: 0
+            }
+        );
+
+        // 2 rewards per LP token accrued, paid on the second deposit.
+        let (user_info, pending) =
+            update_stake(user_info, acc(2 * ACC_REWARD_PRECISION), 50, 0).unwrap();
+        assert_eq!(pending, 200);
+        assert_eq!(user_info.amount, 150);
+        assert_eq!(user_info.reward_debt, 300);
+
+        // A harvest without accrual pays nothing.
+        let (user_info, pending) =
+            update_stake(user_info, acc(2 * ACC_REWARD_PRECISION), 0, 0).unwrap();
+        assert_eq!(pending, 0);
+
+        // 1 more reward per LP token, paid on the withdrawal.
+        let (user_info, pending) =
+            update_stake(user_info, acc(3 * ACC_REWARD_PRECISION), 0, 150).unwrap();
+        assert_eq!(pending, 150);
+        assert_eq!(user_info, UserInfo::default());
+
+        assert_eq!(
+            update_stake(user_info, acc(3 * ACC_REWARD_PRECISION), 0, 1),
+            Err(FarmError::InsufficientStake)
+        );
+    }
+}
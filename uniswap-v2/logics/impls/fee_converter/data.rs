@@ -0,0 +1,37 @@
+use crate::traits::types::Observation;
+use openbrush::{
+    storage::Mapping,
+    traits::{
+        AccountId,
+        Timestamp,
+        ZERO_ADDRESS,
+    },
+};
+
+pub const STORAGE_KEY: u32 = openbrush::storage_unique_key!(Data);
+
+#[derive(Debug)]
+#[openbrush::upgradeable_storage(STORAGE_KEY)]
+pub struct Data {
+    pub factory: AccountId,
+    pub payout_token: AccountId,
+    pub recipient: AccountId,
+    pub twap_period: Timestamp,
+    pub max_slippage_bps: u16,
+    pub bridges: Mapping<AccountId, AccountId>,
+    pub observations: Mapping<AccountId, Observation>,
+}
+
+impl Default for Data {
+    fn default() -> Self {
+        Self {
+            factory: ZERO_ADDRESS.into(),
+            payout_token: ZERO_ADDRESS.into(),
+            recipient: ZERO_ADDRESS.into(),
+            twap_period: 0,
+            max_slippage_bps: 0,
+            bridges: Default::default(),
+            observations: Default::default(),
+        }
+    }
+}
@@ -0,0 +1,465 @@
+use crate::{
+    ensure,
+    helpers::{
+        helper::{
+            get_amount_out_with_fee,
+            get_reserves,
+            pair_for_on_chain,
+            sort_tokens,
+        },
+        math::BPS,
+        oracle::{
+            consult,
+            min_amount_out,
+            update_observation,
+        },
+        transfer_helper::safe_transfer,
+    },
+    traits::{
+        errors::{
+            call_result,
+            MathOp,
+        },
+        pair::PairRef,
+        types::Price,
+    },
+};
+pub use crate::{
+    impls::fee_converter::*,
+    traits::fee_converter::*,
+};
+use ink::{
+    env::CallFlags,
+    prelude::{
+        vec,
+        vec::Vec,
+    },
+};
+use openbrush::{
+    contracts::{
+        ownable::*,
+        reentrancy_guard::*,
+        traits::psp22::PSP22Ref,
+    },
+    modifiers,
+    traits::{
+        AccountId,
+        Balance,
+        Storage,
+        Timestamp,
+    },
+};
+
+/// Maximum number of swaps from a token to the payout token, bounds bridge cycles.
+pub const MAX_HOPS: u8 = 4;
+
+pub trait Internal {
+    /// Checkpoints the accumulators of `pair`, returns `false` if the period has not elapsed.
+    fn _update_observation(&mut self, pair: AccountId) -> Result<bool, FeeConverterError>;
+
+    fn _convert(
+        &mut self,
+        token_a: AccountId,
+        token_b: AccountId,
+    ) -> Result<Balance, FeeConverterError>;
+
+    /// Burns the LP tokens held for `pair`, returns the amounts of `token_0` and `token_1`.
+    fn _burn(&self, pair: AccountId) -> Result<(Balance, Balance), FeeConverterError>;
+
+    /// Swaps `amount` of `token` into the payout token following the bridges.
+    fn _convert_token(
+        &mut self,
+        token: AccountId,
+        amount: Balance,
+    ) -> Result<Balance, FeeConverterError>;
+
+    /// Swaps `amount_in` of `token_in` for `token_out` in their pair, failing if the
+    /// output deviates from the TWAP by more than `max_slippage_bps`.
+    fn _swap(
+        &mut self,
+        token_in: AccountId,
+        token_out: AccountId,
+        amount_in: Balance,
+    ) -> Result<Balance, FeeConverterError>;
+
+    fn _emit_convert_event(
+        &self,
+        _sender: AccountId,
+        _token_0: AccountId,
+        _token_1: AccountId,
+        _amount_0: Balance,
+        _amount_1: Balance,
+        _amount_out: Balance,
+    );
+
+    fn _emit_bridge_set_event(&self, _token: AccountId, _bridge: AccountId);
+}
+
+impl<T: Storage<data::Data> + Storage<ownable::Data> + Storage<reentrancy_guard::Data>> FeeConverter
+    for T
+{
+    default fn factory(&self) -> AccountId {
+        self.data::<data::Data>().factory
+    }
+
+    default fn payout_token(&self) -> AccountId {
+        self.data::<data::Data>().payout_token
+    }
+
+    default fn recipient(&self) -> AccountId {
+        self.data::<data::Data>().recipient
+    }
+
+    default fn twap_period(&self) -> Timestamp {
+        self.data::<data::Data>().twap_period
+    }
+
+    default fn max_slippage_bps(&self) -> u16 {
+        self.data::<data::Data>().max_slippage_bps
+    }
+
+    default fn bridge_for(&self, token: AccountId) -> AccountId {
+        self.data::<data::Data>()
+            .bridges
+            .get(&token)
+            .unwrap_or(self.data::<data::Data>().payout_token)
+    }
+
+    #[modifiers(only_owner)]
+    default fn set_bridge(
+        &mut self,
+        token: AccountId,
+        bridge: AccountId,
+    ) -> Result<(), FeeConverterError> {
+        ensure!(
+            token != self.data::<data::Data>().payout_token && token != bridge,
+            FeeConverterError::InvalidBridge
+        );
+        self.data::<data::Data>().bridges.insert(&token, &bridge);
+
+        self._emit_bridge_set_event(token, bridge);
+
+        Ok(())
+    }
+
+    #[modifiers(only_owner)]
+    default fn set_recipient(&mut self, recipient: AccountId) -> Result<(), FeeConverterError> {
+        self.data::<data::Data>().recipient = recipient;
+        Ok(())
+    }
+
+    #[modifiers(only_owner)]
+    default fn set_max_slippage_bps(
+        &mut self,
+        max_slippage_bps: u16,
+    ) -> Result<(), FeeConverterError> {
+        ensure!(max_slippage_bps <= BPS, FeeConverterError::InvalidSlippage);
+        self.data::<data::Data>().max_slippage_bps = max_slippage_bps;
+        Ok(())
+    }
+
+    default fn update_observation(&mut self, pair: AccountId) -> Result<(), FeeConverterError> {
+        ensure!(
+            self._update_observation(pair)?,
+            FeeConverterError::ObservationNotDue
+        );
+        Ok(())
+    }
+
+    default fn observation(&self, pair: AccountId) -> Option<Observation> {
+        self.data::<data::Data>().observations.get(&pair)
+    }
+
+    #[modifiers(non_reentrant)]
+    default fn convert(
+        &mut self,
+        token_a: AccountId,
+        token_b: AccountId,
+    ) -> Result<Balance, FeeConverterError> {
+        let amount_out = self._convert(token_a, token_b)?;
+        if amount_out > 0 {
+            safe_transfer(
+                self.data::<data::Data>().payout_token,
+                self.data::<data::Data>().recipient,
+                amount_out,
+            )?;
+        }
+        Ok(amount_out)
+    }
+
+    #[modifiers(non_reentrant)]
+    default fn convert_multiple(
+        &mut self,
+        tokens_a: Vec<AccountId>,
+        tokens_b: Vec<AccountId>,
+    ) -> Result<Balance, FeeConverterError> {
+        ensure!(
+            tokens_a.len() == tokens_b.len(),
+            FeeConverterError::InvalidLength
+        );
+        let mut amount_out: Balance = 0;
+        for (token_a, token_b) in tokens_a.into_iter().zip(tokens_b) {
+            amount_out = amount_out
+                .checked_add(self._convert(token_a, token_b)?)
+                .ok_or(FeeConverterError::Arithmetic(
+                    MathOp::Add,
+                    FeeConverterLocation::AmountOut,
+                ))?;
+        }
+        if amount_out > 0 {
+            safe_transfer(
+                self.data::<data::Data>().payout_token,
+                self.data::<data::Data>().recipient,
+                amount_out,
+            )?;
+        }
+        Ok(amount_out)
+    }
+}
+
+/// Returns the tokens `token` is swapped through to reach `payout_token`, following
+/// `bridge_of` and defaulting to a direct swap.
+pub fn bridge_path<F>(
+    mut token: AccountId,
+    payout_token: AccountId,
+    bridge_of: F,
+) -> Result<Vec<AccountId>, FeeConverterError>
+where
+    F: Fn(&AccountId) -> Option<AccountId>,
+{
+    let mut path = vec![token];
+    while token != payout_token {
+        ensure!(
+            path.len() <= usize::from(MAX_HOPS),
+            FeeConverterError::TooManyHops
+        );
+        token = bridge_of(&token).unwrap_or(payout_token);
+        path.push(token);
+    }
+    Ok(path)
+}
+
+/// Fails if `amount_out` for `amount_in` is below the value at `twap` by more than
+/// `max_slippage_bps`.
+pub fn ensure_near_twap(
+    amount_in: Balance,
+    amount_out: Balance,
+    twap: Price,
+    max_slippage_bps: u16,
+) -> Result<(), FeeConverterError> {
+    ensure!(
+        amount_out >= min_amount_out(amount_in, twap, max_slippage_bps)?,
+        FeeConverterError::PriceDeviation
+    );
+    Ok(())
+}
+
+impl<T: Storage<data::Data>> Internal for T {
+    default fn _update_observation(&mut self, pair: AccountId) -> Result<bool, FeeConverterError> {
+        let last = self.data::<data::Data>().observations.get(&pair);
+        match update_observation(
+            &pair,
+            last,
+            self.data::<data::Data>().twap_period,
+            Self::env().block_timestamp(),
+        )? {
+            Some(observation) => {
+                self.data::<data::Data>()
+                    .observations
+                    .insert(&pair, &observation);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    default fn _convert(
+        &mut self,
+        token_a: AccountId,
+        token_b: AccountId,
+    ) -> Result<Balance, FeeConverterError> {
+        let (token_0, token_1) = sort_tokens(token_a, token_b)?;
+        let pair = pair_for_on_chain(&self.data::<data::Data>().factory, token_0, token_1)
+            .ok_or(FeeConverterError::PairNotFound)?;
+        let liquidity = PSP22Ref::balance_of(&pair, Self::env().account_id());
+        ensure!(liquidity > 0, FeeConverterError::NoLiquidity);
+
+        safe_transfer(pair, pair, liquidity)?;
+        let (amount_0, amount_1) = self._burn(pair)?;
+        let amount_out = self
+            ._convert_token(token_0, amount_0)?
+            .checked_add(self._convert_token(token_1, amount_1)?)
+            .ok_or(FeeConverterError::Arithmetic(
+                MathOp::Add,
+                FeeConverterLocation::AmountOut,
+            ))?;
+
+        self._emit_convert_event(
+            Self::env().caller(),
+            token_0,
+            token_1,
+            amount_0,
+            amount_1,
+            amount_out,
+        );
+
+        Ok(amount_out)
+    }
+
+    default fn _burn(&self, pair: AccountId) -> Result<(Balance, Balance), FeeConverterError> {
+        // The pair sends the underlying tokens to this contract, which must allow the reentry.
+        Ok(call_result(
+            pair,
+            PairRef::burn_builder(&pair, Self::env().account_id())
+                .call_flags(CallFlags::default().set_allow_reentry(true))
+                .try_invoke(),
+        )??)
+    }
+
+    default fn _convert_token(
+        &mut self,
+        token: AccountId,
+        mut amount: Balance,
+    ) -> Result<Balance, FeeConverterError> {
+        let data = self.data::<data::Data>();
+        let path = bridge_path(token, data.payout_token, |token| data.bridges.get(token))?;
+        for tokens in path.windows(2) {
+            if amount == 0 {
+                break
+            }
+            amount = self._swap(tokens[0], tokens[1], amount)?;
+        }
+        Ok(amount)
+    }
+
+    default fn _swap(
+        &mut self,
+        token_in: AccountId,
+        token_out: AccountId,
+        amount_in: Balance,
+    ) -> Result<Balance, FeeConverterError> {
+        let factory = self.data::<data::Data>().factory;
+        let pair = pair_for_on_chain(&factory, token_in, token_out)
+            .ok_or(FeeConverterError::PairNotFound)?;
+        self._update_observation(pair)?;
+        let observation = self
+            .data::<data::Data>()
+            .observations
+            .get(&pair)
+            .ok_or(FeeConverterError::TwapNotReady)?;
+        let twap = consult(&observation, token_in, token_out)?;
+
+        let (reserve_in, reserve_out) = get_reserves(&factory, token_in, token_out)?;
+        let amount_out =
+            get_amount_out_with_fee(amount_in, reserve_in, reserve_out, PairRef::get_fee(&pair))?;
+        ensure_near_twap(
+            amount_in,
+            amount_out,
+            twap,
+            self.data::<data::Data>().max_slippage_bps,
+        )?;
+
+        let (token_0, _) = sort_tokens(token_in, token_out)?;
+        let (amount_0_out, amount_1_out) = if token_in == token_0 {
+            (0, amount_out)
+        } else {
+            (amount_out, 0)
+        };
+        safe_transfer(token_in, pair, amount_in)?;
+        call_result(
+            pair,
+            PairRef::swap_builder(&pair, amount_0_out, amount_1_out, Self::env().account_id())
+                .call_flags(CallFlags::default().set_allow_reentry(true))
+                .try_invoke(),
+        )??;
+        Ok(amount_out)
+    }
+
+    default fn _emit_convert_event(
+        &self,
+        _sender: AccountId,
+        _token_0: AccountId,
+        _token_1: AccountId,
+        _amount_0: Balance,
+        _amount_1: Balance,
+        _amount_out: Balance,
+    ) {
+    }
+
+    default fn _emit_bridge_set_event(&self, _token: AccountId, _bridge: AccountId) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        bridge_path,
+        ensure_near_twap,
+        FeeConverterError,
+        MAX_HOPS,
+    };
+    use openbrush::traits::AccountId;
+
+    const PRICE_TWO: u128 = 2_000_000_000_000_000_000;
+
+    const PAYOUT: u8 = 0xff;
+
+    fn token(id: u8) -> AccountId {
+        AccountId::from([id; 32])
+    }
+
+    fn bridges(links: &'static [(u8, u8)]) -> impl Fn(&AccountId) -> Option<AccountId> {
+        move |t| {
+            links
+                .iter()
+                .find(|(from, _)| token(*from) == *t)
+                .map(|(_, to)| token(*to))
+        }
+    }
+
+    #[ink::test]
+    fn bridge_path_follows_bridges() {
+        let payout = token(PAYOUT);
+        assert_eq!(bridge_path(payout, payout, bridges(&[])), Ok(vec![payout]));
+        assert_eq!(
+            bridge_path(token(1), payout, bridges(&[])),
+            Ok(vec![token(1), payout])
+        );
+        assert_eq!(
+            bridge_path(token(1), payout, bridges(&[(1, 2), (2, 3)])),
+            Ok(vec![token(1), token(2), token(3), payout])
+        );
+    }
+
+    #[ink::test]
+    fn bridge_path_bounds_hops() {
+        let payout = token(PAYOUT);
+        assert_eq!(
+            bridge_path(
+                token(1),
+                payout,
+                bridges(&[(1, 2), (2, 3), (3, 4), (4, PAYOUT)])
+            )
+            .map(|path| path.len()),
+            Ok(usize::from(MAX_HOPS) + 1)
+        );
+        assert_eq!(
+            bridge_path(token(1), payout, bridges(&[(1, 2), (2, 3), (3, 4), (4, 5)])),
+            Err(FeeConverterError::TooManyHops)
+        );
+        assert_eq!(
+            bridge_path(token(1), payout, bridges(&[(1, 2), (2, 1)])),
+            Err(FeeConverterError::TooManyHops)
+        );
+    }
+
+    #[ink::test]
+    fn ensure_near_twap_rejects_deviation() {
+        // 1_000 at a TWAP of 2 minus 1% is 1_980.
+        assert_eq!(ensure_near_twap(1_000, 1_980, PRICE_TWO, 100), Ok(()));
+        assert_eq!(
+            ensure_near_twap(1_000, 1_979, PRICE_TWO, 100),
+            Err(FeeConverterError::PriceDeviation)
+        );
+        assert_eq!(ensure_near_twap(1_000, 1_979, PRICE_TWO, 200), Ok(()));
+    }
+}
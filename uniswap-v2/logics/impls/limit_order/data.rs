@@ -0,0 +1,34 @@
+use crate::traits::limit_order::{
+    Order,
+    OrderId,
+};
+use ink::prelude::vec::Vec;
+use openbrush::{
+    storage::Mapping,
+    traits::{
+        AccountId,
+        ZERO_ADDRESS,
+    },
+};
+
+pub const STORAGE_KEY: u32 = openbrush::storage_unique_key!(Data);
+
+#[derive(Debug)]
+#[openbrush::upgradeable_storage(STORAGE_KEY)]
+pub struct Data {
+    pub factory: AccountId,
+    pub orders: Mapping<OrderId, Order>,
+    pub orders_of: Mapping<AccountId, Vec<OrderId>>,
+    pub next_order_id: OrderId,
+}
+
+impl Default for Data {
+    fn default() -> Self {
+        Self {
+            factory: ZERO_ADDRESS.into(),
+            orders: Default::default(),
+            orders_of: Default::default(),
+            next_order_id: 0,
+        }
+    }
+}
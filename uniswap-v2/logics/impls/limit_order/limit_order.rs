@@ -0,0 +1,512 @@
+use crate::{
+    ensure,
+    helpers::{
+        helper::{
+            get_amount_out_with_fee,
+            get_reserves,
+            pair_for_on_chain,
+            sort_tokens,
+        },
+        transfer_helper::{
+            safe_transfer,
+            safe_transfer_from,
+        },
+    },
+    traits::{
+        errors::{
+            call_result,
+            MathOp,
+        },
+        pair::PairRef,
+    },
+};
+pub use crate::{
+    impls::limit_order::*,
+    traits::limit_order::*,
+};
+use amm_math::Rounding;
+use ink::{
+    env::CallFlags,
+    prelude::vec::Vec,
+};
+use openbrush::{
+    contracts::reentrancy_guard::*,
+    modifiers,
+    traits::{
+        AccountId,
+        Balance,
+        Storage,
+        Timestamp,
+    },
+};
+
+/// Maximum fill reward of a keeper, in basis points of the output of the fill.
+pub const MAX_KEEPER_REWARD_BPS: u16 = 10;
+
+/// Amounts of a fill of an order.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Fill {
+    /// Amount of `token_out` bought from the pair.
+    pub amount_out: Balance,
+    /// Part of `amount_out` sent to the owner, at least `amount_out_min` pro rata rounded up.
+    pub owner_amount: Balance,
+    /// Part of the surplus over `amount_out_min` paid to the keeper, capped at
+    /// `MAX_KEEPER_REWARD_BPS` of `amount_out`. The rest of the surplus goes to the owner.
+    pub reward: Balance,
+}
+
+pub trait Internal {
+    /// Returns the pair and the amounts of filling `amount_in` of `order` through it.
+    fn _fill_amounts(
+        &self,
+        order: &Order,
+        amount_in: Balance,
+    ) -> Result<(AccountId, Fill), LimitOrderError>;
+
+    fn _swap(
+        &self,
+        pair: AccountId,
+        token_in: AccountId,
+        token_out: AccountId,
+        amount_out: Balance,
+        to: AccountId,
+    ) -> Result<(), LimitOrderError>;
+
+    /// Stores `order` under the next order id, which is returned.
+    fn _add_order(&mut self, order: &Order) -> Result<OrderId, LimitOrderError>;
+
+    /// Records the fill of `amount_in` of `order` for `owner_amount`, the order is removed
+    /// once filled entirely.
+    fn _record_fill(
+        &mut self,
+        order_id: OrderId,
+        order: &mut Order,
+        amount_in: Balance,
+        owner_amount: Balance,
+    ) -> Result<(), LimitOrderError>;
+
+    fn _remove_order(&mut self, order_id: OrderId, owner: AccountId);
+
+    fn _emit_order_placed_event(
+        &self,
+        _order_id: OrderId,
+        _owner: AccountId,
+        _token_in: AccountId,
+        _token_out: AccountId,
+        _amount_in: Balance,
+        _amount_out_min: Balance,
+        _expiry: Timestamp,
+    );
+
+    fn _emit_order_filled_event(
+        &self,
+        _order_id: OrderId,
+        _keeper: AccountId,
+        _amount_in: Balance,
+        _amount_out: Balance,
+        _reward: Balance,
+    );
+
+    fn _emit_order_cancelled_event(&self, _order_id: OrderId, _amount_in_refunded: Balance);
+}
+
+impl<T: Storage<data::Data> + Storage<reentrancy_guard::Data>> LimitOrder for T {
+    default fn factory(&self) -> AccountId {
+        self.data::<data::Data>().factory
+    }
+
+    #[modifiers(non_reentrant)]
+    default fn place_order(
+        &mut self,
+        token_in: AccountId,
+        token_out: AccountId,
+        amount_in: Balance,
+        amount_out_min: Balance,
+        expiry: Timestamp,
+    ) -> Result<OrderId, LimitOrderError> {
+        ensure!(
+            amount_in > 0 && amount_out_min > 0,
+            LimitOrderError::InsufficientAmount
+        );
+        ensure!(
+            expiry > Self::env().block_timestamp(),
+            LimitOrderError::InvalidExpiry
+        );
+        pair_for_on_chain(&self.data::<data::Data>().factory, token_in, token_out)
+            .ok_or(LimitOrderError::PairNotFound)?;
+
+        let owner = Self::env().caller();
+        safe_transfer_from(token_in, owner, Self::env().account_id(), amount_in)?;
+
+        let order_id = self._add_order(&Order {
+            owner,
+            token_in,
+            token_out,
+            amount_in,
+            amount_out_min,
+            amount_in_remaining: amount_in,
+            amount_out_filled: 0,
+            expiry,
+        })?;
+
+        self._emit_order_placed_event(
+            order_id,
+            owner,
+            token_in,
+            token_out,
+            amount_in,
+            amount_out_min,
+            expiry,
+        );
+
+        Ok(order_id)
+    }
+
+    #[modifiers(non_reentrant)]
+    default fn cancel_order(&mut self, order_id: OrderId) -> Result<Balance, LimitOrderError> {
+        let order = self
+            .data::<data::Data>()
+            .orders
+            .get(&order_id)
+            .ok_or(LimitOrderError::OrderNotFound)?;
+        ensure!(
+            order.owner == Self::env().caller(),
+            LimitOrderError::CallerIsNotOwner
+        );
+
+        self._remove_order(order_id, order.owner);
+        safe_transfer(order.token_in, order.owner, order.amount_in_remaining)?;
+
+        self._emit_order_cancelled_event(order_id, order.amount_in_remaining);
+
+        Ok(order.amount_in_remaining)
+    }
+
+    #[modifiers(non_reentrant)]
+    default fn fill_order(
+        &mut self,
+        order_id: OrderId,
+        amount_in: Balance,
+    ) -> Result<Balance, LimitOrderError> {
+        let mut order = self
+            .data::<data::Data>()
+            .orders
+            .get(&order_id)
+            .ok_or(LimitOrderError::OrderNotFound)?;
+        ensure!(
+            Self::env().block_timestamp() <= order.expiry,
+            LimitOrderError::Expired
+        );
+        let (pair, fill) = self._fill_amounts(&order, amount_in)?;
+        self._record_fill(order_id, &mut order, amount_in, fill.owner_amount)?;
+
+        safe_transfer(order.token_in, pair, amount_in)?;
+        self._swap(
+            pair,
+            order.token_in,
+            order.token_out,
+            fill.amount_out,
+            Self::env().account_id(),
+        )?;
+        safe_transfer(order.token_out, order.owner, fill.owner_amount)?;
+        let keeper = Self::env().caller();
+        if fill.reward > 0 {
+            safe_transfer(order.token_out, keeper, fill.reward)?;
+        }
+
+        self._emit_order_filled_event(order_id, keeper, amount_in, fill.amount_out, fill.reward);
+
+        Ok(fill.reward)
+    }
+
+    default fn quote_fill(
+        &self,
+        order_id: OrderId,
+        amount_in: Balance,
+    ) -> Result<Balance, LimitOrderError> {
+        let order = self
+            .data::<data::Data>()
+            .orders
+            .get(&order_id)
+            .ok_or(LimitOrderError::OrderNotFound)?;
+        ensure!(
+            Self::env().block_timestamp() <= order.expiry,
+            LimitOrderError::Expired
+        );
+        Ok(self._fill_amounts(&order, amount_in)?.1.reward)
+    }
+
+    default fn get_order(&self, order_id: OrderId) -> Option<Order> {
+        self.data::<data::Data>().orders.get(&order_id)
+    }
+
+    default fn orders_of(&self, owner: AccountId) -> Vec<OrderId> {
+        self.data::<data::Data>()
+            .orders_of
+            .get(&owner)
+            .unwrap_or_default()
+    }
+
+    default fn next_order_id(&self) -> OrderId {
+        self.data::<data::Data>().next_order_id
+    }
+}
+
+/// Returns the amounts of filling `amount_in` of `order` through a pair with `reserve_in`,
+/// `reserve_out` and `fee_bps`.
+pub fn fill_amounts(
+    order: &Order,
+    amount_in: Balance,
+    reserve_in: Balance,
+    reserve_out: Balance,
+    fee_bps: u16,
+) -> Result<Fill, LimitOrderError> {
+    let amount_out = get_amount_out_with_fee(amount_in, reserve_in, reserve_out, fee_bps)?;
+    // Rounded up so that partial fills never pay the owner less than the target price.
+    let amount_out_min = amm_math::mul_div_u128(
+        amount_in,
+        order.amount_out_min,
+        order.amount_in.into(),
+        Rounding::Up,
+    )
+    .map_err(|error| LimitOrderError::Arithmetic(error.into(), LimitOrderLocation::OwnerAmount))?;
+    ensure!(
+        amount_out >= amount_out_min,
+        LimitOrderError::PriceNotReached
+    );
+
+    let max_reward = amm_math::fee_amount(amount_out, MAX_KEEPER_REWARD_BPS, Rounding::Down)
+        .map_err(|error| LimitOrderError::Arithmetic(error.into(), LimitOrderLocation::Reward))?;
+    let reward = (amount_out - amount_out_min).min(max_reward);
+    Ok(Fill {
+        amount_out,
+        owner_amount: amount_out - reward,
+        reward,
+    })
+}
+
+impl<T: Storage<data::Data>> Internal for T {
+    default fn _fill_amounts(
+        &self,
+        order: &Order,
+        amount_in: Balance,
+    ) -> Result<(AccountId, Fill), LimitOrderError> {
+        ensure!(amount_in > 0, LimitOrderError::InsufficientAmount);
+        ensure!(
+            amount_in <= order.amount_in_remaining,
+            LimitOrderError::ExcessiveInputAmount
+        );
+        let factory = self.data::<data::Data>().factory;
+        let pair = pair_for_on_chain(&factory, order.token_in, order.token_out)
+            .ok_or(LimitOrderError::PairNotFound)?;
+        let (reserve_in, reserve_out) = get_reserves(&factory, order.token_in, order.token_out)?;
+        let fill = fill_amounts(
+            order,
+            amount_in,
+            reserve_in,
+            reserve_out,
+            PairRef::get_fee(&pair),
+        )?;
+        Ok((pair, fill))
+    }
+
+    default fn _swap(
+        &self,
+        pair: AccountId,
+        token_in: AccountId,
+        token_out: AccountId,
+        amount_out: Balance,
+        to: AccountId,
+    ) -> Result<(), LimitOrderError> {
+        let (token_0, _) = sort_tokens(token_in, token_out)?;
+        let (amount_0_out, amount_1_out) = if token_in == token_0 {
+            (0, amount_out)
+        } else {
+            (amount_out, 0)
+        };
+        Ok(call_result(
+            pair,
+            PairRef::swap_builder(&pair, amount_0_out, amount_1_out, to)
+                .call_flags(CallFlags::default().set_allow_reentry(true))
+                .try_invoke(),
+        )??)
+    }
+
+    default fn _add_order(&mut self, order: &Order) -> Result<OrderId, LimitOrderError> {
+        let order_id = self.data::<data::Data>().next_order_id;
+        self.data::<data::Data>().next_order_id = order_id.checked_add(1).ok_or(
+            LimitOrderError::Arithmetic(MathOp::Add, LimitOrderLocation::OrderId),
+        )?;
+        self.data::<data::Data>().orders.insert(&order_id, order);
+        let mut owner_orders = self
+            .data::<data::Data>()
+            .orders_of
+            .get(&order.owner)
+            .unwrap_or_default();
+        owner_orders.push(order_id);
+        self.data::<data::Data>()
+            .orders_of
+            .insert(&order.owner, &owner_orders);
+        Ok(order_id)
+    }
+
+    default fn _record_fill(
+        &mut self,
+        order_id: OrderId,
+        order: &mut Order,
+        amount_in: Balance,
+        owner_amount: Balance,
+    ) -> Result<(), LimitOrderError> {
+        order.amount_in_remaining =
+            order
+                .amount_in_remaining
+                .checked_sub(amount_in)
+                .ok_or(LimitOrderError::Arithmetic(
+                    MathOp::Sub,
+                    LimitOrderLocation::Fill,
+                ))?;
+        order.amount_out_filled = order.amount_out_filled.checked_add(owner_amount).ok_or(
+            LimitOrderError::Arithmetic(MathOp::Add, LimitOrderLocation::Fill),
+        )?;
+        if order.amount_in_remaining == 0 {
+            self._remove_order(order_id, order.owner);
+        } else {
+            self.data::<data::Data>().orders.insert(&order_id, order);
+        }
+        Ok(())
+    }
+
+    default fn _remove_order(&mut self, order_id: OrderId, owner: AccountId) {
+        self.data::<data::Data>().orders.remove(&order_id);
+        let mut owner_orders = self
+            .data::<data::Data>()
+            .orders_of
+            .get(&owner)
+            .unwrap_or_default();
+        owner_orders.retain(|id| *id != order_id);
+        self.data::<data::Data>()
+            .orders_of
+            .insert(&owner, &owner_orders);
+    }
+
+    default fn _emit_order_placed_event(
+        &self,
+        _order_id: OrderId,
+        _owner: AccountId,
+        _token_in: AccountId,
+        _token_out: AccountId,
+        _amount_in: Balance,
+        _amount_out_min: Balance,
+        _expiry: Timestamp,
+    ) {
+    }
+
+    default fn _emit_order_filled_event(
+        &self,
+        _order_id: OrderId,
+        _keeper: AccountId,
+        _amount_in: Balance,
+        _amount_out: Balance,
+        _reward: Balance,
+    ) {
+    }
+
+    default fn _emit_order_cancelled_event(
+        &self,
+        _order_id: OrderId,
+        _amount_in_refunded: Balance,
+    ) {
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        fill_amounts,
+        Fill,
+        LimitOrderError,
+        Order,
+    };
+    use crate::helpers::math::DEFAULT_FEE_BPS;
+    use openbrush::traits::AccountId;
+
+    fn order() -> Order {
+        Order {
+            owner: AccountId::from([0x01; 32]),
+            token_in: AccountId::from([0x02; 32]),
+            token_out: AccountId::from([0x03; 32]),
+            amount_in: 1_000,
+            amount_out_min: 1_900,
+            amount_in_remaining: 1_000,
+            amount_out_filled: 0,
+            expiry: 10,
+        }
+    }
+
+    fn large_order() -> Order {
+        Order {
+            amount_in: 100_000,
+            amount_out_min: 190_000,
+            amount_in_remaining: 100_000,
+            ..order()
+        }
+    }
+
+    #[ink::test]
+    fn fill_amounts_full_fill() {
+        // 100_000 * 997 * 200_000_000 / (100_000_000 * 1_000 + 100_000 * 997)
+        assert_eq!(
+            fill_amounts(
+                &large_order(),
+                100_000,
+                100_000_000,
+                200_000_000,
+                DEFAULT_FEE_BPS
+            ),
+            Ok(Fill {
+                amount_out: 199_201,
+                // Surplus of 9_201 over the target, 0.1% of the output to the keeper.
+                owner_amount: 199_002,
+                reward: 199,
+            })
+        );
+    }
+
+    #[ink::test]
+    fn fill_amounts_reward_below_cap() {
+        let order = Order {
+            amount_out_min: 199_100,
+            ..large_order()
+        };
+        let fill = fill_amounts(&order, 100_000, 100_000_000, 200_000_000, DEFAULT_FEE_BPS);
+        assert_eq!(
+            fill,
+            Ok(Fill {
+                amount_out: 199_201,
+                owner_amount: 199_100,
+                reward: 101,
+            })
+        );
+    }
+
+    #[ink::test]
+    fn fill_amounts_partial_fill() {
+        let fill = fill_amounts(&order(), 333, 1_000_000, 2_000_000, DEFAULT_FEE_BPS).unwrap();
+        assert_eq!(fill.amount_out, 663);
+        // 0.1% of 663 rounds down to no reward, the owner gets above 333 * 1_900 / 1_000.
+        assert_eq!(fill.reward, 0);
+        assert_eq!(fill.owner_amount, 663);
+    }
+
+    #[ink::test]
+    fn fill_amounts_below_target_price() {
+        let order = Order {
+            amount_out_min: 2_000,
+            ..order()
+        };
+        assert_eq!(
+            fill_amounts(&order, 1_000, 1_000_000, 2_000_000, DEFAULT_FEE_BPS),
+            Err(LimitOrderError::PriceNotReached)
+        );
+    }
+}
@@ -0,0 +1,43 @@
+use crate::traits::types::WrappedU256;
+use openbrush::{
+    storage::Mapping,
+    traits::{
+        AccountId,
+        Balance,
+        Timestamp,
+        ZERO_ADDRESS,
+    },
+};
+
+pub const STORAGE_KEY: u32 = openbrush::storage_unique_key!(Data);
+
+#[derive(Debug)]
+#[openbrush::upgradeable_storage(STORAGE_KEY)]
+pub struct Data {
+    pub reward_token: AccountId,
+    pub reward_distributor: AccountId,
+    pub reward_rate: WrappedU256,
+    pub period_finish: Timestamp,
+    pub last_update_time: Timestamp,
+    pub reward_per_token_stored: WrappedU256,
+    pub user_reward_per_token_paid: Mapping<AccountId, WrappedU256>,
+    pub rewards: Mapping<AccountId, Balance>,
+    /// Rewards streamed to the LP holders and not claimed yet.
+    pub rewards_owed: Balance,
+}
+
+impl Default for Data {
+    fn default() -> Self {
+        Self {
+            reward_token: ZERO_ADDRESS.into(),
+            reward_distributor: ZERO_ADDRESS.into(),
+            reward_rate: Default::default(),
+            period_finish: 0,
+            last_update_time: 0,
+            reward_per_token_stored: Default::default(),
+            user_reward_per_token_paid: Default::default(),
+            rewards: Default::default(),
+            rewards_owed: 0,
+        }
+    }
+}
@@ -0,0 +1,380 @@
+use crate::{
+    ensure,
+    helpers::transfer_helper::{
+        safe_transfer,
+        safe_transfer_from,
+    },
+    impls::pair::data as pair_data,
+    traits::{
+        errors::MathOp,
+        types::WrappedU256,
+    },
+};
+pub use crate::{
+    impls::lp_rewards::*,
+    traits::lp_rewards::*,
+};
+use core::cmp::min;
+use openbrush::{
+    contracts::{
+        psp22::*,
+        reentrancy_guard::*,
+        traits::psp22::PSP22Ref,
+    },
+    modifiers,
+    traits::{
+        AccountId,
+        AccountIdExt,
+        Balance,
+        Storage,
+        Timestamp,
+        ZERO_ADDRESS,
+    },
+};
+use primitive_types::U256;
+
+/// Precision of the reward rate and of the reward per token.
+pub const REWARD_PRECISION: u128 = 1_000_000_000_000_000_000;
+
+pub trait Internal {
+    /// Checkpoints the reward per token and, if given, the rewards of `account`.
+    /// Must be called before any change of the LP balance of `account` or of the supply.
+    /// Does nothing while rewards are not configured.
+    fn _update_reward(&mut self, account: Option<AccountId>) -> Result<(), LpRewardsError>;
+
+    fn _last_time_reward_applicable(&self) -> Timestamp;
+
+    /// LP supply earning rewards. The minimum liquidity locked at the zero address and the
+    /// liquidity sent to the pair to be burnt earn none.
+    fn _reward_supply(&self) -> Balance;
+
+    /// LP balance of `account` earning rewards, 0 for the zero address and the pair.
+    fn _reward_balance_of(&self, account: AccountId) -> Balance;
+
+    fn _emit_reward_config_set_event(
+        &self,
+        _reward_token: AccountId,
+        _reward_distributor: AccountId,
+    );
+
+    fn _emit_reward_added_event(&self, _amount: Balance, _duration: Timestamp);
+
+    fn _emit_reward_paid_event(&self, _account: AccountId, _to: AccountId, _amount: Balance);
+}
+
+impl<
+        T: Storage<data::Data>
+            + Storage<pair_data::Data>
+            + Storage<psp22::Data>
+            + Storage<reentrancy_guard::Data>,
+    > LpRewards for T
+{
+    default fn reward_token(&self) -> AccountId {
+        self.data::<data::Data>().reward_token
+    }
+
+    default fn reward_distributor(&self) -> AccountId {
+        self.data::<data::Data>().reward_distributor
+    }
+
+    default fn reward_rate(&self) -> WrappedU256 {
+        self.data::<data::Data>().reward_rate
+    }
+
+    default fn period_finish(&self) -> Timestamp {
+        self.data::<data::Data>().period_finish
+    }
+
+    default fn reward_per_token(&self) -> Result<WrappedU256, LpRewardsError> {
+        let stored: U256 = self.data::<data::Data>().reward_per_token_stored.into();
+        let supply = self._reward_supply();
+        if supply == 0 {
+            return Ok(stored.into())
+        }
+        let elapsed = self
+            ._last_time_reward_applicable()
+            .saturating_sub(self.data::<data::Data>().last_update_time);
+        let reward_rate: U256 = self.data::<data::Data>().reward_rate.into();
+        Ok(reward_rate
+            .checked_mul(elapsed.into())
+            .ok_or(LpRewardsError::Arithmetic(
+                MathOp::Mul,
+                LpRewardsLocation::RewardPerToken,
+            ))?
+            .checked_div(supply.into())
+            .ok_or(LpRewardsError::Arithmetic(
+                MathOp::Div,
+                LpRewardsLocation::RewardPerToken,
+            ))?
+            .checked_add(stored)
+            .ok_or(LpRewardsError::Arithmetic(
+                MathOp::Add,
+                LpRewardsLocation::RewardPerToken,
+            ))?
+            .into())
+    }
+
+    default fn earned(&self, account: AccountId) -> Result<Balance, LpRewardsError> {
+        let reward_per_token: U256 = self.reward_per_token()?.into();
+        let paid: U256 = self
+            .data::<data::Data>()
+            .user_reward_per_token_paid
+            .get(&account)
+            .unwrap_or_default()
+            .into();
+        let balance = self._reward_balance_of(account);
+        let accrued: Balance = reward_per_token
+            .checked_sub(paid)
+            .ok_or(LpRewardsError::Arithmetic(
+                MathOp::Sub,
+                LpRewardsLocation::Earned,
+            ))?
+            .checked_mul(balance.into())
+            .ok_or(LpRewardsError::Arithmetic(
+                MathOp::Mul,
+                LpRewardsLocation::Earned,
+            ))?
+            .checked_div(REWARD_PRECISION.into())
+            .ok_or(LpRewardsError::Arithmetic(
+                MathOp::Div,
+                LpRewardsLocation::Earned,
+            ))?
+            .try_into()
+            .map_err(|_| LpRewardsError::Arithmetic(MathOp::Cast, LpRewardsLocation::Earned))?;
+        accrued
+            .checked_add(
+                self.data::<data::Data>()
+                    .rewards
+                    .get(&account)
+                    .unwrap_or_default(),
+            )
+            .ok_or(LpRewardsError::Arithmetic(
+                MathOp::Add,
+                LpRewardsLocation::Earned,
+            ))
+    }
+
+    #[modifiers(non_reentrant)]
+    default fn claim_rewards(&mut self, to: AccountId) -> Result<Balance, LpRewardsError> {
+        let caller = Self::env().caller();
+        self._update_reward(Some(caller))?;
+        let reward = self
+            .data::<data::Data>()
+            .rewards
+            .get(&caller)
+            .unwrap_or_default();
+        if reward > 0 {
+            self.data::<data::Data>().rewards.remove(&caller);
+            // Earned rewards are rounded down, their sum never exceeds the rewards owed.
+            let rewards_owed = self.data::<data::Data>().rewards_owed;
+            self.data::<data::Data>().rewards_owed = rewards_owed.saturating_sub(reward);
+            safe_transfer(self.data::<data::Data>().reward_token, to, reward)?;
+            self._emit_reward_paid_event(caller, to, reward);
+        }
+        Ok(reward)
+    }
+
+    #[modifiers(non_reentrant)]
+    default fn notify_reward_amount(
+        &mut self,
+        amount: Balance,
+        duration: Timestamp,
+    ) -> Result<(), LpRewardsError> {
+        let reward_token = self.data::<data::Data>().reward_token;
+        ensure!(
+            !reward_token.is_zero(),
+            LpRewardsError::RewardsNotConfigured
+        );
+        let caller = Self::env().caller();
+        ensure!(
+            caller == self.data::<data::Data>().reward_distributor,
+            LpRewardsError::CallerIsNotDistributor
+        );
+        ensure!(duration > 0, LpRewardsError::InvalidDuration);
+
+        self._update_reward(None)?;
+        let contract = Self::env().account_id();
+        safe_transfer_from(reward_token, caller, contract, amount)?;
+
+        let now = Self::env().block_timestamp();
+        let period_finish = self.data::<data::Data>().period_finish;
+        let mut total = U256::from(amount)
+            .checked_mul(REWARD_PRECISION.into())
+            .ok_or(LpRewardsError::Arithmetic(
+                MathOp::Mul,
+                LpRewardsLocation::RewardRate,
+            ))?;
+        if now < period_finish {
+            let reward_rate: U256 = self.data::<data::Data>().reward_rate.into();
+            let leftover = reward_rate
+                .checked_mul((period_finish - now).into())
+                .ok_or(LpRewardsError::Arithmetic(
+                    MathOp::Mul,
+                    LpRewardsLocation::RewardRate,
+                ))?;
+            total = total
+                .checked_add(leftover)
+                .ok_or(LpRewardsError::Arithmetic(
+                    MathOp::Add,
+                    LpRewardsLocation::RewardRate,
+                ))?;
+        }
+        let reward_rate = total / U256::from(duration);
+
+        // Rewards claimed later are paid from the balance, the rate must not exceed what is
+        // left of it after the rewards owed.
+        let balance = PSP22Ref::balance_of(&reward_token, contract)
+            .saturating_sub(self.data::<data::Data>().rewards_owed);
+        ensure!(
+            reward_rate
+                <= U256::from(balance)
+                    .checked_mul(REWARD_PRECISION.into())
+                    .ok_or(LpRewardsError::Arithmetic(
+                        MathOp::Mul,
+                        LpRewardsLocation::RewardRate
+                    ))?
+                    / U256::from(duration),
+            LpRewardsError::InsufficientRewardBalance
+        );
+
+        self.data::<data::Data>().reward_rate = reward_rate.into();
+        self.data::<data::Data>().last_update_time = now;
+        self.data::<data::Data>().period_finish = now.checked_add(duration).ok_or(
+            LpRewardsError::Arithmetic(MathOp::Add, LpRewardsLocation::PeriodFinish),
+        )?;
+
+        self._emit_reward_added_event(amount, duration);
+
+        Ok(())
+    }
+
+    default fn set_reward_config(
+        &mut self,
+        reward_token: AccountId,
+        reward_distributor: AccountId,
+    ) -> Result<(), LpRewardsError> {
+        ensure!(
+            Self::env().caller() == self.data::<pair_data::Data>().factory,
+            LpRewardsError::CallerIsNotFactory
+        );
+        // Rewards held in a reserve token would be counted as swap or mint input.
+        ensure!(
+            !reward_token.is_zero()
+                && reward_token != self.data::<pair_data::Data>().token_0
+                && reward_token != self.data::<pair_data::Data>().token_1
+                && reward_token != Self::env().account_id(),
+            LpRewardsError::InvalidRewardToken
+        );
+        let current = self.data::<data::Data>().reward_token;
+        ensure!(
+            current.is_zero() || current == reward_token,
+            LpRewardsError::RewardTokenAlreadySet
+        );
+
+        if current.is_zero() {
+            self.data::<data::Data>().last_update_time = Self::env().block_timestamp();
+        }
+        self.data::<data::Data>().reward_token = reward_token;
+        self.data::<data::Data>().reward_distributor = reward_distributor;
+
+        self._emit_reward_config_set_event(reward_token, reward_distributor);
+
+        Ok(())
+    }
+}
+
+impl<
+        T: Storage<data::Data>
+            + Storage<pair_data::Data>
+            + Storage<psp22::Data>
+            + Storage<reentrancy_guard::Data>,
+    > Internal for T
+{
+    default fn _update_reward(&mut self, account: Option<AccountId>) -> Result<(), LpRewardsError> {
+        if self.data::<data::Data>().reward_token.is_zero() {
+            return Ok(())
+        }
+        let reward_per_token = self.reward_per_token()?;
+        let stored: U256 = self.data::<data::Data>().reward_per_token_stored.into();
+        let streamed: Balance = (U256::from(reward_per_token) - stored)
+            .checked_mul(self._reward_supply().into())
+            .ok_or(LpRewardsError::Arithmetic(
+                MathOp::Mul,
+                LpRewardsLocation::RewardsOwed,
+            ))?
+            .checked_div(REWARD_PRECISION.into())
+            .ok_or(LpRewardsError::Arithmetic(
+                MathOp::Div,
+                LpRewardsLocation::RewardsOwed,
+            ))?
+            .try_into()
+            .map_err(|_| {
+                LpRewardsError::Arithmetic(MathOp::Cast, LpRewardsLocation::RewardsOwed)
+            })?;
+        self.data::<data::Data>().rewards_owed = self
+            .data::<data::Data>()
+            .rewards_owed
+            .checked_add(streamed)
+            .ok_or(LpRewardsError::Arithmetic(
+                MathOp::Add,
+                LpRewardsLocation::RewardsOwed,
+            ))?;
+        self.data::<data::Data>().reward_per_token_stored = reward_per_token;
+        self.data::<data::Data>().last_update_time = self._last_time_reward_applicable();
+        if let Some(account) = account {
+            let earned = self.earned(account)?;
+            self.data::<data::Data>().rewards.insert(&account, &earned);
+            self.data::<data::Data>()
+                .user_reward_per_token_paid
+                .insert(&account, &reward_per_token);
+        }
+        Ok(())
+    }
+
+    default fn _last_time_reward_applicable(&self) -> Timestamp {
+        min(
+            Self::env().block_timestamp(),
+            self.data::<data::Data>().period_finish,
+        )
+    }
+
+    default fn _reward_supply(&self) -> Balance {
+        let zero: AccountId = ZERO_ADDRESS.into();
+        let locked = self.data::<psp22::Data>().balances.get(&zero);
+        let held = self
+            .data::<psp22::Data>()
+            .balances
+            .get(&Self::env().account_id());
+        self.data::<psp22::Data>()
+            .supply
+            .saturating_sub(locked.unwrap_or_default())
+            .saturating_sub(held.unwrap_or_default())
+    }
+
+    default fn _reward_balance_of(&self, account: AccountId) -> Balance {
+        if account.is_zero() || account == Self::env().account_id() {
+            return 0
+        }
+        self.data::<psp22::Data>()
+            .balances
+            .get(&account)
+            .unwrap_or_default()
+    }
+
+    default fn _emit_reward_config_set_event(
+        &self,
+        _reward_token: AccountId,
+        _reward_distributor: AccountId,
+    ) {
+    }
+
+    default fn _emit_reward_added_event(&self, _amount: Balance, _duration: Timestamp) {}
+
+    default fn _emit_reward_paid_event(
+        &self,
+        _account: AccountId,
+        _to: AccountId,
+        _amount: Balance,
+    ) {
+    }
+}
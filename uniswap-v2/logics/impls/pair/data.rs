@@ -1,4 +1,14 @@
-use crate::traits::types::WrappedU256;
+use crate::traits::{
+    pair::{
+        DynamicFeeConfig,
+        ProtocolFeeMode,
+    },
+    types::{
+        Price,
+        WrappedU256,
+    },
+};
+use ink::storage::Lazy;
 use openbrush::traits::{
     AccountId,
     Balance,
@@ -17,9 +27,32 @@ pub struct Data {
     pub reserve_0: Balance,
     pub reserve_1: Balance,
     pub block_timestamp_last: Timestamp,
+    /// Sum of the `UQ128x128` prices of token 0 times the milliseconds elapsed, wrapping.
     pub price_0_cumulative_last: WrappedU256,
     pub price_1_cumulative_last: WrappedU256,
     pub k_last: WrappedU256,
+    pub protocol_fee_mode: ProtocolFeeMode,
+    pub protocol_fees_0: Balance,
+    pub protocol_fees_1: Balance,
+    pub dynamic_fee_config: Option<DynamicFeeConfig>,
+    /// Checkpoint of `price_0_cumulative_last` at the start of the current fee window.
+    pub volatility_price_0_cumulative: WrappedU256,
+    pub volatility_timestamp: Timestamp,
+    /// Average price of token 0 over the last completed fee window, 0 if none.
+    pub volatility_twap: Price,
+    /// `Lazy` under its own key, unset until the oracle is first enabled.
+    pub log_price_oracle: Lazy<LogPriceOracle>,
+}
+
+/// Accumulator of the tick of the price of token 0, see `Pair::log_price_cumulative_last`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct LogPriceOracle {
+    pub enabled: bool,
+    pub cumulative: i128,
 }
 
 impl Default for Data {
@@ -34,6 +67,14 @@ impl Default for Data {
             price_0_cumulative_last: Default::default(),
             price_1_cumulative_last: Default::default(),
             k_last: Default::default(),
+            protocol_fee_mode: Default::default(),
+            protocol_fees_0: 0,
+            protocol_fees_1: 0,
+            dynamic_fee_config: None,
+            volatility_price_0_cumulative: Default::default(),
+            volatility_timestamp: 0,
+            volatility_twap: 0,
+            log_price_oracle: Default::default(),
         }
     }
 }
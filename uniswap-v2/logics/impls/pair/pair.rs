@@ -1,12 +1,29 @@
 use crate::{
     ensure,
     helpers::{
-        math::casted_mul,
+        math::{
+            casted_mul,
+            mul_div,
+            sqrt_mul,
+            tick_at_price,
+            BPS,
+            DEFAULT_FEE_BPS,
+        },
+        oracle::average_price,
         transfer_helper::safe_transfer,
     },
     traits::{
-        factory::FactoryRef,
-        types::WrappedU256,
+        errors::MathOp,
+        factory::{
+            FactoryRef,
+            ProtocolFee,
+        },
+        types::{
+            Price,
+            UQ128x128,
+            WrappedU256,
+            PRICE_ONE,
+        },
     },
 };
 pub use crate::{
@@ -31,17 +48,27 @@ use openbrush::{
     },
 };
 use primitive_types::U256;
-use sp_arithmetic::{
-    traits::IntegerSquareRoot,
-    FixedPointNumber,
-    FixedU128,
-};
 
-pub const MINIMUM_LIQUIDITY: u128 = 1000;
+pub const MINIMUM_LIQUIDITY: u128 = amm_math::MINIMUM_LIQUIDITY;
+
+/// In `ProtocolFeeMode::Tokens`, 1/6 of the swap fee is set aside, as with liquidity minting.
+pub const PROTOCOL_FEE_SHARE: u128 = 6;
 
 pub trait Internal {
     fn _mint_fee(&mut self, reserve_0: Balance, reserve_1: Balance) -> Result<bool, PairError>;
 
+    /// Swaps charging `fee_bps` on the input, as `Pair::swap` with the current fee.
+    fn _swap(
+        &mut self,
+        amount_0_out: Balance,
+        amount_1_out: Balance,
+        to: AccountId,
+        fee_bps: u16,
+    ) -> Result<(), PairError>;
+
+    /// Returns the token balances of the pair minus the protocol fees set aside.
+    fn _balances(&self) -> Result<(Balance, Balance), PairError>;
+
     fn _update(
         &mut self,
         balance_0: Balance,
@@ -92,6 +119,10 @@ impl<
         self.data::<data::Data>().price_1_cumulative_last
     }
 
+    default fn price_accumulator_version(&self) -> u8 {
+        PRICE_ACCUMULATOR_VERSION
+    }
+
     #[modifiers(only_owner)]
     default fn initialize(
         &mut self,
@@ -106,42 +137,30 @@ impl<
     #[modifiers(non_reentrant)]
     default fn mint(&mut self, to: AccountId) -> Result<Balance, PairError> {
         let reserves = self.get_reserves();
-        let contract = Self::env().account_id();
-        let balance_0 = PSP22Ref::balance_of(&self.data::<data::Data>().token_0, contract);
-        let balance_1 = PSP22Ref::balance_of(&self.data::<data::Data>().token_1, contract);
+        let (balance_0, balance_1) = self._balances()?;
         let amount_0 = balance_0
             .checked_sub(reserves.0)
-            .ok_or(PairError::SubUnderFlow1)?;
+            .ok_or(PairError::Arithmetic(
+                MathOp::Sub,
+                PairLocation::MintAmounts,
+            ))?;
         let amount_1 = balance_1
             .checked_sub(reserves.1)
-            .ok_or(PairError::SubUnderFlow2)?;
+            .ok_or(PairError::Arithmetic(
+                MathOp::Sub,
+                PairLocation::MintAmounts,
+            ))?;
 
         let fee_on = self._mint_fee(reserves.0, reserves.1)?;
         let total_supply = self.data::<psp22::Data>().supply;
 
-        let liquidity;
-        if total_supply == 0 {
-            let liq = amount_0
-                .checked_mul(amount_1)
-                .ok_or(PairError::MulOverFlow1)?;
-            liquidity = liq
-                .integer_sqrt()
-                .checked_sub(MINIMUM_LIQUIDITY)
-                .ok_or(PairError::SubUnderFlow3)?;
+        let liquidity = if total_supply == 0 {
+            let liquidity = initial_liquidity(amount_0, amount_1)?;
             self._mint_to(ZERO_ADDRESS.into(), MINIMUM_LIQUIDITY)?;
+            liquidity
         } else {
-            let liquidity_1 = amount_0
-                .checked_mul(total_supply)
-                .ok_or(PairError::MulOverFlow2)?
-                .checked_div(reserves.0)
-                .ok_or(PairError::DivByZero1)?;
-            let liquidity_2 = amount_1
-                .checked_mul(total_supply)
-                .ok_or(PairError::MulOverFlow3)?
-                .checked_div(reserves.1)
-                .ok_or(PairError::DivByZero2)?;
-            liquidity = min(liquidity_1, liquidity_2);
-        }
+            liquidity_minted((amount_0, amount_1), (reserves.0, reserves.1), total_supply)?
+        };
 
         ensure!(liquidity > 0, PairError::InsufficientLiquidityMinted);
 
@@ -164,22 +183,12 @@ impl<
         let contract = Self::env().account_id();
         let token_0 = self.data::<data::Data>().token_0;
         let token_1 = self.data::<data::Data>().token_1;
-        let mut balance_0 = PSP22Ref::balance_of(&token_0, contract);
-        let mut balance_1 = PSP22Ref::balance_of(&token_1, contract);
+        let (mut balance_0, mut balance_1) = self._balances()?;
         let liquidity = self._balance_of(&contract);
 
         let fee_on = self._mint_fee(reserves.0, reserves.1)?;
         let total_supply = self.data::<psp22::Data>().supply;
-        let amount_0 = liquidity
-            .checked_mul(balance_0)
-            .ok_or(PairError::MulOverFlow5)?
-            .checked_div(total_supply)
-            .ok_or(PairError::DivByZero3)?;
-        let amount_1 = liquidity
-            .checked_mul(balance_1)
-            .ok_or(PairError::MulOverFlow6)?
-            .checked_div(total_supply)
-            .ok_or(PairError::DivByZero4)?;
+        let (amount_0, amount_1) = burn_amounts(liquidity, (balance_0, balance_1), total_supply)?;
 
         ensure!(
             amount_0 > 0 && amount_1 > 0,
@@ -191,8 +200,7 @@ impl<
         safe_transfer(token_0, to, amount_0)?;
         safe_transfer(token_1, to, amount_1)?;
 
-        balance_0 = PSP22Ref::balance_of(&token_0, contract);
-        balance_1 = PSP22Ref::balance_of(&token_1, contract);
+        (balance_0, balance_1) = self._balances()?;
 
         self._update(balance_0, balance_1, reserves.0, reserves.1)?;
 
@@ -212,141 +220,178 @@ impl<
         amount_1_out: Balance,
         to: AccountId,
     ) -> Result<(), PairError> {
-        ensure!(
-            amount_0_out > 0 || amount_1_out > 0,
-            PairError::InsufficientOutputAmount
-        );
-        let reserves = self.get_reserves();
-        ensure!(
-            amount_0_out < reserves.0 && amount_1_out < reserves.1,
-            PairError::InsufficientLiquidity
-        );
-
-        let token_0 = self.data::<data::Data>().token_0;
-        let token_1 = self.data::<data::Data>().token_1;
-
-        ensure!(to != token_0 && to != token_1, PairError::InvalidTo);
-        if amount_0_out > 0 {
-            safe_transfer(token_0, to, amount_0_out)?;
-        }
-        if amount_1_out > 0 {
-            safe_transfer(token_1, to, amount_1_out)?;
-        }
-        let contract = Self::env().account_id();
-        let balance_0 = PSP22Ref::balance_of(&token_0, contract);
-        let balance_1 = PSP22Ref::balance_of(&token_1, contract);
-
-        let amount_0_in = if balance_0
-            > reserves
-                .0
-                .checked_sub(amount_0_out)
-                .ok_or(PairError::SubUnderFlow4)?
-        {
-            balance_0
-                .checked_sub(
-                    reserves
-                        .0
-                        .checked_sub(amount_0_out)
-                        .ok_or(PairError::SubUnderFlow5)?,
-                )
-                .ok_or(PairError::SubUnderFlow6)?
-        } else {
-            0
-        };
-        let amount_1_in = if balance_1
-            > reserves
-                .1
-                .checked_sub(amount_1_out)
-                .ok_or(PairError::SubUnderFlow7)?
-        {
-            balance_1
-                .checked_sub(
-                    reserves
-                        .1
-                        .checked_sub(amount_1_out)
-                        .ok_or(PairError::SubUnderFlow8)?,
-                )
-                .ok_or(PairError::SubUnderFlow9)?
-        } else {
-            0
-        };
-
-        ensure!(
-            amount_0_in > 0 || amount_1_in > 0,
-            PairError::InsufficientInputAmount
-        );
+        // Read before the reserves are updated, as quoted by the router.
+        let fee_bps = self.get_fee();
+        self._swap(amount_0_out, amount_1_out, to, fee_bps)
+    }
 
-        let balance_0_adjusted = balance_0
-            .checked_mul(1000)
-            .ok_or(PairError::MulOverFlow7)?
-            .checked_sub(amount_0_in.checked_mul(3).ok_or(PairError::MulOverFlow8)?)
-            .ok_or(PairError::SubUnderFlow10)?;
-        let balance_1_adjusted = balance_1
-            .checked_mul(1000)
-            .ok_or(PairError::MulOverFlow9)?
-            .checked_sub(amount_1_in.checked_mul(3).ok_or(PairError::MulOverFlow10)?)
-            .ok_or(PairError::SubUnderFlow11)?;
-
-        // Cast to U256 to prevent Overflow
+    #[modifiers(non_reentrant)]
+    default fn swap_with_fee(
+        &mut self,
+        amount_0_out: Balance,
+        amount_1_out: Balance,
+        to: AccountId,
+        fee_bps: u16,
+    ) -> Result<(), PairError> {
+        let factory = self.data::<data::Data>().factory;
         ensure!(
-            casted_mul(balance_0_adjusted, balance_1_adjusted)
-                >= casted_mul(reserves.0, reserves.1)
-                    .checked_mul(1000u128.pow(2).into())
-                    .ok_or(PairError::MulOverFlow14)?,
-            PairError::K
+            FactoryRef::is_trusted_router(&factory, Self::env().caller()),
+            PairError::CallerIsNotTrustedRouter
         );
-
-        self._update(balance_0, balance_1, reserves.0, reserves.1)?;
-
-        self._emit_swap_event(
-            Self::env().caller(),
-            amount_0_in,
-            amount_1_in,
-            amount_0_out,
-            amount_1_out,
-            to,
-        );
-        Ok(())
+        ensure!(fee_bps <= self.get_fee(), PairError::InvalidFee);
+        self._swap(amount_0_out, amount_1_out, to, fee_bps)
     }
 
     #[modifiers(non_reentrant)]
     default fn skim(&mut self, to: AccountId) -> Result<(), PairError> {
-        let contract = Self::env().account_id();
         let reserve_0 = self.data::<data::Data>().reserve_0;
         let reserve_1 = self.data::<data::Data>().reserve_1;
         let token_0 = self.data::<data::Data>().token_0;
         let token_1 = self.data::<data::Data>().token_1;
-        let balance_0 = PSP22Ref::balance_of(&token_0, contract);
-        let balance_1 = PSP22Ref::balance_of(&token_1, contract);
+        let (balance_0, balance_1) = self._balances()?;
         safe_transfer(
             token_0,
             to,
             balance_0
                 .checked_sub(reserve_0)
-                .ok_or(PairError::SubUnderFlow12)?,
+                .ok_or(PairError::Arithmetic(MathOp::Sub, PairLocation::Skim))?,
         )?;
         safe_transfer(
             token_1,
             to,
             balance_1
                 .checked_sub(reserve_1)
-                .ok_or(PairError::SubUnderFlow13)?,
+                .ok_or(PairError::Arithmetic(MathOp::Sub, PairLocation::Skim))?,
         )?;
         Ok(())
     }
 
     #[modifiers(non_reentrant)]
     default fn sync(&mut self) -> Result<(), PairError> {
-        let contract = Self::env().account_id();
         let reserve_0 = self.data::<data::Data>().reserve_0;
         let reserve_1 = self.data::<data::Data>().reserve_1;
-        let token_0 = self.data::<data::Data>().token_0;
-        let token_1 = self.data::<data::Data>().token_1;
-        let balance_0 = PSP22Ref::balance_of(&token_0, contract);
-        let balance_1 = PSP22Ref::balance_of(&token_1, contract);
+        let (balance_0, balance_1) = self._balances()?;
         self._update(balance_0, balance_1, reserve_0, reserve_1)
     }
 
+    default fn get_fee(&self) -> u16 {
+        self.get_fee_at(
+            self.data::<data::Data>().reserve_0,
+            self.data::<data::Data>().reserve_1,
+        )
+    }
+
+    default fn get_fee_at(&self, reserve_0: Balance, reserve_1: Balance) -> u16 {
+        match self.data::<data::Data>().dynamic_fee_config {
+            Some(config) => {
+                dynamic_fee(
+                    &config,
+                    self.data::<data::Data>().volatility_twap,
+                    reserve_0,
+                    reserve_1,
+                )
+            }
+            None => DEFAULT_FEE_BPS,
+        }
+    }
+
+    default fn dynamic_fee_config(&self) -> Option<DynamicFeeConfig> {
+        self.data::<data::Data>().dynamic_fee_config
+    }
+
+    #[modifiers(only_owner)]
+    default fn set_dynamic_fee_config(
+        &mut self,
+        config: Option<DynamicFeeConfig>,
+    ) -> Result<(), PairError> {
+        if let Some(config) = config {
+            ensure!(config.is_valid(), PairError::InvalidFeeConfig);
+        }
+        self.data::<data::Data>().dynamic_fee_config = config;
+        self.data::<data::Data>().volatility_price_0_cumulative =
+            self.data::<data::Data>().price_0_cumulative_last;
+        self.data::<data::Data>().volatility_timestamp =
+            self.data::<data::Data>().block_timestamp_last;
+        self.data::<data::Data>().volatility_twap = 0;
+        Ok(())
+    }
+
+    default fn log_price_cumulative_last(&self) -> i128 {
+        self.data::<data::Data>()
+            .log_price_oracle
+            .get()
+            .unwrap_or_default()
+            .cumulative
+    }
+
+    default fn log_price_oracle_enabled(&self) -> bool {
+        self.data::<data::Data>()
+            .log_price_oracle
+            .get()
+            .unwrap_or_default()
+            .enabled
+    }
+
+    #[modifiers(only_owner)]
+    default fn set_log_price_oracle_enabled(&mut self, enabled: bool) -> Result<(), PairError> {
+        let mut oracle = self
+            .data::<data::Data>()
+            .log_price_oracle
+            .get()
+            .unwrap_or_default();
+        oracle.enabled = enabled;
+        self.data::<data::Data>().log_price_oracle.set(&oracle);
+        Ok(())
+    }
+
+    default fn protocol_fee_mode(&self) -> ProtocolFeeMode {
+        self.data::<data::Data>().protocol_fee_mode
+    }
+
+    default fn protocol_fees(&self) -> (Balance, Balance) {
+        (
+            self.data::<data::Data>().protocol_fees_0,
+            self.data::<data::Data>().protocol_fees_1,
+        )
+    }
+
+    #[modifiers(only_owner)]
+    default fn set_protocol_fee_mode(&mut self, mode: ProtocolFeeMode) -> Result<(), PairError> {
+        if mode == ProtocolFeeMode::Tokens {
+            // Settles the fee accrued as liquidity so far.
+            let reserves = self.get_reserves();
+            self._mint_fee(reserves.0, reserves.1)?;
+        }
+        self.data::<data::Data>().k_last = 0.into();
+        self.data::<data::Data>().protocol_fee_mode = mode;
+        Ok(())
+    }
+
+    #[modifiers(only_owner, non_reentrant)]
+    default fn collect_protocol_fees(
+        &mut self,
+        protocol_fee: ProtocolFee,
+    ) -> Result<(Balance, Balance), PairError> {
+        let (amount_0, amount_1) = self.protocol_fees();
+        self.data::<data::Data>().protocol_fees_0 = 0;
+        self.data::<data::Data>().protocol_fees_1 = 0;
+        let tokens = [
+            (self.data::<data::Data>().token_0, amount_0),
+            (self.data::<data::Data>().token_1, amount_1),
+        ];
+        for (token, amount) in tokens {
+            let creator_amount =
+                creator_share(amount, &protocol_fee, PairLocation::CollectProtocolFees)?;
+            if creator_amount > 0 {
+                safe_transfer(token, protocol_fee.creator, creator_amount)?;
+            }
+            if amount > creator_amount {
+                safe_transfer(token, protocol_fee.fee_to, amount - creator_amount)?;
+            }
+        }
+        Ok((amount_0, amount_1))
+    }
+
     default fn get_token_0(&self) -> AccountId {
         self.data::<data::Data>().token_0
     }
@@ -356,13 +401,98 @@ impl<
     }
 }
 
-fn min(x: u128, y: u128) -> u128 {
-    if x < y {
-        return x
+/// Returns the fee for the deviation of the spot price of token 0 from `twap`, the
+/// minimum fee while no window has completed.
+pub fn dynamic_fee(
+    config: &DynamicFeeConfig,
+    twap: Price,
+    reserve_0: Balance,
+    reserve_1: Balance,
+) -> u16 {
+    if twap == 0 || reserve_0 == 0 {
+        return config.min_fee_bps
+    }
+    // Compares reserve_1 / reserve_0 to twap / PRICE_ONE without rounding the spot price.
+    let spot = casted_mul(reserve_1, PRICE_ONE);
+    let twap = casted_mul(twap, reserve_0);
+    let deviation = if spot > twap {
+        spot - twap
+    } else {
+        twap - spot
+    };
+    let deviation_bps = deviation.saturating_mul(U256::from(BPS)) / twap;
+    let fee = deviation_bps.saturating_mul(config.volatility_factor_bps.into()) / U256::from(BPS)
+        + U256::from(config.min_fee_bps);
+    if fee > config.max_fee_bps.into() {
+        config.max_fee_bps
+    } else {
+        fee.as_u32() as u16
+    }
+}
+
+/// Returns the part of the fee on `amount_in` set aside for the protocol.
+fn protocol_fee(amount_in: Balance, fee_bps: u16) -> Result<Balance, PairError> {
+    mul_div(
+        amount_in,
+        fee_bps.into(),
+        casted_mul(BPS.into(), PROTOCOL_FEE_SHARE),
+    )
+    .map_err(|op| PairError::Arithmetic(op, PairLocation::SwapProtocolFees))
+}
+
+/// Returns the liquidity minted to the first depositor, `MINIMUM_LIQUIDITY` is locked on top.
+fn initial_liquidity(amount_0: Balance, amount_1: Balance) -> Result<Balance, PairError> {
+    amm_math::initial_liquidity(amount_0, amount_1)
+        .map_err(|error| PairError::Arithmetic(error.into(), PairLocation::MintInitialLiquidity))
+}
+
+/// Returns the liquidity minted for `amounts` deposited on top of `reserves`.
+fn liquidity_minted(
+    amounts: (Balance, Balance),
+    reserves: (Balance, Balance),
+    total_supply: Balance,
+) -> Result<Balance, PairError> {
+    amm_math::liquidity_minted(amounts, reserves, total_supply)
+        .map_err(|error| PairError::Arithmetic(error.into(), PairLocation::MintLiquidity))
+}
+
+/// Returns the share of `balances` redeemed by burning `liquidity`.
+fn burn_amounts(
+    liquidity: Balance,
+    balances: (Balance, Balance),
+    total_supply: Balance,
+) -> Result<(Balance, Balance), PairError> {
+    amm_math::burn_amounts(liquidity, balances, total_supply)
+        .map_err(|error| PairError::Arithmetic(error.into(), PairLocation::BurnAmounts))
+}
+
+/// Returns the liquidity minted as protocol fee, 1/6 of the growth of sqrt(k) since
+/// `root_k_last`.
+fn protocol_fee_liquidity(
+    total_supply: Balance,
+    root_k: Balance,
+    root_k_last: Balance,
+) -> Result<Balance, PairError> {
+    amm_math::protocol_fee_liquidity(total_supply, root_k, root_k_last)
+        .map_err(|error| PairError::Arithmetic(error.into(), PairLocation::MintFee))
+}
+
+/// Returns the part of the protocol fee `amount` owed to the creator of the pair.
+fn creator_share(
+    amount: Balance,
+    protocol_fee: &ProtocolFee,
+    location: PairLocation,
+) -> Result<Balance, PairError> {
+    if protocol_fee.creator.is_zero() {
+        return Ok(0)
     }
-    y
+    mul_div(amount, protocol_fee.creator_share_bps.into(), BPS.into())
+        .map_err(|op| PairError::Arithmetic(op, location))
 }
 
+/// Adds the `UQ128x128` prices times `time_elapsed` to the accumulators, wrapping around
+/// `U256::MAX`.
+/// Consumers must difference accumulators with `helpers::oracle::cumulative_delta`.
 #[inline]
 fn update_cumulative(
     price_0_cumulative_last: WrappedU256,
@@ -371,23 +501,14 @@ fn update_cumulative(
     reserve_0: Balance,
     reserve_1: Balance,
 ) -> (WrappedU256, WrappedU256) {
-    let price_cumulative_last_0: WrappedU256 = U256::from(
-        FixedU128::checked_from_rational(reserve_1, reserve_0)
-            .unwrap_or_default()
-            .into_inner(),
-    )
-    .saturating_mul(time_elapsed)
-    .saturating_add(price_0_cumulative_last.into())
-    .into();
-    let price_cumulative_last_1: WrappedU256 = U256::from(
-        FixedU128::checked_from_rational(reserve_0, reserve_1)
-            .unwrap_or_default()
-            .into_inner(),
-    )
-    .saturating_mul(time_elapsed)
-    .saturating_add(price_1_cumulative_last.into())
-    .into();
-    (price_cumulative_last_0, price_cumulative_last_1)
+    let (price_0_cumulative, price_1_cumulative) = amm_math::update_cumulative(
+        price_0_cumulative_last.into(),
+        price_1_cumulative_last.into(),
+        time_elapsed,
+        reserve_0,
+        reserve_1,
+    );
+    (price_0_cumulative.into(), price_1_cumulative.into())
 }
 
 impl<T: Storage<data::Data> + Storage<psp22::Data>> Internal for T {
@@ -396,38 +517,36 @@ impl<T: Storage<data::Data> + Storage<psp22::Data>> Internal for T {
         reserve_0: Balance,
         reserve_1: Balance,
     ) -> Result<bool, PairError> {
-        let fee_to = FactoryRef::fee_to(&self.data::<data::Data>().factory);
-        let fee_on = !fee_to.is_zero();
+        let protocol_fee = FactoryRef::protocol_fee_of(
+            &self.data::<data::Data>().factory,
+            Self::env().account_id(),
+        );
+        let fee_on = !protocol_fee.fee_to.is_zero()
+            && self.data::<data::Data>().protocol_fee_mode == ProtocolFeeMode::MintLiquidity;
         let k_last: U256 = self.data::<data::Data>().k_last.into();
         if fee_on {
             if !k_last.is_zero() {
-                let root_k: Balance = casted_mul(reserve_0, reserve_1)
-                    .integer_sqrt()
-                    .try_into()
-                    .map_err(|_| PairError::CastOverflow1)?;
+                let root_k = sqrt_mul(reserve_0, reserve_1);
                 let root_k_last = k_last
                     .integer_sqrt()
                     .try_into()
-                    .map_err(|_| PairError::CastOverflow2)?;
+                    .map_err(|_| PairError::Arithmetic(MathOp::Cast, PairLocation::MintFee))?;
                 if root_k > root_k_last {
-                    let total_supply = self.data::<psp22::Data>().supply;
-                    let numerator = total_supply
-                        .checked_mul(
-                            root_k
-                                .checked_sub(root_k_last)
-                                .ok_or(PairError::SubUnderFlow14)?,
-                        )
-                        .ok_or(PairError::MulOverFlow13)?;
-                    let denominator = root_k
-                        .checked_mul(5)
-                        .ok_or(PairError::MulOverFlow13)?
-                        .checked_add(root_k_last)
-                        .ok_or(PairError::AddOverflow1)?;
-                    let liquidity = numerator
-                        .checked_div(denominator)
-                        .ok_or(PairError::DivByZero5)?;
-                    if liquidity > 0 {
-                        self._mint_to(fee_to, liquidity)?;
+                    let liquidity = protocol_fee_liquidity(
+                        self.data::<psp22::Data>().supply,
+                        root_k,
+                        root_k_last,
+                    )?;
+                    let creator_liquidity =
+                        creator_share(liquidity, &protocol_fee, PairLocation::MintFee)?;
+                    if creator_liquidity > 0 {
+                        self._mint_to(protocol_fee.creator, creator_liquidity)?;
+                    }
+                    let fee_to_liquidity = liquidity
+                        .checked_sub(creator_liquidity)
+                        .ok_or(PairError::Arithmetic(MathOp::Sub, PairLocation::MintFee))?;
+                    if fee_to_liquidity > 0 {
+                        self._mint_to(protocol_fee.fee_to, fee_to_liquidity)?;
                     }
                 }
             }
@@ -437,6 +556,146 @@ impl<T: Storage<data::Data> + Storage<psp22::Data>> Internal for T {
         Ok(fee_on)
     }
 
+    default fn _swap(
+        &mut self,
+        amount_0_out: Balance,
+        amount_1_out: Balance,
+        to: AccountId,
+        fee_bps: u16,
+    ) -> Result<(), PairError> {
+        ensure!(
+            amount_0_out > 0 || amount_1_out > 0,
+            PairError::InsufficientOutputAmount
+        );
+        let reserves = (
+            self.data::<data::Data>().reserve_0,
+            self.data::<data::Data>().reserve_1,
+        );
+        ensure!(
+            amount_0_out < reserves.0 && amount_1_out < reserves.1,
+            PairError::InsufficientLiquidity
+        );
+
+        let token_0 = self.data::<data::Data>().token_0;
+        let token_1 = self.data::<data::Data>().token_1;
+
+        ensure!(to != token_0 && to != token_1, PairError::InvalidTo);
+        if amount_0_out > 0 {
+            safe_transfer(token_0, to, amount_0_out)?;
+        }
+        if amount_1_out > 0 {
+            safe_transfer(token_1, to, amount_1_out)?;
+        }
+        let (mut balance_0, mut balance_1) = self._balances()?;
+
+        let amount_0_in =
+            if balance_0
+                > reserves
+                    .0
+                    .checked_sub(amount_0_out)
+                    .ok_or(PairError::Arithmetic(
+                        MathOp::Sub,
+                        PairLocation::SwapAmountsIn,
+                    ))?
+            {
+                balance_0
+                    .checked_sub(reserves.0.checked_sub(amount_0_out).ok_or(
+                        PairError::Arithmetic(MathOp::Sub, PairLocation::SwapAmountsIn),
+                    )?)
+                    .ok_or(PairError::Arithmetic(
+                        MathOp::Sub,
+                        PairLocation::SwapAmountsIn,
+                    ))?
+            } else {
+                0
+            };
+        let amount_1_in =
+            if balance_1
+                > reserves
+                    .1
+                    .checked_sub(amount_1_out)
+                    .ok_or(PairError::Arithmetic(
+                        MathOp::Sub,
+                        PairLocation::SwapAmountsIn,
+                    ))?
+            {
+                balance_1
+                    .checked_sub(reserves.1.checked_sub(amount_1_out).ok_or(
+                        PairError::Arithmetic(MathOp::Sub, PairLocation::SwapAmountsIn),
+                    )?)
+                    .ok_or(PairError::Arithmetic(
+                        MathOp::Sub,
+                        PairLocation::SwapAmountsIn,
+                    ))?
+            } else {
+                0
+            };
+
+        ensure!(
+            amount_0_in > 0 || amount_1_in > 0,
+            PairError::InsufficientInputAmount
+        );
+
+        let to_error = |error: amm_math::MathError| {
+            PairError::Arithmetic(error.into(), PairLocation::SwapAdjustedBalances)
+        };
+        let balance_0_adjusted =
+            amm_math::adjusted_balance(balance_0, amount_0_in, fee_bps).map_err(to_error)?;
+        let balance_1_adjusted =
+            amm_math::adjusted_balance(balance_1, amount_1_in, fee_bps).map_err(to_error)?;
+        ensure!(
+            amm_math::k_preserved((balance_0_adjusted, balance_1_adjusted), reserves),
+            PairError::K
+        );
+
+        if self.data::<data::Data>().protocol_fee_mode == ProtocolFeeMode::Tokens {
+            // Set aside from the swap fee, out of the reserves.
+            let protocol_fee_0 = protocol_fee(amount_0_in, fee_bps)?;
+            let protocol_fee_1 = protocol_fee(amount_1_in, fee_bps)?;
+            self.data::<data::Data>().protocol_fees_0 = self
+                .data::<data::Data>()
+                .protocol_fees_0
+                .checked_add(protocol_fee_0)
+                .ok_or(PairError::Arithmetic(
+                    MathOp::Add,
+                    PairLocation::SwapProtocolFees,
+                ))?;
+            self.data::<data::Data>().protocol_fees_1 = self
+                .data::<data::Data>()
+                .protocol_fees_1
+                .checked_add(protocol_fee_1)
+                .ok_or(PairError::Arithmetic(
+                    MathOp::Add,
+                    PairLocation::SwapProtocolFees,
+                ))?;
+            balance_0 -= protocol_fee_0;
+            balance_1 -= protocol_fee_1;
+        }
+
+        self._update(balance_0, balance_1, reserves.0, reserves.1)?;
+
+        self._emit_swap_event(
+            Self::env().caller(),
+            amount_0_in,
+            amount_1_in,
+            amount_0_out,
+            amount_1_out,
+            to,
+        );
+        Ok(())
+    }
+
+    default fn _balances(&self) -> Result<(Balance, Balance), PairError> {
+        let contract = Self::env().account_id();
+        let balance_0 = PSP22Ref::balance_of(&self.data::<data::Data>().token_0, contract)
+            .checked_sub(self.data::<data::Data>().protocol_fees_0)
+            .ok_or(PairError::Arithmetic(MathOp::Sub, PairLocation::Balances))?;
+        let balance_1 = PSP22Ref::balance_of(&self.data::<data::Data>().token_1, contract)
+            .checked_sub(self.data::<data::Data>().protocol_fees_1)
+            .ok_or(PairError::Arithmetic(MathOp::Sub, PairLocation::Balances))?;
+        Ok((balance_0, balance_1))
+    }
+
     default fn _update(
         &mut self,
         balance_0: Balance,
@@ -446,7 +705,7 @@ impl<T: Storage<data::Data> + Storage<psp22::Data>> Internal for T {
     ) -> Result<(), PairError> {
         ensure!(
             balance_0 <= u128::MAX && balance_1 <= u128::MAX,
-            PairError::Overflow
+            PairError::Arithmetic(MathOp::Cast, PairLocation::Update)
         );
         let now = Self::env().block_timestamp();
         let last_timestamp = self.data::<data::Data>().block_timestamp_last;
@@ -460,6 +719,40 @@ impl<T: Storage<data::Data> + Storage<psp22::Data>> Internal for T {
             );
             self.data::<data::Data>().price_0_cumulative_last = price_0_cumulative_last;
             self.data::<data::Data>().price_1_cumulative_last = price_1_cumulative_last;
+
+            let mut oracle = self
+                .data::<data::Data>()
+                .log_price_oracle
+                .get()
+                .unwrap_or_default();
+            let tick = if oracle.enabled {
+                UQ128x128::checked_from_rational(reserve_1, reserve_0).and_then(tick_at_price)
+            } else {
+                None
+            };
+            if let Some(tick) = tick {
+                oracle.cumulative = oracle.cumulative.wrapping_add(
+                    i128::from(tick).wrapping_mul(now.saturating_sub(last_timestamp).into()),
+                );
+                self.data::<data::Data>().log_price_oracle.set(&oracle);
+            }
+        }
+        if let Some(config) = self.data::<data::Data>().dynamic_fee_config {
+            let elapsed = now.saturating_sub(self.data::<data::Data>().volatility_timestamp);
+            if elapsed >= config.window {
+                let price_0_cumulative = self.data::<data::Data>().price_0_cumulative_last;
+                // An accumulator error only leaves the fee at its minimum.
+                self.data::<data::Data>().volatility_twap = average_price(
+                    self.data::<data::Data>()
+                        .volatility_price_0_cumulative
+                        .into(),
+                    price_0_cumulative.into(),
+                    elapsed,
+                )
+                .unwrap_or_default();
+                self.data::<data::Data>().volatility_price_0_cumulative = price_0_cumulative;
+                self.data::<data::Data>().volatility_timestamp = now;
+            }
         }
         self.data::<data::Data>().reserve_0 = balance_0;
         self.data::<data::Data>().reserve_1 = balance_1;
@@ -495,9 +788,43 @@ impl<T: Storage<data::Data> + Storage<psp22::Data>> Internal for T {
 #[cfg(test)]
 mod tests {
     use primitive_types::U256;
-    use sp_arithmetic::FixedU128;
 
-    use super::update_cumulative;
+    use super::{
+        burn_amounts,
+        creator_share,
+        dynamic_fee,
+        initial_liquidity,
+        liquidity_minted,
+        protocol_fee_liquidity,
+        update_cumulative,
+        AccountId,
+        DynamicFeeConfig,
+        PairLocation,
+        ProtocolFee,
+        UQ128x128,
+        MINIMUM_LIQUIDITY,
+        ZERO_ADDRESS,
+    };
+    use crate::{
+        helpers::{
+            math::{
+                mul_div,
+                price_at_tick,
+                tick_at_price,
+                MAX_TICK,
+                MIN_TICK,
+            },
+            oracle::{
+                average_price,
+                average_tick,
+                cumulative_delta,
+                tick_price,
+            },
+        },
+        traits::errors::MathOp,
+    };
+
+    const ONE: u128 = 1_000_000_000_000_000_000;
 
     #[ink::test]
     fn update_cumulative_from_zero_time_elapsed() {
@@ -509,13 +836,209 @@ mod tests {
     #[ink::test]
     fn update_cumulative_from_one_time_elapsed() {
         let (cumulative0, cumulative1) = update_cumulative(0.into(), 0.into(), 1.into(), 10, 10);
+        assert_eq!(U256::from(cumulative0), U256::one() << 128);
+        assert_eq!(U256::from(cumulative1), U256::one() << 128);
+    }
+
+    #[ink::test]
+    fn update_cumulative_wraps_around() {
+        let one = U256::one() << 128;
+        let start = U256::MAX - one;
+        let (cumulative0, cumulative1) =
+            update_cumulative(start.into(), start.into(), 3.into(), 10, 20);
+        // 2 * 3 and 0.5 * 3 added past U256::MAX.
+        assert_eq!(U256::from(cumulative0), one * 5 - 1);
+        assert_eq!(U256::from(cumulative1), one / 2 - 1);
+        assert_eq!(cumulative_delta(start, cumulative0.into()), one * 6);
+        assert_eq!(cumulative_delta(start, cumulative1.into()), one * 3 / 2);
+    }
+
+    #[ink::test]
+    fn average_price_across_overflow() {
+        let one: u128 = 1_000_000_000_000_000_000;
+        let mut price_0_cumulative = U256::MAX - U256::from(one / 2);
+        let mut price_1_cumulative = U256::MAX;
+        let (start_0, start_1) = (price_0_cumulative, price_1_cumulative);
+        for _ in 0..4 {
+            let (cumulative0, cumulative1) = update_cumulative(
+                price_0_cumulative.into(),
+                price_1_cumulative.into(),
+                1_000.into(),
+                4_000,
+                1_000,
+            );
+            price_0_cumulative = cumulative0.into();
+            price_1_cumulative = cumulative1.into();
+        }
+        assert!(price_0_cumulative < start_0);
+        assert!(price_1_cumulative < start_1);
+        assert_eq!(
+            average_price(start_0, price_0_cumulative, 4_000),
+            Ok(one / 4)
+        );
+        assert_eq!(
+            average_price(start_1, price_1_cumulative, 4_000),
+            Ok(one * 4)
+        );
+    }
+
+    #[ink::test]
+    fn update_cumulative_keeps_extreme_prices() {
+        // Out of the range of `FixedU128`, recorded as 0 with it.
+        let (cumulative0, cumulative1) =
+            update_cumulative(0.into(), 0.into(), 1.into(), 1, u128::MAX);
+        assert_eq!(U256::from(cumulative0), U256::from(u128::MAX) << 128);
+        assert_eq!(U256::from(cumulative1), U256::one());
+        // Ratio of a 6 and an 18 decimals token.
+        let (cumulative0, _) =
+            update_cumulative(0.into(), 0.into(), 1.into(), 3 * 10u128.pow(18), 1_000_000);
+        assert_eq!(
+            UQ128x128::from_inner(cumulative0.into()).to_price(),
+            Some(333_333)
+        );
+    }
+
+    #[ink::test]
+    fn tick_at_price_works() {
+        let tick = |reserve_0, reserve_1| {
+            tick_at_price(UQ128x128::checked_from_rational(reserve_1, reserve_0).unwrap())
+        };
+        assert_eq!(tick(1, 1), Some(0));
+        assert_eq!(tick(1, 2), Some(6_931));
+        assert_eq!(tick(2, 1), Some(-6_932));
+        assert_eq!(tick(1_000_000, 1), Some(-138_163));
+        assert_eq!(tick(1, 1_000_000), Some(138_162));
+        assert_eq!(tick(u128::MAX, 1), Some(MIN_TICK));
+        assert_eq!(tick(1, u128::MAX), Some(MAX_TICK));
+        assert_eq!(tick_at_price(UQ128x128::default()), None);
+    }
+
+    #[ink::test]
+    fn price_at_tick_inverts_tick_at_price() {
+        for tick in [-600_000, -138_163, -1, 0, 1, 6_931, 138_162, MAX_TICK - 1] {
+            let price = price_at_tick(tick).unwrap();
+            let next = price_at_tick(tick + 1).unwrap();
+            assert!(price < next);
+            // Rounding may put the tick of an exact power one below.
+            let found = tick_at_price(price).unwrap();
+            assert!(found == tick || found == tick - 1);
+            assert_eq!(
+                tick_at_price(UQ128x128::from_inner(
+                    price.into_inner() + (next.into_inner() - price.into_inner()) / 2
+                )),
+                Some(tick)
+            );
+        }
+        assert_eq!(price_at_tick(MAX_TICK + 1), None);
+        assert_eq!(tick_price(0), Ok(1_000_000_000_000_000_000));
+        assert_eq!(tick_price(6_931).unwrap() / 1_000_000_000, 1_999_836_340);
+    }
+
+    #[ink::test]
+    fn average_tick_across_overflow() {
+        let start = i128::MAX - 1_000;
+        let end = start
+            .wrapping_add(6_931 * 2_000)
+            .wrapping_add(-6_932 * 2_000);
+        assert_eq!(average_tick(start, end, 4_000), Ok(-1));
+        assert_eq!(
+            average_tick(start, start.wrapping_add(6_931 * 4_000), 4_000),
+            Ok(6_931)
+        );
+    }
+
+    #[ink::test]
+    fn liquidity_math_with_18_decimals() {
+        // 1_000 * 4_000 tokens of 18 decimals, the product alone exceeds u128::MAX.
+        assert_eq!(
+            initial_liquidity(1_000 * ONE, 4_000 * ONE),
+            Ok(2_000 * ONE - MINIMUM_LIQUIDITY)
+        );
+        let reserves = (1_000 * ONE, 4_000 * ONE);
+        let total_supply = 2_000 * ONE;
+        assert_eq!(
+            liquidity_minted((10 * ONE, 40 * ONE), reserves, total_supply),
+            Ok(20 * ONE)
+        );
+        // Excess of token 1 is donated to the pool.
+        assert_eq!(
+            liquidity_minted((10 * ONE, 80 * ONE), reserves, total_supply),
+            Ok(20 * ONE)
+        );
+        assert_eq!(
+            burn_amounts(20 * ONE, (1_010 * ONE, 4_040 * ONE), 2_020 * ONE),
+            Ok((10 * ONE, 40 * ONE))
+        );
+        // sqrt(k) grew by 0.1%, 1/6 of it is minted to the protocol.
+        assert_eq!(
+            protocol_fee_liquidity(total_supply, 2_002 * ONE, 2_000 * ONE),
+            Ok(333_055_786_844_296_419)
+        );
+    }
+
+    #[ink::test]
+    fn mul_div_reports_failed_operation() {
         assert_eq!(
-            FixedU128::from_inner(U256::from(cumulative0).as_u128()),
-            1.into()
+            mul_div(u128::MAX, u128::MAX, u128::MAX.into()),
+            Ok(u128::MAX)
         );
+        assert_eq!(mul_div(ONE, ONE, 0.into()), Err(MathOp::Div));
+        assert_eq!(mul_div(u128::MAX, 2, 1.into()), Err(MathOp::Overflow));
+    }
+
+    #[ink::test]
+    fn dynamic_fee_follows_deviation() {
+        let config = DynamicFeeConfig {
+            min_fee_bps: 10,
+            max_fee_bps: 100,
+            volatility_factor_bps: 1_000,
+            window: 60_000,
+        };
+        let one: u128 = 1_000_000_000_000_000_000;
+        // No completed window yet.
+        assert_eq!(dynamic_fee(&config, 0, 1_000, 1_200), 10);
+        // Spot at the TWAP.
+        assert_eq!(dynamic_fee(&config, one, 1_000, 1_000), 10);
+        // 2% deviation adds 20 bps at a factor of 10%.
+        assert_eq!(dynamic_fee(&config, one, 1_000, 1_020), 30);
+        assert_eq!(dynamic_fee(&config, one, 1_020, 1_000), 29);
+        // Capped by the maximum fee.
+        assert_eq!(dynamic_fee(&config, one, 1_000, 3_000), 100);
+
+        // Spot price of 3.5e20, out of the range of `FixedU128`, 16.66% above the TWAP.
+        let config = DynamicFeeConfig {
+            max_fee_bps: 1_000,
+            ..config
+        };
+        assert_eq!(
+            dynamic_fee(
+                &config,
+                300_000_000_000_000_000_000 * one,
+                1,
+                350_000_000_000_000_000_000
+            ),
+            176
+        );
+    }
+
+    #[ink::test]
+    fn creator_share_of_protocol_fees() {
+        let protocol_fee = ProtocolFee {
+            fee_to: AccountId::from([0x01; 32]),
+            creator: AccountId::from([0x02; 32]),
+            creator_share_bps: 2_500,
+        };
+        assert_eq!(
+            creator_share(1_001, &protocol_fee, PairLocation::CollectProtocolFees),
+            Ok(250)
+        );
+        let protocol_fee = ProtocolFee {
+            creator: ZERO_ADDRESS.into(),
+            ..protocol_fee
+        };
         assert_eq!(
-            FixedU128::from_inner(U256::from(cumulative1).as_u128()),
-            1.into()
+            creator_share(1_001, &protocol_fee, PairLocation::CollectProtocolFees),
+            Ok(0)
         );
     }
 }
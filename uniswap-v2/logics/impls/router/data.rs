@@ -1,6 +1,14 @@
-use openbrush::traits::{
-    AccountId,
-    ZERO_ADDRESS,
+use crate::traits::router::{
+    FeeTier,
+    FeeTierStake,
+};
+use ink::prelude::vec::Vec;
+use openbrush::{
+    storage::Mapping,
+    traits::{
+        AccountId,
+        ZERO_ADDRESS,
+    },
 };
 
 pub const STORAGE_KEY: u32 = openbrush::storage_unique_key!(Data);
@@ -10,6 +18,11 @@ pub const STORAGE_KEY: u32 = openbrush::storage_unique_key!(Data);
 pub struct Data {
     pub factory: AccountId,
     pub wnative: AccountId,
+    pub fee_tier_token: AccountId,
+    pub fee_tiers: Vec<FeeTier>,
+    pub market_maker_discounts: Mapping<AccountId, u16>,
+    pub max_referral_fee_bps: u16,
+    pub fee_tier_stakes: Mapping<AccountId, FeeTierStake>,
 }
 
 impl Default for Data {
@@ -17,6 +30,11 @@ impl Default for Data {
         Self {
             factory: ZERO_ADDRESS.into(),
             wnative: ZERO_ADDRESS.into(),
+            fee_tier_token: ZERO_ADDRESS.into(),
+            fee_tiers: Vec::new(),
+            market_maker_discounts: Default::default(),
+            max_referral_fee_bps: 0,
+            fee_tier_stakes: Default::default(),
         }
     }
 }
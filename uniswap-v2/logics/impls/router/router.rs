@@ -2,15 +2,28 @@ use crate::{
     ensure,
     helpers::{
         helper::{
+            discounted_fee,
             get_amount_in,
+            get_amount_in_with_fee,
             get_amount_out,
+            get_amount_out_with_fee,
             get_amounts_in,
+            get_amounts_in_with_discount,
+            get_amounts_in_with_overrides,
             get_amounts_out,
+            get_amounts_out_with_discount,
+            get_amounts_out_with_overrides,
+            get_hops,
             get_reserves,
             pair_for_on_chain,
             quote,
+            quote_swap,
             sort_tokens,
+            swap_amounts_in,
+            swap_amounts_out,
+            Hop,
         },
+        math::BPS,
         transfer_helper::{
             safe_transfer,
             safe_transfer_from,
@@ -20,10 +33,15 @@ use crate::{
         },
     },
     traits::{
+        errors::{
+            call_result,
+            MathOp,
+        },
         factory::FactoryRef,
         pair::PairRef,
     },
 };
+use amm_math::Rounding;
 use ink::{
     env::CallFlags,
     prelude::vec::Vec,
@@ -34,6 +52,7 @@ use openbrush::{
     modifiers,
     traits::{
         AccountId,
+        AccountIdExt,
         Balance,
         Storage,
     },
@@ -55,12 +74,105 @@ pub trait Internal {
         amount_b_min: Balance,
     ) -> Result<(Balance, Balance), RouterError>;
 
+    /// Swaps along `path` through the `hops` quoted for `amounts`, discounting the fee of each
+    /// pair by `discount_bps`.
     fn _swap(
         &self,
         amounts: &Vec<Balance>,
         path: Vec<AccountId>,
+        hops: &[Hop],
+        to: AccountId,
+        discount_bps: u16,
+    ) -> Result<(), RouterError>;
+
+    fn _fee_discount_of(&self, trader: AccountId) -> u16;
+
+    /// Returns `referral` if it has a fee, checked against the maximum referral fee.
+    fn _check_referral(&self, referral: Option<Referral>) -> Result<Option<Referral>, RouterError>;
+
+    /// Swaps along `path`, paying `input_fee` of the caller or `output_fee` out of the
+    /// swap output to the referrer.
+    fn _swap_and_pay_referral(
+        &self,
+        amounts: &Vec<Balance>,
+        path: Vec<AccountId>,
+        hops: &[Hop],
         to: AccountId,
+        discount_bps: u16,
+        referral: Option<Referral>,
+        input_fee: Balance,
+        output_fee: Balance,
     ) -> Result<(), RouterError>;
+
+    /// Swaps along `path` into the native token, paying `input_fee` of the caller or
+    /// `output_fee` out of the unwrapped output to the referrer.
+    fn _swap_to_native_and_pay_referral(
+        &self,
+        amounts: &Vec<Balance>,
+        path: Vec<AccountId>,
+        hops: &[Hop],
+        to: AccountId,
+        discount_bps: u16,
+        referral: Option<Referral>,
+        input_fee: Balance,
+        output_fee: Balance,
+    ) -> Result<(), RouterError>;
+
+    /// Pays `fee` in native tokens held by the router to the referrer, reported as `wnative`.
+    fn _pay_native_referral(
+        &self,
+        referral: Option<Referral>,
+        wnative: AccountId,
+        fee: Balance,
+    ) -> Result<(), RouterError>;
+
+    fn _remove_liquidity(
+        &self,
+        token_a: AccountId,
+        token_b: AccountId,
+        liquidity: Balance,
+        amount_a_min: Balance,
+        amount_b_min: Balance,
+        to: AccountId,
+    ) -> Result<(Balance, Balance), RouterError>;
+
+    fn _emit_swapped_event(
+        &self,
+        _sender: AccountId,
+        _path: Vec<AccountId>,
+        _amounts: Vec<Balance>,
+        _to: AccountId,
+    );
+
+    fn _emit_liquidity_added_event(
+        &self,
+        _sender: AccountId,
+        _token_a: AccountId,
+        _token_b: AccountId,
+        _amount_a: Balance,
+        _amount_b: Balance,
+        _liquidity: Balance,
+        _to: AccountId,
+    );
+
+    fn _emit_liquidity_removed_event(
+        &self,
+        _sender: AccountId,
+        _token_a: AccountId,
+        _token_b: AccountId,
+        _amount_a: Balance,
+        _amount_b: Balance,
+        _liquidity: Balance,
+        _to: AccountId,
+    );
+
+    fn _emit_referral_paid_event(
+        &self,
+        _referrer: AccountId,
+        _trader: AccountId,
+        _token: AccountId,
+        _amount: Balance,
+    );
 }
 
 impl<T: Storage<data::Data>> Router for T {
@@ -102,6 +214,9 @@ impl<T: Storage<data::Data>> Router for T {
 
         let liquidity = PairRef::mint(&pair_contract, to)?;
 
+        self._emit_liquidity_added_event(
+            caller, token_a, token_b, amount_a, amount_b, liquidity, to,
+        );
         Ok((amount_a, amount_b, liquidity))
     }
 
@@ -137,6 +252,15 @@ impl<T: Storage<data::Data>> Router for T {
         if received_value > amount_native {
             safe_transfer_native(caller, received_value - amount_native)?
         }
+        self._emit_liquidity_added_event(
+            caller,
+            token,
+            wnative,
+            amount,
+            amount_native,
+            liquidity,
+            to,
+        );
         Ok((amount, amount_native, liquidity))
     }
 
@@ -151,43 +275,17 @@ impl<T: Storage<data::Data>> Router for T {
         to: AccountId,
         deadline: u64,
     ) -> Result<(Balance, Balance), RouterError> {
-        let pair_contract = pair_for_on_chain(&self.data().factory, token_a, token_b)
-            .ok_or(RouterError::PairNotFound)?;
-
-        safe_transfer_from(
-            pair_contract,
+        let (amount_a, amount_b) =
+            self._remove_liquidity(token_a, token_b, liquidity, amount_a_min, amount_b_min, to)?;
+        self._emit_liquidity_removed_event(
             Self::env().caller(),
-            pair_contract,
+            token_a,
+            token_b,
+            amount_a,
+            amount_b,
             liquidity,
-        )?;
-
-        let (amount_0, amount_1) = match PairRef::burn_builder(&pair_contract, to)
-            .call_flags(CallFlags::default().set_allow_reentry(true))
-            .try_invoke()
-        {
-            Ok(res) => {
-                match res {
-                    Ok(v) => {
-                        match v {
-                            Ok(tuple) => Ok(tuple),
-                            Err(err) => Err(RouterError::PairError(err)),
-                        }
-                    }
-                    Err(_) => Err(RouterError::TransferError),
-                }
-            }
-            Err(_) => Err(RouterError::TransferError),
-        }?;
-        let (token_0, _) = sort_tokens(token_a, token_b)?;
-        let (amount_a, amount_b) = if token_a == token_0 {
-            (amount_0, amount_1)
-        } else {
-            (amount_1, amount_0)
-        };
-
-        ensure!(amount_a >= amount_a_min, RouterError::InsufficientAAmount);
-        ensure!(amount_b >= amount_b_min, RouterError::InsufficientBAmount);
-
+            to,
+        );
         Ok((amount_a, amount_b))
     }
 
@@ -202,22 +300,29 @@ impl<T: Storage<data::Data>> Router for T {
         deadline: u64,
     ) -> Result<(Balance, Balance), RouterError> {
         let wnative = self.data().wnative;
-        let (amount_token, amount_native) = self.remove_liquidity(
+        let (amount_token, amount_native) = self._remove_liquidity(
             token,
             wnative,
             liquidity,
             amount_token_min,
             amount_native_min,
             Self::env().account_id(),
-            deadline,
         )?;
         safe_transfer(token, to, amount_token)?;
         unwrap(&wnative, amount_native)?;
         safe_transfer_native(to, amount_native)?;
+        self._emit_liquidity_removed_event(
+            Self::env().caller(),
+            token,
+            wnative,
+            amount_token,
+            amount_native,
+            liquidity,
+            to,
+        );
         Ok((amount_token, amount_native))
     }
 
-    #[modifiers(ensure(deadline))]
     default fn swap_exact_tokens_for_tokens(
         &mut self,
         amount_in: Balance,
@@ -226,49 +331,119 @@ impl<T: Storage<data::Data>> Router for T {
         to: AccountId,
         deadline: u64,
     ) -> Result<Vec<Balance>, RouterError> {
+        self.swap_exact_tokens_for_tokens_with_referral(
+            amount_in,
+            amount_out_min,
+            path,
+            to,
+            deadline,
+            None,
+        )
+    }
+
+    default fn swap_tokens_for_exact_tokens(
+        &mut self,
+        amount_out: Balance,
+        amount_in_max: Balance,
+        path: Vec<AccountId>,
+        to: AccountId,
+        deadline: u64,
+    ) -> Result<Vec<Balance>, RouterError> {
+        self.swap_tokens_for_exact_tokens_with_referral(
+            amount_out,
+            amount_in_max,
+            path,
+            to,
+            deadline,
+            None,
+        )
+    }
+
+    #[modifiers(ensure(deadline))]
+    default fn swap_exact_tokens_for_tokens_with_referral(
+        &mut self,
+        amount_in: Balance,
+        amount_out_min: Balance,
+        path: Vec<AccountId>,
+        to: AccountId,
+        deadline: u64,
+        referral: Option<Referral>,
+    ) -> Result<Vec<Balance>, RouterError> {
+        let referral = self._check_referral(referral)?;
         let factory = self.data().factory;
+        let caller = Self::env().caller();
+        let discount_bps = self._fee_discount_of(caller);
 
-        let amounts = get_amounts_out(&factory, amount_in, &path)?;
+        let (input_fee_bps, output_fee_bps) = referral_fees_bps(&referral);
+        let input_fee = referral_fee(amount_in, input_fee_bps)?;
+        let hops = get_hops(&factory, &path)?;
+        let amounts = swap_amounts_out(amount_in - input_fee, &hops, discount_bps)?;
+        let amount_out = amounts[amounts.len() - 1];
+        let output_fee = referral_fee(amount_out, output_fee_bps)?;
         ensure!(
-            amounts[amounts.len() - 1] >= amount_out_min,
+            amount_out - output_fee >= amount_out_min,
             RouterError::InsufficientOutputAmount
         );
-        safe_transfer_from(
-            path[0],
-            Self::env().caller(),
-            pair_for_on_chain(&factory, path[0], path[1]).ok_or(RouterError::PairNotFound)?,
-            amounts[0],
+        safe_transfer_from(path[0], caller, hops[0].pair, amounts[0])?;
+        self._swap_and_pay_referral(
+            &amounts,
+            path.clone(),
+            &hops,
+            to,
+            discount_bps,
+            referral,
+            input_fee,
+            output_fee,
         )?;
-        self._swap(&amounts, path, to)?;
+        self._emit_swapped_event(Self::env().caller(), path, amounts.clone(), to);
         Ok(amounts)
     }
 
     #[modifiers(ensure(deadline))]
-    default fn swap_tokens_for_exact_tokens(
+    default fn swap_tokens_for_exact_tokens_with_referral(
         &mut self,
         amount_out: Balance,
         amount_in_max: Balance,
         path: Vec<AccountId>,
         to: AccountId,
         deadline: u64,
+        referral: Option<Referral>,
     ) -> Result<Vec<Balance>, RouterError> {
+        let referral = self._check_referral(referral)?;
         let factory = self.data().factory;
-        let amounts = get_amounts_in(&factory, amount_out, &path)?;
+        let caller = Self::env().caller();
+        let discount_bps = self._fee_discount_of(caller);
+
+        let (input_fee_bps, output_fee_bps) = referral_fees_bps(&referral);
+        let gross_amount_out = gross_amount(amount_out, output_fee_bps)?;
+        let hops = get_hops(&factory, &path)?;
+        let amounts = swap_amounts_in(gross_amount_out, &hops, discount_bps)?;
+        let input_fee = gross_amount(amounts[0], input_fee_bps)? - amounts[0];
         ensure!(
-            amounts[0] <= amount_in_max,
+            amounts[0]
+                .checked_add(input_fee)
+                .ok_or(RouterError::Arithmetic(
+                    MathOp::Add,
+                    RouterLocation::ReferralGrossAmount
+                ))?
+                <= amount_in_max,
             RouterError::ExcessiveInputAmount
         );
-        safe_transfer_from(
-            path[0],
-            Self::env().caller(),
-            pair_for_on_chain(&factory, path[0], path[1]).ok_or(RouterError::PairNotFound)?,
-            amounts[0],
+        safe_transfer_from(path[0], caller, hops[0].pair, amounts[0])?;
+        self._swap_and_pay_referral(
+            &amounts,
+            path.clone(),
+            &hops,
+            to,
+            discount_bps,
+            referral,
+            input_fee,
+            gross_amount_out - amount_out,
         )?;
-        self._swap(&amounts, path, to)?;
+        self._emit_swapped_event(Self::env().caller(), path, amounts.clone(), to);
         Ok(amounts)
     }
 
-    #[modifiers(ensure(deadline))]
     default fn swap_exact_native_for_tokens(
         &mut self,
         amount_out_min: Balance,
@@ -276,114 +451,235 @@ impl<T: Storage<data::Data>> Router for T {
         to: AccountId,
         deadline: u64,
     ) -> Result<Vec<Balance>, RouterError> {
+        self.swap_exact_native_for_tokens_with_referral(amount_out_min, path, to, deadline, None)
+    }
+
+    default fn swap_tokens_for_exact_native(
+        &mut self,
+        amount_out: Balance,
+        amount_in_max: Balance,
+        path: Vec<AccountId>,
+        to: AccountId,
+        deadline: u64,
+    ) -> Result<Vec<Balance>, RouterError> {
+        self.swap_tokens_for_exact_native_with_referral(
+            amount_out,
+            amount_in_max,
+            path,
+            to,
+            deadline,
+            None,
+        )
+    }
+
+    default fn swap_exact_tokens_for_native(
+        &mut self,
+        amount_in: Balance,
+        amount_out_min: Balance,
+        path: Vec<AccountId>,
+        to: AccountId,
+        deadline: u64,
+    ) -> Result<Vec<Balance>, RouterError> {
+        self.swap_exact_tokens_for_native_with_referral(
+            amount_in,
+            amount_out_min,
+            path,
+            to,
+            deadline,
+            None,
+        )
+    }
+
+    default fn swap_native_for_exact_tokens(
+        &mut self,
+        amount_out: Balance,
+        path: Vec<AccountId>,
+        to: AccountId,
+        deadline: u64,
+    ) -> Result<Vec<Balance>, RouterError> {
+        self.swap_native_for_exact_tokens_with_referral(amount_out, path, to, deadline, None)
+    }
+
+    #[modifiers(ensure(deadline))]
+    default fn swap_exact_native_for_tokens_with_referral(
+        &mut self,
+        amount_out_min: Balance,
+        path: Vec<AccountId>,
+        to: AccountId,
+        deadline: u64,
+        referral: Option<Referral>,
+    ) -> Result<Vec<Balance>, RouterError> {
+        let referral = self._check_referral(referral)?;
         let factory = self.data().factory;
+        let discount_bps = self._fee_discount_of(Self::env().caller());
 
         let received_value = Self::env().transferred_value();
         let wnative = self.data().wnative;
         ensure!(path[0] == wnative, RouterError::InvalidPath);
-        let amounts = get_amounts_out(&factory, received_value, &path)?;
+        let (input_fee_bps, output_fee_bps) = referral_fees_bps(&referral);
+        let input_fee = referral_fee(received_value, input_fee_bps)?;
+        let hops = get_hops(&factory, &path)?;
+        let amounts = swap_amounts_out(received_value - input_fee, &hops, discount_bps)?;
+        let amount_out = amounts[amounts.len() - 1];
+        let output_fee = referral_fee(amount_out, output_fee_bps)?;
         ensure!(
-            amounts[amounts.len() - 1] >= amount_out_min,
+            amount_out - output_fee >= amount_out_min,
             RouterError::InsufficientOutputAmount
         );
-        wrap(&wnative, received_value)?;
-        safe_transfer(
-            wnative,
-            pair_for_on_chain(&factory, path[0], path[1]).ok_or(RouterError::PairNotFound)?,
-            amounts[0],
+        wrap(&wnative, amounts[0])?;
+        safe_transfer(wnative, hops[0].pair, amounts[0])?;
+        self._swap_and_pay_referral(
+            &amounts,
+            path.clone(),
+            &hops,
+            to,
+            discount_bps,
+            referral,
+            0,
+            output_fee,
         )?;
-        self._swap(&amounts, path, to)?;
+        self._pay_native_referral(referral, wnative, input_fee)?;
+        self._emit_swapped_event(Self::env().caller(), path, amounts.clone(), to);
         Ok(amounts)
     }
 
     #[modifiers(ensure(deadline))]
-    default fn swap_tokens_for_exact_native(
+    default fn swap_tokens_for_exact_native_with_referral(
         &mut self,
         amount_out: Balance,
         amount_in_max: Balance,
         path: Vec<AccountId>,
         to: AccountId,
         deadline: u64,
+        referral: Option<Referral>,
     ) -> Result<Vec<Balance>, RouterError> {
+        let referral = self._check_referral(referral)?;
         let factory = self.data().factory;
+        let caller = Self::env().caller();
+        let discount_bps = self._fee_discount_of(caller);
 
         let wnative = self.data().wnative;
         ensure!(path[path.len() - 1] == wnative, RouterError::InvalidPath);
-        let amounts = get_amounts_in(&factory, amount_out, &path)?;
+        let (input_fee_bps, output_fee_bps) = referral_fees_bps(&referral);
+        let gross_amount_out = gross_amount(amount_out, output_fee_bps)?;
+        let hops = get_hops(&factory, &path)?;
+        let amounts = swap_amounts_in(gross_amount_out, &hops, discount_bps)?;
+        let input_fee = gross_amount(amounts[0], input_fee_bps)? - amounts[0];
         ensure!(
-            amounts[0] <= amount_in_max,
+            amounts[0]
+                .checked_add(input_fee)
+                .ok_or(RouterError::Arithmetic(
+                    MathOp::Add,
+                    RouterLocation::ReferralGrossAmount
+                ))?
+                <= amount_in_max,
             RouterError::ExcessiveInputAmount
         );
-        safe_transfer_from(
-            path[0],
-            Self::env().caller(),
-            pair_for_on_chain(&factory, path[0], path[1]).ok_or(RouterError::PairNotFound)?,
-            amounts[0],
+        safe_transfer_from(path[0], caller, hops[0].pair, amounts[0])?;
+        self._swap_to_native_and_pay_referral(
+            &amounts,
+            path.clone(),
+            &hops,
+            to,
+            discount_bps,
+            referral,
+            input_fee,
+            gross_amount_out - amount_out,
         )?;
-        self._swap(&amounts, path, Self::env().account_id())?;
-        unwrap(&wnative, amounts[amounts.len() - 1])?;
-        safe_transfer_native(to, amounts[amounts.len() - 1])?;
+        self._emit_swapped_event(Self::env().caller(), path, amounts.clone(), to);
         Ok(amounts)
     }
 
     #[modifiers(ensure(deadline))]
-    fn swap_exact_tokens_for_native(
+    default fn swap_exact_tokens_for_native_with_referral(
         &mut self,
         amount_in: Balance,
         amount_out_min: Balance,
         path: Vec<AccountId>,
         to: AccountId,
         deadline: u64,
+        referral: Option<Referral>,
     ) -> Result<Vec<Balance>, RouterError> {
+        let referral = self._check_referral(referral)?;
         let factory = self.data().factory;
+        let caller = Self::env().caller();
+        let discount_bps = self._fee_discount_of(caller);
 
         let wnative = self.data().wnative;
         ensure!(path[path.len() - 1] == wnative, RouterError::InvalidPath);
-        let amounts = get_amounts_out(&factory, amount_in, &path)?;
+        let (input_fee_bps, output_fee_bps) = referral_fees_bps(&referral);
+        let input_fee = referral_fee(amount_in, input_fee_bps)?;
+        let hops = get_hops(&factory, &path)?;
+        let amounts = swap_amounts_out(amount_in - input_fee, &hops, discount_bps)?;
+        let amount_out = amounts[amounts.len() - 1];
+        let output_fee = referral_fee(amount_out, output_fee_bps)?;
         ensure!(
-            amounts[amounts.len() - 1] >= amount_out_min,
+            amount_out - output_fee >= amount_out_min,
             RouterError::InsufficientOutputAmount
         );
-        safe_transfer_from(
-            path[0],
-            Self::env().caller(),
-            pair_for_on_chain(&factory, path[0], path[1]).ok_or(RouterError::PairNotFound)?,
-            amounts[0],
+        safe_transfer_from(path[0], caller, hops[0].pair, amounts[0])?;
+        self._swap_to_native_and_pay_referral(
+            &amounts,
+            path.clone(),
+            &hops,
+            to,
+            discount_bps,
+            referral,
+            input_fee,
+            output_fee,
         )?;
-        self._swap(&amounts, path, Self::env().account_id())?;
-        unwrap(&wnative, amounts[amounts.len() - 1])?;
-        safe_transfer_native(to, amounts[amounts.len() - 1])?;
+        self._emit_swapped_event(Self::env().caller(), path, amounts.clone(), to);
         Ok(amounts)
     }
 
     #[modifiers(ensure(deadline))]
-    fn swap_native_for_exact_tokens(
+    default fn swap_native_for_exact_tokens_with_referral(
         &mut self,
         amount_out: Balance,
         path: Vec<AccountId>,
         to: AccountId,
         deadline: u64,
+        referral: Option<Referral>,
     ) -> Result<Vec<Balance>, RouterError> {
+        let referral = self._check_referral(referral)?;
         let factory = self.data().factory;
+        let discount_bps = self._fee_discount_of(Self::env().caller());
         let wnative = self.data().wnative;
         let received_value = Self::env().transferred_value();
 
         ensure!(path[0] == wnative, RouterError::InvalidPath);
-        let amounts = get_amounts_in(&factory, amount_out, &path)?;
+        let (input_fee_bps, output_fee_bps) = referral_fees_bps(&referral);
+        let gross_amount_out = gross_amount(amount_out, output_fee_bps)?;
+        let hops = get_hops(&factory, &path)?;
+        let amounts = swap_amounts_in(gross_amount_out, &hops, discount_bps)?;
+        let input_fee = gross_amount(amounts[0], input_fee_bps)? - amounts[0];
+        let amount_in = amounts[0]
+            .checked_add(input_fee)
+            .ok_or(RouterError::Arithmetic(
+                MathOp::Add,
+                RouterLocation::ReferralGrossAmount,
+            ))?;
         ensure!(
-            amounts[0] <= received_value,
+            amount_in <= received_value,
             RouterError::ExcessiveInputAmount
         );
         wrap(&wnative, amounts[0])?;
-        safe_transfer(
-            wnative,
-            pair_for_on_chain(&factory, path[0], path[1]).ok_or(RouterError::PairNotFound)?,
-            amounts[0],
+        safe_transfer(wnative, hops[0].pair, amounts[0])?;
+        self._swap_and_pay_referral(
+            &amounts,
+            path.clone(),
+            &hops,
+            to,
+            discount_bps,
+            referral,
+            0,
+            gross_amount_out - amount_out,
         )?;
-        self._swap(&amounts, path, to)?;
-        if received_value > amounts[0] {
-            safe_transfer_native(Self::env().caller(), received_value - amounts[0])?
+        self._pay_native_referral(referral, wnative, input_fee)?;
+        if received_value > amount_in {
+            safe_transfer_native(Self::env().caller(), received_value - amount_in)?
         }
+        self._emit_swapped_event(Self::env().caller(), path, amounts.clone(), to);
         Ok(amounts)
     }
 
@@ -414,6 +710,36 @@ impl<T: Storage<data::Data>> Router for T {
         Ok(get_amount_in(amount_out, reserve_in, reserve_out)?)
     }
 
+    default fn get_amount_out_with_fee(
+        &self,
+        amount_in: Balance,
+        reserve_in: Balance,
+        reserve_out: Balance,
+        fee_bps: u16,
+    ) -> Result<Balance, RouterError> {
+        Ok(get_amount_out_with_fee(
+            amount_in,
+            reserve_in,
+            reserve_out,
+            fee_bps,
+        )?)
+    }
+
+    default fn get_amount_in_with_fee(
+        &self,
+        amount_out: Balance,
+        reserve_in: Balance,
+        reserve_out: Balance,
+        fee_bps: u16,
+    ) -> Result<Balance, RouterError> {
+        Ok(get_amount_in_with_fee(
+            amount_out,
+            reserve_in,
+            reserve_out,
+            fee_bps,
+        )?)
+    }
+
     default fn get_amounts_out(
         &self,
         amount_in: Balance,
@@ -429,10 +755,212 @@ impl<T: Storage<data::Data>> Router for T {
     ) -> Result<Vec<Balance>, RouterError> {
         Ok(get_amounts_in(&self.data().factory, amount_out, &path)?)
     }
+
+    default fn get_amounts_out_for(
+        &self,
+        trader: AccountId,
+        amount_in: Balance,
+        path: Vec<AccountId>,
+    ) -> Result<Vec<Balance>, RouterError> {
+        Ok(get_amounts_out_with_discount(
+            &self.data().factory,
+            amount_in,
+            &path,
+            self._fee_discount_of(trader),
+        )?)
+    }
+
+    default fn get_amounts_in_for(
+        &self,
+        trader: AccountId,
+        amount_out: Balance,
+        path: Vec<AccountId>,
+    ) -> Result<Vec<Balance>, RouterError> {
+        Ok(get_amounts_in_with_discount(
+            &self.data().factory,
+            amount_out,
+            &path,
+            self._fee_discount_of(trader),
+        )?)
+    }
+
+    default fn get_amounts_out_with_overrides(
+        &self,
+        amount_in: Balance,
+        path: Vec<AccountId>,
+        overrides: Vec<(AccountId, Balance, Balance)>,
+    ) -> Result<Vec<Balance>, RouterError> {
+        let discount_bps = self._fee_discount_of(Self::env().caller());
+        Ok(get_amounts_out_with_overrides(
+            &self.data().factory,
+            amount_in,
+            &path,
+            &overrides,
+            discount_bps,
+        )?)
+    }
+
+    default fn get_amounts_in_with_overrides(
+        &self,
+        amount_out: Balance,
+        path: Vec<AccountId>,
+        overrides: Vec<(AccountId, Balance, Balance)>,
+    ) -> Result<Vec<Balance>, RouterError> {
+        let discount_bps = self._fee_discount_of(Self::env().caller());
+        Ok(get_amounts_in_with_overrides(
+            &self.data().factory,
+            amount_out,
+            &path,
+            &overrides,
+            discount_bps,
+        )?)
+    }
+
+    default fn quote_exact_in(
+        &self,
+        amount_in: Balance,
+        path: Vec<AccountId>,
+    ) -> Result<SwapQuote, RouterError> {
+        let hops = get_hops(&self.data().factory, &path)?;
+        let discount_bps = self._fee_discount_of(Self::env().caller());
+        let amounts = swap_amounts_out(amount_in, &hops, discount_bps)?;
+        Ok(quote_swap(&path, &hops, &amounts, discount_bps)?)
+    }
+
+    default fn quote_exact_out(
+        &self,
+        amount_out: Balance,
+        path: Vec<AccountId>,
+    ) -> Result<SwapQuote, RouterError> {
+        let hops = get_hops(&self.data().factory, &path)?;
+        let discount_bps = self._fee_discount_of(Self::env().caller());
+        let amounts = swap_amounts_in(amount_out, &hops, discount_bps)?;
+        Ok(quote_swap(&path, &hops, &amounts, discount_bps)?)
+    }
+
+    default fn max_referral_fee_bps(&self) -> u16 {
+        self.data().max_referral_fee_bps
+    }
+
+    #[modifiers(only_fee_setter)]
+    default fn set_max_referral_fee_bps(
+        &mut self,
+        max_referral_fee_bps: u16,
+    ) -> Result<(), RouterError> {
+        ensure!(max_referral_fee_bps < BPS, RouterError::InvalidReferralFee);
+        self.data().max_referral_fee_bps = max_referral_fee_bps;
+        Ok(())
+    }
+
+    default fn fee_discount_of(&self, trader: AccountId) -> u16 {
+        self._fee_discount_of(trader)
+    }
+
+    default fn fee_tier_token(&self) -> AccountId {
+        self.data().fee_tier_token
+    }
+
+    default fn fee_tiers(&self) -> Vec<FeeTier> {
+        self.data().fee_tiers.clone()
+    }
+
+    #[modifiers(only_fee_setter)]
+    default fn set_fee_tiers(
+        &mut self,
+        token: AccountId,
+        tiers: Vec<FeeTier>,
+    ) -> Result<(), RouterError> {
+        ensure!(tiers.len() <= MAX_FEE_TIERS, RouterError::InvalidFeeTiers);
+        ensure!(
+            tiers.is_empty() || !token.is_zero(),
+            RouterError::ZeroAddress
+        );
+        for (i, tier) in tiers.iter().enumerate() {
+            ensure!(tier.discount_bps <= BPS, RouterError::InvalidDiscount);
+            ensure!(
+                i == 0 || tier.min_balance > tiers[i - 1].min_balance,
+                RouterError::InvalidFeeTiers
+            );
+        }
+        self.data().fee_tier_token = token;
+        self.data().fee_tiers = tiers;
+        Ok(())
+    }
+
+    default fn fee_tier_stake(&self, account: AccountId) -> Option<FeeTierStake> {
+        self.data().fee_tier_stakes.get(&account)
+    }
+
+    default fn stake_fee_tier_token(&mut self, amount: Balance) -> Result<(), RouterError> {
+        ensure!(amount > 0, RouterError::InsufficientAmount);
+        let token = self.data().fee_tier_token;
+        ensure!(!token.is_zero(), RouterError::ZeroAddress);
+        let caller = Self::env().caller();
+        let staked = match self.data().fee_tier_stakes.get(&caller) {
+            Some(stake) => {
+                ensure!(stake.token == token, RouterError::StakedTokenChanged);
+                stake.amount
+            }
+            None => 0,
+        };
+        let stake = FeeTierStake {
+            token,
+            amount: staked.checked_add(amount).ok_or(RouterError::Arithmetic(
+                MathOp::Add,
+                RouterLocation::FeeTierStake,
+            ))?,
+            staked_at: Self::env().block_number(),
+        };
+        self.data().fee_tier_stakes.insert(&caller, &stake);
+        safe_transfer_from(token, caller, Self::env().account_id(), amount)?;
+        Ok(())
+    }
+
+    default fn unstake_fee_tier_token(&mut self, amount: Balance) -> Result<(), RouterError> {
+        let caller = Self::env().caller();
+        let mut stake = self
+            .data()
+            .fee_tier_stakes
+            .get(&caller)
+            .ok_or(RouterError::InsufficientStake)?;
+        ensure!(amount <= stake.amount, RouterError::InsufficientStake);
+        stake.amount -= amount;
+        if stake.amount == 0 {
+            self.data().fee_tier_stakes.remove(&caller);
+        } else {
+            self.data().fee_tier_stakes.insert(&caller, &stake);
+        }
+        safe_transfer(stake.token, caller, amount)?;
+        Ok(())
+    }
+
+    default fn market_maker_discount(&self, account: AccountId) -> u16 {
+        self.data()
+            .market_maker_discounts
+            .get(&account)
+            .unwrap_or(0)
+    }
+
+    #[modifiers(only_fee_setter)]
+    default fn set_market_maker_discount(
+        &mut self,
+        account: AccountId,
+        discount_bps: u16,
+    ) -> Result<(), RouterError> {
+        ensure!(discount_bps <= BPS, RouterError::InvalidDiscount);
+        if discount_bps == 0 {
+            self.data().market_maker_discounts.remove(&account);
+        } else {
+            self.data()
+                .market_maker_discounts
+                .insert(&account, &discount_bps);
+        }
+        Ok(())
+    }
 }
 
 impl<T: Storage<data::Data>> Internal for T {
-    fn _add_liquidity(
+    default fn _add_liquidity(
         &self,
         token_a: AccountId,
         token_b: AccountId,
@@ -443,7 +971,12 @@ impl<T: Storage<data::Data>> Internal for T {
     ) -> Result<(Balance, Balance), RouterError> {
         let factory = self.data().factory;
         if pair_for_on_chain(&factory, token_a, token_b).is_none() {
-            FactoryRef::create_pair(&factory, token_a, token_b)?;
+            // A trusted router records the liquidity provider as the creator, instead of itself.
+            if FactoryRef::is_trusted_router(&factory, Self::env().account_id()) {
+                FactoryRef::create_pair_for(&factory, token_a, token_b, Self::env().caller())?;
+            } else {
+                FactoryRef::create_pair(&factory, token_a, token_b)?;
+            }
         };
 
         let (reserve_a, reserve_b) = get_reserves(&factory, token_a, token_b)?;
@@ -469,13 +1002,14 @@ impl<T: Storage<data::Data>> Internal for T {
         }
     }
 
-    fn _swap(
+    default fn _swap(
         &self,
         amounts: &Vec<Balance>,
         path: Vec<AccountId>,
+        hops: &[Hop],
         _to: AccountId,
+        discount_bps: u16,
     ) -> Result<(), RouterError> {
-        let factory = self.data().factory;
         for i in 0..path.len() - 1 {
             let (input, output) = (path[i], path[i + 1]);
             let (token_0, _) = sort_tokens(input, output)?;
@@ -486,35 +1020,273 @@ impl<T: Storage<data::Data>> Internal for T {
                 (amount_out, 0)
             };
             let to = if i < path.len() - 2 {
-                pair_for_on_chain(&factory, output, path[i + 2]).ok_or(RouterError::PairNotFound)?
+                hops[i + 1].pair
             } else {
                 _to
             };
-            match PairRef::swap_builder(
-                &pair_for_on_chain(&factory, input, output).ok_or(RouterError::PairNotFound)?,
-                amount_0_out,
-                amount_1_out,
-                to,
-            )
-            .call_flags(CallFlags::default().set_allow_reentry(true))
-            .try_invoke()
+            let pair = hops[i].pair;
+            let result = if discount_bps == 0 {
+                PairRef::swap_builder(&pair, amount_0_out, amount_1_out, to)
+                    .call_flags(CallFlags::default().set_allow_reentry(true))
+                    .try_invoke()
+            } else {
+                // Same fee as quoted in `hops`, the reserves of this pair are untouched until
+                // its hop.
+                let fee_bps = discounted_fee(hops[i].fee_bps, discount_bps);
+                PairRef::swap_with_fee_builder(&pair, amount_0_out, amount_1_out, to, fee_bps)
+                    .call_flags(CallFlags::default().set_allow_reentry(true))
+                    .try_invoke()
+            };
+            call_result(pair, result)??;
+        }
+        Ok(())
+    }
+
+    default fn _fee_discount_of(&self, trader: AccountId) -> u16 {
+        let mut discount_bps = self.data().market_maker_discounts.get(&trader).unwrap_or(0);
+        if !self.data().fee_tiers.is_empty() {
+            // Only stakes of previous blocks count, a balance can be borrowed for a swap.
+            let balance = self
+                .data()
+                .fee_tier_stakes
+                .get(&trader)
+                .filter(|stake| {
+                    stake.token == self.data().fee_tier_token
+                        && stake.staked_at < Self::env().block_number()
+                })
+                .map_or(0, |stake| stake.amount);
+            // Tiers are sorted by increasing `min_balance`.
+            if let Some(tier) = self
+                .data()
+                .fee_tiers
+                .iter()
+                .rev()
+                .find(|tier| balance >= tier.min_balance)
             {
-                Ok(res) => {
-                    match res {
-                        Ok(v) => {
-                            match v {
-                                Ok(v) => Ok(v),
-                                Err(err) => Err(RouterError::PairError(err)),
-                            }
-                        }
-                        Err(err) => Err(RouterError::LangError(err)),
-                    }
-                }
-                Err(_) => Err(RouterError::TransferError),
-            }?;
+                discount_bps = discount_bps.max(tier.discount_bps);
+            }
+        }
+        // Pairs only accept a discounted fee from routers trusted by the factory.
+        if discount_bps == 0
+            || !FactoryRef::is_trusted_router(&self.data().factory, Self::env().account_id())
+        {
+            return 0
+        }
+        discount_bps
+    }
+
+    default fn _check_referral(
+        &self,
+        referral: Option<Referral>,
+    ) -> Result<Option<Referral>, RouterError> {
+        match referral {
+            Some(referral) if referral.fee_bps > 0 => {
+                ensure!(
+                    referral.fee_bps <= self.data().max_referral_fee_bps,
+                    RouterError::InvalidReferralFee
+                );
+                ensure!(!referral.referrer.is_zero(), RouterError::ZeroAddress);
+                Ok(Some(referral))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    default fn _swap_and_pay_referral(
+        &self,
+        amounts: &Vec<Balance>,
+        path: Vec<AccountId>,
+        hops: &[Hop],
+        to: AccountId,
+        discount_bps: u16,
+        referral: Option<Referral>,
+        input_fee: Balance,
+        output_fee: Balance,
+    ) -> Result<(), RouterError> {
+        let referral = match referral {
+            Some(referral) => referral,
+            None => return self._swap(amounts, path, hops, to, discount_bps),
+        };
+        let caller = Self::env().caller();
+        let (token_in, token_out) = (path[0], path[path.len() - 1]);
+        if referral.from_output {
+            self._swap(amounts, path, hops, Self::env().account_id(), discount_bps)?;
+            safe_transfer(token_out, to, amounts[amounts.len() - 1] - output_fee)?;
+            if output_fee > 0 {
+                safe_transfer(token_out, referral.referrer, output_fee)?;
+                self._emit_referral_paid_event(referral.referrer, caller, token_out, output_fee);
+            }
+        } else {
+            self._swap(amounts, path, hops, to, discount_bps)?;
+            if input_fee > 0 {
+                safe_transfer_from(token_in, caller, referral.referrer, input_fee)?;
+                self._emit_referral_paid_event(referral.referrer, caller, token_in, input_fee);
+            }
         }
         Ok(())
     }
+
+    default fn _swap_to_native_and_pay_referral(
+        &self,
+        amounts: &Vec<Balance>,
+        path: Vec<AccountId>,
+        hops: &[Hop],
+        to: AccountId,
+        discount_bps: u16,
+        referral: Option<Referral>,
+        input_fee: Balance,
+        output_fee: Balance,
+    ) -> Result<(), RouterError> {
+        let (token_in, wnative) = (path[0], path[path.len() - 1]);
+        let amount_out = amounts[amounts.len() - 1];
+        self._swap(amounts, path, hops, Self::env().account_id(), discount_bps)?;
+        unwrap(&wnative, amount_out)?;
+        safe_transfer_native(to, amount_out - output_fee)?;
+        self._pay_native_referral(referral, wnative, output_fee)?;
+        if let Some(referral) = referral {
+            if input_fee > 0 {
+                let caller = Self::env().caller();
+                safe_transfer_from(token_in, caller, referral.referrer, input_fee)?;
+                self._emit_referral_paid_event(referral.referrer, caller, token_in, input_fee);
+            }
+        }
+        Ok(())
+    }
+
+    default fn _pay_native_referral(
+        &self,
+        referral: Option<Referral>,
+        wnative: AccountId,
+        fee: Balance,
+    ) -> Result<(), RouterError> {
+        if let Some(referral) = referral {
+            if fee > 0 {
+                safe_transfer_native(referral.referrer, fee)?;
+                self._emit_referral_paid_event(
+                    referral.referrer,
+                    Self::env().caller(),
+                    wnative,
+                    fee,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    default fn _remove_liquidity(
+        &self,
+        token_a: AccountId,
+        token_b: AccountId,
+        liquidity: Balance,
+        amount_a_min: Balance,
+        amount_b_min: Balance,
+        to: AccountId,
+    ) -> Result<(Balance, Balance), RouterError> {
+        let pair_contract = pair_for_on_chain(&self.data().factory, token_a, token_b)
+            .ok_or(RouterError::PairNotFound)?;
+
+        safe_transfer_from(
+            pair_contract,
+            Self::env().caller(),
+            pair_contract,
+            liquidity,
+        )?;
+
+        let (amount_0, amount_1) = call_result(
+            pair_contract,
+            PairRef::burn_builder(&pair_contract, to)
+                .call_flags(CallFlags::default().set_allow_reentry(true))
+                .try_invoke(),
+        )??;
+        let (token_0, _) = sort_tokens(token_a, token_b)?;
+        let (amount_a, amount_b) = if token_a == token_0 {
+            (amount_0, amount_1)
+        } else {
+            (amount_1, amount_0)
+        };
+
+        ensure!(amount_a >= amount_a_min, RouterError::InsufficientAAmount);
+        ensure!(amount_b >= amount_b_min, RouterError::InsufficientBAmount);
+
+        Ok((amount_a, amount_b))
+    }
+
+    default fn _emit_swapped_event(
+        &self,
+        _sender: AccountId,
+        _path: Vec<AccountId>,
+        _amounts: Vec<Balance>,
+        _to: AccountId,
+    ) {
+    }
+
+    default fn _emit_liquidity_added_event(
+        &self,
+        _sender: AccountId,
+        _token_a: AccountId,
+        _token_b: AccountId,
+        _amount_a: Balance,
+        _amount_b: Balance,
+        _liquidity: Balance,
+        _to: AccountId,
+    ) {
+    }
+
+    default fn _emit_liquidity_removed_event(
+        &self,
+        _sender: AccountId,
+        _token_a: AccountId,
+        _token_b: AccountId,
+        _amount_a: Balance,
+        _amount_b: Balance,
+        _liquidity: Balance,
+        _to: AccountId,
+    ) {
+    }
+
+    default fn _emit_referral_paid_event(
+        &self,
+        _referrer: AccountId,
+        _trader: AccountId,
+        _token: AccountId,
+        _amount: Balance,
+    ) {
+    }
+}
+
+/// Returns the referral fee taken from the input and from the output, in basis points.
+fn referral_fees_bps(referral: &Option<Referral>) -> (u16, u16) {
+    match referral {
+        Some(referral) if referral.from_output => (0, referral.fee_bps),
+        Some(referral) => (referral.fee_bps, 0),
+        None => (0, 0),
+    }
+}
+
+/// Returns `fee_bps` of `amount`, rounded down.
+fn referral_fee(amount: Balance, fee_bps: u16) -> Result<Balance, RouterError> {
+    amm_math::fee_amount(amount, fee_bps, Rounding::Down)
+        .map_err(|error| RouterError::Arithmetic(error.into(), RouterLocation::ReferralFee))
+}
+
+/// Returns the amount whose `fee_bps` leaves `net_amount`, rounded up.
+fn gross_amount(net_amount: Balance, fee_bps: u16) -> Result<Balance, RouterError> {
+    amm_math::gross_amount(net_amount, fee_bps)
+        .map_err(|error| RouterError::Arithmetic(error.into(), RouterLocation::ReferralGrossAmount))
+}
+
+#[modifier_definition]
+pub fn only_fee_setter<T, F, R, E>(instance: &mut T, body: F) -> Result<R, E>
+where
+    T: Storage<data::Data>,
+    F: FnOnce(&mut T) -> Result<R, E>,
+    E: From<RouterError>,
+{
+    ensure!(
+        T::env().caller() == FactoryRef::fee_to_setter(&instance.data().factory),
+        RouterError::CallerIsNotFeeSetter
+    );
+    body(instance)
 }
 
 #[modifier_definition]
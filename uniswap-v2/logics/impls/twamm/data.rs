@@ -0,0 +1,71 @@
+use crate::traits::{
+    twamm::{
+        LongTermOrder,
+        OrderId,
+    },
+    types::{
+        Observation,
+        WrappedU256,
+    },
+};
+use ink::prelude::vec::Vec;
+use openbrush::{
+    storage::Mapping,
+    traits::{
+        AccountId,
+        Balance,
+        Timestamp,
+        ZERO_ADDRESS,
+    },
+};
+
+pub const STORAGE_KEY: u32 = openbrush::storage_unique_key!(Data);
+
+#[derive(Debug)]
+#[openbrush::upgradeable_storage(STORAGE_KEY)]
+pub struct Data {
+    pub pair: AccountId,
+    pub token_0: AccountId,
+    pub token_1: AccountId,
+    pub order_interval: Timestamp,
+    pub last_virtual_order_timestamp: Timestamp,
+    pub sell_rate_0: Balance,
+    pub sell_rate_1: Balance,
+    /// Sum of the proceeds per unit of sell rate of each order pool.
+    pub reward_factor_0: WrappedU256,
+    pub reward_factor_1: WrappedU256,
+    /// Sell rate ending at an interval boundary, keyed by (sell_token_0, boundary).
+    pub sell_rate_ending: Mapping<(bool, Timestamp), Balance>,
+    /// Reward factor of the order pool at an interval boundary where orders expire.
+    pub reward_factor_at_expiry: Mapping<(bool, Timestamp), WrappedU256>,
+    pub orders: Mapping<OrderId, LongTermOrder>,
+    pub orders_of: Mapping<AccountId, Vec<OrderId>>,
+    pub next_order_id: OrderId,
+    pub twap_period: Timestamp,
+    pub max_slippage_bps: u16,
+    pub observation: Option<Observation>,
+}
+
+impl Default for Data {
+    fn default() -> Self {
+        Self {
+            pair: ZERO_ADDRESS.into(),
+            token_0: ZERO_ADDRESS.into(),
+            token_1: ZERO_ADDRESS.into(),
+            order_interval: 0,
+            last_virtual_order_timestamp: 0,
+            sell_rate_0: 0,
+            sell_rate_1: 0,
+            reward_factor_0: Default::default(),
+            reward_factor_1: Default::default(),
+            sell_rate_ending: Default::default(),
+            reward_factor_at_expiry: Default::default(),
+            orders: Default::default(),
+            orders_of: Default::default(),
+            next_order_id: 0,
+            twap_period: 0,
+            max_slippage_bps: 0,
+            observation: None,
+        }
+    }
+}
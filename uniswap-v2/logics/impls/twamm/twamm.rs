@@ -0,0 +1,873 @@
+use crate::{
+    ensure,
+    helpers::{
+        helper::get_amount_out_with_fee,
+        math::casted_mul,
+        oracle::{
+            consult,
+            min_amount_out,
+            update_observation,
+        },
+        transfer_helper::{
+            safe_transfer,
+            safe_transfer_from,
+        },
+    },
+    traits::{
+        errors::{
+            call_result,
+            MathOp,
+        },
+        pair::PairRef,
+        types::{
+            Price,
+            WrappedU256,
+        },
+    },
+};
+pub use crate::{
+    impls::twamm::*,
+    traits::twamm::*,
+};
+use ink::{
+    env::CallFlags,
+    prelude::vec::Vec,
+};
+use openbrush::{
+    contracts::reentrancy_guard::*,
+    modifiers,
+    traits::{
+        AccountId,
+        Balance,
+        Storage,
+        Timestamp,
+    },
+};
+use primitive_types::U256;
+
+/// Precision of the reward factors of the order pools.
+pub const REWARD_FACTOR_PRECISION: u128 = 10u128.pow(18);
+
+/// Maximum number of interval boundaries crossed by one execution of the virtual orders.
+pub const MAX_INTERVALS_PER_EXECUTION: u32 = 64;
+
+/// Result of the virtual execution of both order pools over a segment of time.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SegmentAmounts {
+    /// Amount of `token_1` earned by the `token_0` sellers.
+    pub proceeds_0: Balance,
+    /// Amount of `token_0` earned by the `token_1` sellers.
+    pub proceeds_1: Balance,
+    pub amount_0_in: Balance,
+    pub amount_1_in: Balance,
+    pub amount_0_out: Balance,
+    pub amount_1_out: Balance,
+}
+
+pub trait Internal {
+    /// Executes the virtual orders up to the current block, fails with `TooManyIntervals`
+    /// if it cannot be reached in one execution.
+    fn _execute_virtual_orders(&mut self) -> Result<(), TwammError>;
+
+    /// Executes the virtual orders up to `target`, crossing at most
+    /// `MAX_INTERVALS_PER_EXECUTION` interval boundaries. Returns the timestamp reached.
+    fn _execute_virtual_orders_until(&mut self, target: Timestamp)
+        -> Result<Timestamp, TwammError>;
+
+    /// Checkpoints the accumulators of the pair once per `twap_period`.
+    fn _update_observation(&mut self) -> Result<(), TwammError>;
+
+    /// Sells `sell_rate_0` and `sell_rate_1` over `time_elapsed` and settles the net
+    /// amount against the pair.
+    fn _execute_segment(&mut self, time_elapsed: Timestamp) -> Result<(), TwammError>;
+
+    /// Removes the sell rates of the orders expiring at `boundary` from the order pools.
+    fn _expire_orders(&mut self, boundary: Timestamp) -> Result<(), TwammError>;
+
+    fn _swap(&self, amount_0_out: Balance, amount_1_out: Balance) -> Result<(), TwammError>;
+
+    fn _remove_order(&mut self, order_id: OrderId, owner: AccountId);
+
+    fn _emit_long_term_order_placed_event(
+        &self,
+        _order_id: OrderId,
+        _owner: AccountId,
+        _sell_token_0: bool,
+        _sell_rate: Balance,
+        _expiry: Timestamp,
+    );
+
+    fn _emit_long_term_order_cancelled_event(
+        &self,
+        _order_id: OrderId,
+        _unsold_amount: Balance,
+        _proceeds: Balance,
+    );
+
+    fn _emit_proceeds_withdrawn_event(&self, _order_id: OrderId, _proceeds: Balance);
+
+    fn _emit_virtual_orders_executed_event(
+        &self,
+        _amount_0_in: Balance,
+        _amount_1_in: Balance,
+        _amount_0_out: Balance,
+        _amount_1_out: Balance,
+    );
+}
+
+impl<T: Storage<data::Data> + Storage<reentrancy_guard::Data>> Twamm for T {
+    default fn pair(&self) -> AccountId {
+        self.data::<data::Data>().pair
+    }
+
+    default fn get_token_0(&self) -> AccountId {
+        self.data::<data::Data>().token_0
+    }
+
+    default fn get_token_1(&self) -> AccountId {
+        self.data::<data::Data>().token_1
+    }
+
+    default fn order_interval(&self) -> Timestamp {
+        self.data::<data::Data>().order_interval
+    }
+
+    default fn last_virtual_order_timestamp(&self) -> Timestamp {
+        self.data::<data::Data>().last_virtual_order_timestamp
+    }
+
+    default fn twap_period(&self) -> Timestamp {
+        self.data::<data::Data>().twap_period
+    }
+
+    default fn max_slippage_bps(&self) -> u16 {
+        self.data::<data::Data>().max_slippage_bps
+    }
+
+    default fn observation(&self) -> Option<Observation> {
+        self.data::<data::Data>().observation.clone()
+    }
+
+    default fn sell_rates(&self) -> (Balance, Balance) {
+        (
+            self.data::<data::Data>().sell_rate_0,
+            self.data::<data::Data>().sell_rate_1,
+        )
+    }
+
+    #[modifiers(non_reentrant)]
+    default fn place_long_term_order(
+        &mut self,
+        token_in: AccountId,
+        amount: Balance,
+        number_of_intervals: u32,
+    ) -> Result<OrderId, TwammError> {
+        self._execute_virtual_orders()?;
+
+        let sell_token_0 = if token_in == self.data::<data::Data>().token_0 {
+            true
+        } else if token_in == self.data::<data::Data>().token_1 {
+            false
+        } else {
+            return Err(TwammError::InvalidToken)
+        };
+        let now = Self::env().block_timestamp();
+        let (expiry, sell_rate) = order_schedule(
+            now,
+            self.data::<data::Data>().order_interval,
+            number_of_intervals,
+            amount,
+        )?;
+
+        let owner = Self::env().caller();
+        safe_transfer_from(
+            token_in,
+            owner,
+            Self::env().account_id(),
+            sell_rate * Balance::from(expiry - now),
+        )?;
+
+        if sell_token_0 {
+            self.data::<data::Data>().sell_rate_0 = self
+                .data::<data::Data>()
+                .sell_rate_0
+                .checked_add(sell_rate)
+                .ok_or(TwammError::Arithmetic(MathOp::Add, TwammLocation::SellRate))?;
+        } else {
+            self.data::<data::Data>().sell_rate_1 = self
+                .data::<data::Data>()
+                .sell_rate_1
+                .checked_add(sell_rate)
+                .ok_or(TwammError::Arithmetic(MathOp::Add, TwammLocation::SellRate))?;
+        }
+        let sell_rate_ending = self
+            .data::<data::Data>()
+            .sell_rate_ending
+            .get(&(sell_token_0, expiry))
+            .unwrap_or_default()
+            .checked_add(sell_rate)
+            .ok_or(TwammError::Arithmetic(MathOp::Add, TwammLocation::SellRate))?;
+        self.data::<data::Data>()
+            .sell_rate_ending
+            .insert(&(sell_token_0, expiry), &sell_rate_ending);
+
+        let order_id = self.data::<data::Data>().next_order_id;
+        self.data::<data::Data>().next_order_id = order_id
+            .checked_add(1)
+            .ok_or(TwammError::Arithmetic(MathOp::Add, TwammLocation::OrderId))?;
+        let reward_factor = if sell_token_0 {
+            self.data::<data::Data>().reward_factor_0
+        } else {
+            self.data::<data::Data>().reward_factor_1
+        };
+        self.data::<data::Data>().orders.insert(
+            &order_id,
+            &LongTermOrder {
+                owner,
+                sell_token_0,
+                sell_rate,
+                expiry,
+                reward_factor,
+            },
+        );
+        let mut owner_orders = self.orders_of(owner);
+        owner_orders.push(order_id);
+        self.data::<data::Data>()
+            .orders_of
+            .insert(&owner, &owner_orders);
+
+        self._emit_long_term_order_placed_event(order_id, owner, sell_token_0, sell_rate, expiry);
+
+        Ok(order_id)
+    }
+
+    #[modifiers(non_reentrant)]
+    default fn cancel_long_term_order(
+        &mut self,
+        order_id: OrderId,
+    ) -> Result<(Balance, Balance), TwammError> {
+        self._execute_virtual_orders()?;
+
+        let order = self
+            .data::<data::Data>()
+            .orders
+            .get(&order_id)
+            .ok_or(TwammError::OrderNotFound)?;
+        ensure!(
+            order.owner == Self::env().caller(),
+            TwammError::CallerIsNotOwner
+        );
+        let now = Self::env().block_timestamp();
+        ensure!(now < order.expiry, TwammError::OrderExpired);
+
+        let unsold_amount = order
+            .sell_rate
+            .checked_mul((order.expiry - now).into())
+            .ok_or(TwammError::Arithmetic(
+                MathOp::Mul,
+                TwammLocation::UnsoldAmount,
+            ))?;
+        let (token_in, token_out, reward_factor) = if order.sell_token_0 {
+            self.data::<data::Data>().sell_rate_0 = self
+                .data::<data::Data>()
+                .sell_rate_0
+                .checked_sub(order.sell_rate)
+                .ok_or(TwammError::Arithmetic(MathOp::Sub, TwammLocation::SellRate))?;
+            (
+                self.data::<data::Data>().token_0,
+                self.data::<data::Data>().token_1,
+                self.data::<data::Data>().reward_factor_0,
+            )
+        } else {
+            self.data::<data::Data>().sell_rate_1 = self
+                .data::<data::Data>()
+                .sell_rate_1
+                .checked_sub(order.sell_rate)
+                .ok_or(TwammError::Arithmetic(MathOp::Sub, TwammLocation::SellRate))?;
+            (
+                self.data::<data::Data>().token_1,
+                self.data::<data::Data>().token_0,
+                self.data::<data::Data>().reward_factor_1,
+            )
+        };
+        let sell_rate_ending = self
+            .data::<data::Data>()
+            .sell_rate_ending
+            .get(&(order.sell_token_0, order.expiry))
+            .unwrap_or_default()
+            .checked_sub(order.sell_rate)
+            .ok_or(TwammError::Arithmetic(MathOp::Sub, TwammLocation::SellRate))?;
+        self.data::<data::Data>()
+            .sell_rate_ending
+            .insert(&(order.sell_token_0, order.expiry), &sell_rate_ending);
+        let proceeds = compute_proceeds(order.reward_factor, reward_factor, order.sell_rate)?;
+
+        self._remove_order(order_id, order.owner);
+        safe_transfer(token_in, order.owner, unsold_amount)?;
+        if proceeds > 0 {
+            safe_transfer(token_out, order.owner, proceeds)?;
+        }
+
+        self._emit_long_term_order_cancelled_event(order_id, unsold_amount, proceeds);
+
+        Ok((unsold_amount, proceeds))
+    }
+
+    #[modifiers(non_reentrant)]
+    default fn withdraw_proceeds(&mut self, order_id: OrderId) -> Result<Balance, TwammError> {
+        self._execute_virtual_orders()?;
+
+        let mut order = self
+            .data::<data::Data>()
+            .orders
+            .get(&order_id)
+            .ok_or(TwammError::OrderNotFound)?;
+        ensure!(
+            order.owner == Self::env().caller(),
+            TwammError::CallerIsNotOwner
+        );
+
+        let (token_out, current_reward_factor) = if order.sell_token_0 {
+            (
+                self.data::<data::Data>().token_1,
+                self.data::<data::Data>().reward_factor_0,
+            )
+        } else {
+            (
+                self.data::<data::Data>().token_0,
+                self.data::<data::Data>().reward_factor_1,
+            )
+        };
+        let proceeds = if Self::env().block_timestamp() >= order.expiry {
+            let reward_factor = self
+                .data::<data::Data>()
+                .reward_factor_at_expiry
+                .get(&(order.sell_token_0, order.expiry))
+                .unwrap_or_default();
+            self._remove_order(order_id, order.owner);
+            compute_proceeds(order.reward_factor, reward_factor, order.sell_rate)?
+        } else {
+            let proceeds =
+                compute_proceeds(order.reward_factor, current_reward_factor, order.sell_rate)?;
+            order.reward_factor = current_reward_factor;
+            self.data::<data::Data>().orders.insert(&order_id, &order);
+            proceeds
+        };
+
+        if proceeds > 0 {
+            safe_transfer(token_out, order.owner, proceeds)?;
+        }
+
+        self._emit_proceeds_withdrawn_event(order_id, proceeds);
+
+        Ok(proceeds)
+    }
+
+    #[modifiers(non_reentrant)]
+    default fn execute_virtual_orders(&mut self) -> Result<(), TwammError> {
+        self._execute_virtual_orders()
+    }
+
+    #[modifiers(non_reentrant)]
+    default fn execute_virtual_orders_until(
+        &mut self,
+        timestamp: Timestamp,
+    ) -> Result<Timestamp, TwammError> {
+        let now = Self::env().block_timestamp();
+        self._execute_virtual_orders_until(if timestamp < now { timestamp } else { now })
+    }
+
+    default fn get_order(&self, order_id: OrderId) -> Option<LongTermOrder> {
+        self.data::<data::Data>().orders.get(&order_id)
+    }
+
+    default fn orders_of(&self, owner: AccountId) -> Vec<OrderId> {
+        self.data::<data::Data>()
+            .orders_of
+            .get(&owner)
+            .unwrap_or_default()
+    }
+
+    default fn next_order_id(&self) -> OrderId {
+        self.data::<data::Data>().next_order_id
+    }
+}
+
+/// Returns the expiry and the sell rate of an order selling `amount` from `now` until the end
+/// of `number_of_intervals` intervals after the current one.
+pub fn order_schedule(
+    now: Timestamp,
+    interval: Timestamp,
+    number_of_intervals: u32,
+    amount: Balance,
+) -> Result<(Timestamp, Balance), TwammError> {
+    ensure!(interval > 0, TwammError::InvalidInterval);
+    let expiry = (now / interval)
+        .checked_add(number_of_intervals.into())
+        .and_then(|n| n.checked_add(1))
+        .and_then(|n| n.checked_mul(interval))
+        .ok_or(TwammError::Arithmetic(MathOp::Mul, TwammLocation::Expiry))?;
+    let sell_rate = amount / Balance::from(expiry - now);
+    ensure!(sell_rate > 0, TwammError::InsufficientAmount);
+    Ok((expiry, sell_rate))
+}
+
+/// Returns the end of the segment starting at `last`, the next interval boundary or `target`,
+/// and whether it is an interval boundary.
+pub fn next_segment_end(
+    last: Timestamp,
+    target: Timestamp,
+    interval: Timestamp,
+) -> (Timestamp, bool) {
+    let next_boundary = (last / interval).saturating_add(1).saturating_mul(interval);
+    if next_boundary < target {
+        (next_boundary, true)
+    } else {
+        (target, target == next_boundary)
+    }
+}
+
+/// Ensures each order pool receives at least the value of the amount it sold at the TWAP of
+/// the pair minus `max_slippage_bps`.
+pub fn ensure_segment_near_twap(
+    amount_0: Balance,
+    amount_1: Balance,
+    segment: &SegmentAmounts,
+    price_0: Price,
+    price_1: Price,
+    max_slippage_bps: u16,
+) -> Result<(), TwammError> {
+    ensure!(
+        segment.proceeds_0 >= min_amount_out(amount_0, price_0, max_slippage_bps)?,
+        TwammError::PriceDeviation
+    );
+    ensure!(
+        segment.proceeds_1 >= min_amount_out(amount_1, price_1, max_slippage_bps)?,
+        TwammError::PriceDeviation
+    );
+    Ok(())
+}
+
+/// Matches `amount_0` and `amount_1` against each other at the pair price and swaps
+/// the remainder along the constant product curve, paying `fee_bps`.
+pub fn compute_segment(
+    amount_0: Balance,
+    amount_1: Balance,
+    reserve_0: Balance,
+    reserve_1: Balance,
+    fee_bps: u16,
+) -> Result<SegmentAmounts, TwammError> {
+    if amount_0 == 0 && amount_1 == 0 {
+        return Ok(Default::default())
+    }
+    ensure!(
+        reserve_0 > 0 && reserve_1 > 0,
+        TwammError::InsufficientLiquidity
+    );
+
+    if casted_mul(amount_0, reserve_1) >= casted_mul(amount_1, reserve_0) {
+        // `amount_1` is fully matched by a part of `amount_0`.
+        let matched_0: Balance = (casted_mul(amount_1, reserve_0) / U256::from(reserve_1))
+            .try_into()
+            .map_err(|_| TwammError::Arithmetic(MathOp::Cast, TwammLocation::Segment))?;
+        let amount_0_in = amount_0 - matched_0;
+        let amount_1_out = if amount_0_in > 0 {
+            get_amount_out_with_fee(amount_0_in, reserve_0, reserve_1, fee_bps)?
+        } else {
+            0
+        };
+        Ok(SegmentAmounts {
+            proceeds_0: amount_1
+                .checked_add(amount_1_out)
+                .ok_or(TwammError::Arithmetic(MathOp::Add, TwammLocation::Segment))?,
+            proceeds_1: matched_0,
+            amount_0_in,
+            amount_1_out,
+            ..Default::default()
+        })
+    } else {
+        // `amount_0` is fully matched by a part of `amount_1`.
+        let matched_1: Balance = (casted_mul(amount_0, reserve_1) / U256::from(reserve_0))
+            .try_into()
+            .map_err(|_| TwammError::Arithmetic(MathOp::Cast, TwammLocation::Segment))?;
+        let amount_1_in = amount_1 - matched_1;
+        let amount_0_out = if amount_1_in > 0 {
+            get_amount_out_with_fee(amount_1_in, reserve_1, reserve_0, fee_bps)?
+        } else {
+            0
+        };
+        Ok(SegmentAmounts {
+            proceeds_0: matched_1,
+            proceeds_1: amount_0
+                .checked_add(amount_0_out)
+                .ok_or(TwammError::Arithmetic(MathOp::Add, TwammLocation::Segment))?,
+            amount_1_in,
+            amount_0_out,
+            ..Default::default()
+        })
+    }
+}
+
+#[inline]
+fn add_reward(
+    reward_factor: WrappedU256,
+    proceeds: Balance,
+    sell_rate: Balance,
+) -> Result<WrappedU256, TwammError> {
+    if sell_rate == 0 {
+        return Ok(reward_factor)
+    }
+    Ok(U256::from(reward_factor)
+        .checked_add(casted_mul(proceeds, REWARD_FACTOR_PRECISION) / U256::from(sell_rate))
+        .ok_or(TwammError::Arithmetic(
+            MathOp::Add,
+            TwammLocation::RewardFactor,
+        ))?
+        .into())
+}
+
+#[inline]
+fn compute_proceeds(
+    reward_factor_start: WrappedU256,
+    reward_factor_end: WrappedU256,
+    sell_rate: Balance,
+) -> Result<Balance, TwammError> {
+    U256::from(reward_factor_end)
+        .checked_sub(reward_factor_start.into())
+        .ok_or(TwammError::Arithmetic(MathOp::Sub, TwammLocation::Proceeds))?
+        .checked_mul(sell_rate.into())
+        .ok_or(TwammError::Arithmetic(MathOp::Mul, TwammLocation::Proceeds))?
+        .checked_div(REWARD_FACTOR_PRECISION.into())
+        .ok_or(TwammError::Arithmetic(MathOp::Div, TwammLocation::Proceeds))?
+        .try_into()
+        .map_err(|_| TwammError::Arithmetic(MathOp::Cast, TwammLocation::Proceeds))
+}
+
+impl<T: Storage<data::Data>> Internal for T {
+    default fn _execute_virtual_orders(&mut self) -> Result<(), TwammError> {
+        let now = Self::env().block_timestamp();
+        ensure!(
+            self._execute_virtual_orders_until(now)? == now,
+            TwammError::TooManyIntervals
+        );
+        Ok(())
+    }
+
+    default fn _execute_virtual_orders_until(
+        &mut self,
+        target: Timestamp,
+    ) -> Result<Timestamp, TwammError> {
+        let interval = self.data::<data::Data>().order_interval;
+        ensure!(interval > 0, TwammError::InvalidInterval);
+        let mut last = self.data::<data::Data>().last_virtual_order_timestamp;
+        let mut boundaries = 0;
+        let mut observed = false;
+        while last < target {
+            if self.data::<data::Data>().sell_rate_0 == 0
+                && self.data::<data::Data>().sell_rate_1 == 0
+            {
+                // Nothing is sold and no order expires until the next order is placed.
+                last = target;
+                break
+            }
+            if boundaries == MAX_INTERVALS_PER_EXECUTION {
+                break
+            }
+            if !observed {
+                self._update_observation()?;
+                observed = true;
+            }
+            let (segment_end, at_boundary) = next_segment_end(last, target, interval);
+            self._execute_segment(segment_end - last)?;
+            if at_boundary {
+                self._expire_orders(segment_end)?;
+                boundaries += 1;
+            }
+            last = segment_end;
+        }
+        self.data::<data::Data>().last_virtual_order_timestamp = last;
+        Ok(last)
+    }
+
+    default fn _update_observation(&mut self) -> Result<(), TwammError> {
+        let pair = self.data::<data::Data>().pair;
+        let last = self.data::<data::Data>().observation.clone();
+        let observation = update_observation(
+            &pair,
+            last,
+            self.data::<data::Data>().twap_period,
+            Self::env().block_timestamp(),
+        )?;
+        if observation.is_some() {
+            self.data::<data::Data>().observation = observation;
+        }
+        Ok(())
+    }
+
+    default fn _execute_segment(&mut self, time_elapsed: Timestamp) -> Result<(), TwammError> {
+        let sell_rate_0 = self.data::<data::Data>().sell_rate_0;
+        let sell_rate_1 = self.data::<data::Data>().sell_rate_1;
+        if sell_rate_0 == 0 && sell_rate_1 == 0 {
+            return Ok(())
+        }
+        let amount_0 = sell_rate_0
+            .checked_mul(time_elapsed.into())
+            .ok_or(TwammError::Arithmetic(MathOp::Mul, TwammLocation::Segment))?;
+        let amount_1 = sell_rate_1
+            .checked_mul(time_elapsed.into())
+            .ok_or(TwammError::Arithmetic(MathOp::Mul, TwammLocation::Segment))?;
+        let pair = self.data::<data::Data>().pair;
+        let (reserve_0, reserve_1, _) = PairRef::get_reserves(&pair);
+        let segment = compute_segment(
+            amount_0,
+            amount_1,
+            reserve_0,
+            reserve_1,
+            PairRef::get_fee(&pair),
+        )?;
+        let token_0 = self.data::<data::Data>().token_0;
+        let token_1 = self.data::<data::Data>().token_1;
+        let observation = self
+            .data::<data::Data>()
+            .observation
+            .clone()
+            .unwrap_or_default();
+        ensure_segment_near_twap(
+            amount_0,
+            amount_1,
+            &segment,
+            consult(&observation, token_0, token_1)?,
+            consult(&observation, token_1, token_0)?,
+            self.data::<data::Data>().max_slippage_bps,
+        )?;
+
+        if segment.amount_1_out > 0 {
+            safe_transfer(token_0, pair, segment.amount_0_in)?;
+            self._swap(0, segment.amount_1_out)?;
+        } else if segment.amount_0_out > 0 {
+            safe_transfer(token_1, pair, segment.amount_1_in)?;
+            self._swap(segment.amount_0_out, 0)?;
+        }
+
+        self.data::<data::Data>().reward_factor_0 = add_reward(
+            self.data::<data::Data>().reward_factor_0,
+            segment.proceeds_0,
+            sell_rate_0,
+        )?;
+        self.data::<data::Data>().reward_factor_1 = add_reward(
+            self.data::<data::Data>().reward_factor_1,
+            segment.proceeds_1,
+            sell_rate_1,
+        )?;
+
+        self._emit_virtual_orders_executed_event(
+            segment.amount_0_in,
+            segment.amount_1_in,
+            segment.amount_0_out,
+            segment.amount_1_out,
+        );
+        Ok(())
+    }
+
+    default fn _expire_orders(&mut self, boundary: Timestamp) -> Result<(), TwammError> {
+        for sell_token_0 in [true, false] {
+            let ending = self
+                .data::<data::Data>()
+                .sell_rate_ending
+                .get(&(sell_token_0, boundary))
+                .unwrap_or_default();
+            if ending == 0 {
+                continue
+            }
+            let reward_factor = if sell_token_0 {
+                self.data::<data::Data>().sell_rate_0 = self
+                    .data::<data::Data>()
+                    .sell_rate_0
+                    .checked_sub(ending)
+                    .ok_or(TwammError::Arithmetic(MathOp::Sub, TwammLocation::SellRate))?;
+                self.data::<data::Data>().reward_factor_0
+            } else {
+                self.data::<data::Data>().sell_rate_1 = self
+                    .data::<data::Data>()
+                    .sell_rate_1
+                    .checked_sub(ending)
+                    .ok_or(TwammError::Arithmetic(MathOp::Sub, TwammLocation::SellRate))?;
+                self.data::<data::Data>().reward_factor_1
+            };
+            self.data::<data::Data>()
+                .reward_factor_at_expiry
+                .insert(&(sell_token_0, boundary), &reward_factor);
+        }
+        Ok(())
+    }
+
+    default fn _swap(
+        &self,
+        amount_0_out: Balance,
+        amount_1_out: Balance,
+    ) -> Result<(), TwammError> {
+        let pair = self.data::<data::Data>().pair;
+        Ok(call_result(
+            pair,
+            PairRef::swap_builder(&pair, amount_0_out, amount_1_out, Self::env().account_id())
+                .call_flags(CallFlags::default().set_allow_reentry(true))
+                .try_invoke(),
+        )??)
+    }
+
+    default fn _remove_order(&mut self, order_id: OrderId, owner: AccountId) {
+        self.data::<data::Data>().orders.remove(&order_id);
+        let mut owner_orders = self
+            .data::<data::Data>()
+            .orders_of
+            .get(&owner)
+            .unwrap_or_default();
+        owner_orders.retain(|id| *id != order_id);
+        self.data::<data::Data>()
+            .orders_of
+            .insert(&owner, &owner_orders);
+    }
+
+    default fn _emit_long_term_order_placed_event(
+        &self,
+        _order_id: OrderId,
+        _owner: AccountId,
+        _sell_token_0: bool,
+        _sell_rate: Balance,
+        _expiry: Timestamp,
+    ) {
+    }
+
+    default fn _emit_long_term_order_cancelled_event(
+        &self,
+        _order_id: OrderId,
+        _unsold_amount: Balance,
+        _proceeds: Balance,
+    ) {
+    }
+
+    default fn _emit_proceeds_withdrawn_event(&self, _order_id: OrderId, _proceeds: Balance) {}
+
+    default fn _emit_virtual_orders_executed_event(
+        &self,
+        _amount_0_in: Balance,
+        _amount_1_in: Balance,
+        _amount_0_out: Balance,
+        _amount_1_out: Balance,
+    ) {
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        helpers::math::DEFAULT_FEE_BPS,
+        traits::types::PRICE_ONE,
+    };
+
+    #[ink::test]
+    fn order_schedule_ends_on_interval_boundary() {
+        // Placed 30ms into an interval of 100ms, sold until the end of the next one.
+        assert_eq!(order_schedule(130, 100, 1, 1_000), Ok((300, 5)));
+        assert_eq!(
+            order_schedule(130, 100, 1, 169),
+            Err(TwammError::InsufficientAmount)
+        );
+        assert_eq!(
+            order_schedule(130, 0, 1, 1_000),
+            Err(TwammError::InvalidInterval)
+        );
+    }
+
+    #[ink::test]
+    fn next_segment_end_stops_on_boundaries() {
+        assert_eq!(next_segment_end(130, 450, 100), (200, true));
+        assert_eq!(next_segment_end(200, 450, 100), (300, true));
+        assert_eq!(next_segment_end(400, 450, 100), (450, false));
+        assert_eq!(next_segment_end(400, 500, 100), (500, true));
+    }
+
+    #[ink::test]
+    fn segment_far_from_twap_fails() {
+        let segment = compute_segment(1_000, 0, 1_000_000, 2_000_000, DEFAULT_FEE_BPS).unwrap();
+        // 1_000 at a price of 2, less the fee and the price impact.
+        assert_eq!(segment.proceeds_0, 1_992);
+        assert_eq!(
+            ensure_segment_near_twap(1_000, 0, &segment, 2 * PRICE_ONE, PRICE_ONE / 2, 100),
+            Ok(())
+        );
+        // The pair trades 1% below a TWAP of 2.02.
+        assert_eq!(
+            ensure_segment_near_twap(
+                1_000,
+                0,
+                &segment,
+                2 * PRICE_ONE + PRICE_ONE / 50,
+                PRICE_ONE / 2,
+                100
+            ),
+            Err(TwammError::PriceDeviation)
+        );
+    }
+
+    #[ink::test]
+    fn proceeds_are_shared_by_sell_rate() {
+        // Orders selling 1 and 3 per millisecond share the proceeds of two segments, the
+        // second one placed after the first segment.
+        let reward_factor_start = WrappedU256::default();
+        let segment = compute_segment(100, 0, 1_000_000, 1_000_000, DEFAULT_FEE_BPS).unwrap();
+        let reward_factor_placed = add_reward(reward_factor_start, segment.proceeds_0, 1).unwrap();
+        let proceeds_first = segment.proceeds_0;
+
+        let segment = compute_segment(400, 0, 1_000_000, 1_000_000, DEFAULT_FEE_BPS).unwrap();
+        let reward_factor_end = add_reward(reward_factor_placed, segment.proceeds_0, 4).unwrap();
+
+        let proceeds_0 = compute_proceeds(reward_factor_start, reward_factor_end, 1).unwrap();
+        let proceeds_1 = compute_proceeds(reward_factor_placed, reward_factor_end, 3).unwrap();
+        assert_eq!(proceeds_0, proceeds_first + segment.proceeds_0 / 4);
+        assert_eq!(proceeds_1, segment.proceeds_0 * 3 / 4);
+        assert!(proceeds_0 + proceeds_1 <= proceeds_first + segment.proceeds_0);
+        assert_eq!(
+            compute_proceeds(reward_factor_end, reward_factor_placed, 3),
+            Err(TwammError::Arithmetic(MathOp::Sub, TwammLocation::Proceeds))
+        );
+    }
+
+    #[ink::test]
+    fn compute_segment_without_orders() {
+        assert_eq!(
+            compute_segment(0, 0, 0, 0, DEFAULT_FEE_BPS),
+            Ok(SegmentAmounts::default())
+        );
+    }
+
+    #[ink::test]
+    fn compute_segment_fully_matched() {
+        let segment = compute_segment(100, 200, 1_000, 2_000, DEFAULT_FEE_BPS).unwrap();
+        assert_eq!(segment.proceeds_0, 200);
+        assert_eq!(segment.proceeds_1, 100);
+        assert_eq!(segment.amount_0_in, 0);
+        assert_eq!(segment.amount_1_out, 0);
+    }
+
+    #[ink::test]
+    fn compute_segment_swaps_remainder() {
+        let segment = compute_segment(1_000, 1_000, 1_000_000, 1_000_000, DEFAULT_FEE_BPS).unwrap();
+        assert_eq!(segment.proceeds_0, 1_000);
+        assert_eq!(segment.proceeds_1, 1_000);
+
+        let segment = compute_segment(3_000, 1_000, 1_000_000, 1_000_000, DEFAULT_FEE_BPS).unwrap();
+        assert_eq!(segment.proceeds_1, 1_000);
+        assert_eq!(segment.amount_0_in, 2_000);
+        // 2_000 * 997 * 1_000_000 / (1_000_000 * 1_000 + 2_000 * 997)
+        assert_eq!(segment.amount_1_out, 1_990);
+        assert_eq!(segment.proceeds_0, 2_990);
+
+        let segment = compute_segment(1_000, 3_000, 1_000_000, 1_000_000, DEFAULT_FEE_BPS).unwrap();
+        assert_eq!(segment.proceeds_0, 1_000);
+        assert_eq!(segment.amount_1_in, 2_000);
+        assert_eq!(segment.amount_0_out, 1_990);
+        assert_eq!(segment.proceeds_1, 2_990);
+    }
+}
@@ -0,0 +1,163 @@
+use super::{
+    errors::MathOp,
+    router::RouterError,
+};
+use crate::helpers::helper::HelperError;
+pub use crate::traits::types::{
+    Observation,
+    Price,
+};
+use ink::prelude::vec::Vec;
+use openbrush::{
+    contracts::{
+        reentrancy_guard::*,
+        traits::psp22::PSP22Error,
+    },
+    traits::{
+        AccountId,
+        Balance,
+        Timestamp,
+    },
+};
+
+pub type OrderId = u64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub enum Trigger {
+    /// Executes once the TWAP of `token_in` in `token_out` is lower or equal to the trigger price.
+    StopLoss,
+    /// Executes once the TWAP of `token_in` in `token_out` is greater or equal to the trigger price.
+    TakeProfit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct ConditionalOrderInfo {
+    pub owner: AccountId,
+    pub token_in: AccountId,
+    pub token_out: AccountId,
+    pub amount_in: Balance,
+    pub trigger: Trigger,
+    pub trigger_price: Price,
+    /// Maximum slippage from the TWAP accepted at execution, in basis points.
+    pub max_slippage_bps: u16,
+    /// Amount of `token_in` paid to the keeper executing the order.
+    pub execution_fee: Balance,
+    pub expiry: Timestamp,
+}
+
+#[openbrush::wrapper]
+pub type ConditionalOrderRef = dyn ConditionalOrder;
+
+#[openbrush::trait_definition]
+pub trait ConditionalOrder {
+    #[ink(message)]
+    fn factory(&self) -> AccountId;
+
+    #[ink(message)]
+    fn router(&self) -> AccountId;
+
+    /// Minimum time between two observations of a pair, in milliseconds.
+    #[ink(message)]
+    fn twap_period(&self) -> Timestamp;
+
+    /// Deposits `amount_in` + `execution_fee` of `token_in` and places an order selling
+    /// `amount_in` for `token_out` once the TWAP reaches `trigger_price`.
+    #[ink(message)]
+    fn place_order(
+        &mut self,
+        token_in: AccountId,
+        token_out: AccountId,
+        amount_in: Balance,
+        trigger: Trigger,
+        trigger_price: Price,
+        max_slippage_bps: u16,
+        execution_fee: Balance,
+        expiry: Timestamp,
+    ) -> Result<OrderId, ConditionalOrderError>;
+
+    /// Refunds `amount_in` and `execution_fee` to the owner.
+    #[ink(message)]
+    fn cancel_order(&mut self, order_id: OrderId) -> Result<Balance, ConditionalOrderError>;
+
+    /// Swaps the order through the router if its trigger is reached and pays the
+    /// execution fee to the caller.
+    #[ink(message)]
+    fn execute_order(&mut self, order_id: OrderId) -> Result<Vec<Balance>, ConditionalOrderError>;
+
+    /// Checkpoints the price accumulators of `pair`, callable by anyone once per `twap_period`.
+    /// The accumulators are extrapolated to the current block at the reserves of the pair.
+    #[ink(message)]
+    fn update_observation(&mut self, pair: AccountId) -> Result<(), ConditionalOrderError>;
+
+    #[ink(message)]
+    fn observation(&self, pair: AccountId) -> Option<Observation>;
+
+    /// Returns the TWAP of `token_in` in `token_out` over the last completed period.
+    #[ink(message)]
+    fn twap(
+        &self,
+        token_in: AccountId,
+        token_out: AccountId,
+    ) -> Result<Price, ConditionalOrderError>;
+
+    #[ink(message)]
+    fn get_order(&self, order_id: OrderId) -> Option<ConditionalOrderInfo>;
+
+    /// Returns the ids of the open orders of `owner`.
+    #[ink(message)]
+    fn orders_of(&self, owner: AccountId) -> Vec<OrderId>;
+
+    #[ink(message)]
+    fn next_order_id(&self) -> OrderId;
+}
+
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum ConditionalOrderError {
+    PSP22Error(PSP22Error),
+    RouterError(RouterError),
+    HelperError(HelperError),
+    ReentrancyGuardError(ReentrancyGuardError),
+    PairNotFound,
+    OrderNotFound,
+    CallerIsNotOwner,
+    Expired,
+    InvalidExpiry,
+    InsufficientAmount,
+    InvalidSlippage,
+    ObservationNotDue,
+    TwapNotReady,
+    TriggerNotReached,
+    Arithmetic(MathOp, ConditionalOrderLocation),
+}
+
+/// Step of the conditional order contract in which an arithmetic error occurred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum ConditionalOrderLocation {
+    /// Amount escrowed or refunded, including the execution fee.
+    Deposit,
+    OrderId,
+}
+
+macro_rules! impl_froms {
+    ( $( $error:ident ),* ) => {
+        $(
+            impl From<$error> for ConditionalOrderError {
+                fn from(error: $error) -> Self {
+                    ConditionalOrderError::$error(error)
+                }
+            }
+        )*
+    };
+}
+
+impl_froms!(PSP22Error, RouterError, HelperError, ReentrancyGuardError);
@@ -0,0 +1,159 @@
+use super::{
+    errors::{
+        CallFailed,
+        MathOp,
+    },
+    router::RouterError,
+};
+use crate::helpers::helper::HelperError;
+pub use crate::traits::types::{
+    Observation,
+    Price,
+};
+use ink::prelude::vec::Vec;
+use openbrush::{
+    contracts::{
+        reentrancy_guard::*,
+        traits::psp22::PSP22Error,
+    },
+    traits::{
+        AccountId,
+        Balance,
+        Timestamp,
+    },
+};
+
+pub type PositionId = u64;
+
+#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct Position {
+    pub owner: AccountId,
+    pub token_in: AccountId,
+    pub token_out: AccountId,
+    /// Amount of `token_in` sold at each execution.
+    pub amount_per_interval: Balance,
+    /// Amount of `token_in` paid to the keeper at each execution.
+    pub execution_fee: Balance,
+    pub interval: Timestamp,
+    pub intervals_remaining: u32,
+    pub next_execution: Timestamp,
+    /// Maximum slippage from the TWAP accepted at execution, in basis points.
+    pub max_slippage_bps: u16,
+    /// Amount of `token_out` bought and not withdrawn yet.
+    pub amount_out_accumulated: Balance,
+}
+
+#[openbrush::wrapper]
+pub type DcaRef = dyn Dca;
+
+#[openbrush::trait_definition]
+pub trait Dca {
+    #[ink(message)]
+    fn factory(&self) -> AccountId;
+
+    #[ink(message)]
+    fn router(&self) -> AccountId;
+
+    /// Minimum time between two observations of a pair, in milliseconds.
+    #[ink(message)]
+    fn twap_period(&self) -> Timestamp;
+
+    /// Deposits (`amount_per_interval` + `execution_fee`) * `number_of_intervals` of
+    /// `token_in` to buy `token_out` every `interval`, the first buy being executable now.
+    #[ink(message)]
+    fn create_position(
+        &mut self,
+        token_in: AccountId,
+        token_out: AccountId,
+        amount_per_interval: Balance,
+        number_of_intervals: u32,
+        interval: Timestamp,
+        execution_fee: Balance,
+        max_slippage_bps: u16,
+    ) -> Result<PositionId, DcaError>;
+
+    /// Executes the next buy of the position through the router and pays the execution
+    /// fee to the caller. Returns the amount of `token_out` bought.
+    #[ink(message)]
+    fn execute(&mut self, position_id: PositionId) -> Result<Balance, DcaError>;
+
+    /// Sends the `token_out` bought so far to the owner.
+    #[ink(message)]
+    fn withdraw(&mut self, position_id: PositionId) -> Result<Balance, DcaError>;
+
+    /// Withdraws the `token_out` bought, refunds the remaining `token_in` and removes the position.
+    #[ink(message)]
+    fn close_position(&mut self, position_id: PositionId) -> Result<(Balance, Balance), DcaError>;
+
+    /// Checkpoints the price accumulators of `pair`, callable by anyone once per `twap_period`.
+    #[ink(message)]
+    fn update_observation(&mut self, pair: AccountId) -> Result<(), DcaError>;
+
+    #[ink(message)]
+    fn observation(&self, pair: AccountId) -> Option<Observation>;
+
+    #[ink(message)]
+    fn get_position(&self, position_id: PositionId) -> Option<Position>;
+
+    #[ink(message)]
+    fn positions_of(&self, owner: AccountId) -> Vec<PositionId>;
+
+    #[ink(message)]
+    fn next_position_id(&self) -> PositionId;
+}
+
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum DcaError {
+    PSP22Error(PSP22Error),
+    RouterError(RouterError),
+    HelperError(HelperError),
+    ReentrancyGuardError(ReentrancyGuardError),
+    CallFailed(CallFailed),
+    PairNotFound,
+    PositionNotFound,
+    CallerIsNotOwner,
+    InsufficientAmount,
+    InvalidInterval,
+    InvalidSlippage,
+    NotDue,
+    PositionCompleted,
+    ObservationNotDue,
+    TwapNotReady,
+    Arithmetic(MathOp, DcaLocation),
+}
+
+/// Step of the DCA contract in which an arithmetic error occurred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum DcaLocation {
+    /// Amount escrowed for the remaining intervals.
+    Deposit,
+    PositionId,
+    NextExecution,
+    AmountOutAccumulated,
+}
+
+macro_rules! impl_froms {
+    ( $( $error:ident ),* ) => {
+        $(
+            impl From<$error> for DcaError {
+                fn from(error: $error) -> Self {
+                    DcaError::$error(error)
+                }
+            }
+        )*
+    };
+}
+
+impl_froms!(
+    PSP22Error,
+    RouterError,
+    HelperError,
+    ReentrancyGuardError,
+    CallFailed
+);
@@ -0,0 +1,125 @@
+use ink::{
+    primitives::Hash,
+    LangError,
+};
+use openbrush::traits::AccountId;
+
+/// Arithmetic operation which failed, carried by the `Arithmetic` error variants along with
+/// the location of the failure in the contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum MathOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    /// Narrowing of a `U256` intermediate result.
+    Cast,
+    /// Fee of more than `BPS`, or of `BPS` where something must be left after the fee.
+    InvalidFee,
+    /// Result of `amm_math` out of the range of its type, `U256` or `u128`.
+    Overflow,
+}
+
+impl From<amm_math::MathError> for MathOp {
+    fn from(error: amm_math::MathError) -> Self {
+        match error {
+            amm_math::MathError::DivisionByZero => MathOp::Div,
+            amm_math::MathError::Overflow => MathOp::Overflow,
+            amm_math::MathError::Underflow => MathOp::Sub,
+            amm_math::MathError::InvalidFee => MathOp::InvalidFee,
+        }
+    }
+}
+
+/// Encodable mirror of `ink::env::Error`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum EnvError {
+    Decode,
+    CalleeTrapped,
+    CalleeReverted,
+    KeyNotFound,
+    TransferFailed,
+    CodeNotFound,
+    NotCallable,
+    Other,
+}
+
+impl From<ink::env::Error> for EnvError {
+    fn from(error: ink::env::Error) -> Self {
+        match error {
+            ink::env::Error::Decode(_) => EnvError::Decode,
+            ink::env::Error::CalleeTrapped => EnvError::CalleeTrapped,
+            ink::env::Error::CalleeReverted => EnvError::CalleeReverted,
+            ink::env::Error::KeyNotFound => EnvError::KeyNotFound,
+            ink::env::Error::TransferFailed => EnvError::TransferFailed,
+            ink::env::Error::CodeNotFound => EnvError::CodeNotFound,
+            ink::env::Error::NotCallable => EnvError::NotCallable,
+            _ => EnvError::Other,
+        }
+    }
+}
+
+/// Reason a cross-contract call failed before the callee could return its own error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum CallError {
+    Env(EnvError),
+    Lang(LangError),
+}
+
+/// Failure of a call to `callee`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct CallFailed {
+    pub callee: AccountId,
+    pub error: CallError,
+}
+
+/// Unpacks the result of `try_invoke` on `callee` into the result of the message.
+pub fn call_result<R, E>(
+    callee: AccountId,
+    result: Result<ink::MessageResult<Result<R, E>>, ink::env::Error>,
+) -> Result<Result<R, E>, CallFailed> {
+    match result {
+        Ok(Ok(res)) => Ok(res),
+        Ok(Err(err)) => {
+            Err(CallFailed {
+                callee,
+                error: CallError::Lang(err),
+            })
+        }
+        Err(err) => {
+            Err(CallFailed {
+                callee,
+                error: CallError::Env(err.into()),
+            })
+        }
+    }
+}
+
+/// Unpacks the result of `try_instantiate` of `code_hash`. A failed instantiation has no
+/// address, the code hash is reported as its callee.
+pub fn instantiate_result<C>(
+    code_hash: Hash,
+    result: Result<ink::ConstructorResult<C>, ink::env::Error>,
+) -> Result<C, CallFailed> {
+    let mut callee = [0; 32];
+    callee.copy_from_slice(code_hash.as_ref());
+    match result {
+        Ok(Ok(instance)) => Ok(instance),
+        Ok(Err(err)) => {
+            Err(CallFailed {
+                callee: callee.into(),
+                error: CallError::Lang(err),
+            })
+        }
+        Err(err) => {
+            Err(CallFailed {
+                callee: callee.into(),
+                error: CallError::Env(err.into()),
+            })
+        }
+    }
+}
@@ -1,6 +1,77 @@
-use crate::traits::pair::PairError;
-use ink::primitives::Hash;
-use openbrush::traits::AccountId;
+use crate::{
+    helpers::helper::HelperError,
+    traits::{
+        errors::CallFailed,
+        lp_rewards::LpRewardsError,
+        pair::{
+            DynamicFeeConfig,
+            PairError,
+            ProtocolFeeMode,
+        },
+    },
+};
+use ink::{
+    prelude::vec::Vec,
+    primitives::Hash,
+};
+use openbrush::traits::{
+    AccountId,
+    Balance,
+};
+
+/// Identifier of a pool type in the factory registry.
+pub type PoolTypeId = u32;
+
+/// Pool type registered at construction for `PairContract` (constant-product pools).
+pub const CONSTANT_PRODUCT_POOL_TYPE: PoolTypeId = 0;
+
+/// How the factory calls the constructor of a pool contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub enum ConstructorKind {
+    /// `new()` without arguments, as `PairContract`.
+    Default,
+    /// `new_with_params(params: Vec<u8>)`, params are pool specific (e.g. amplification).
+    WithParams,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct PoolTypeInfo {
+    pub code_hash: Hash,
+    pub constructor: ConstructorKind,
+    pub enabled: bool,
+}
+
+/// Protocol fee settings of a pair overriding the global `fee_to`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct PairFeeConfig {
+    pub enabled: bool,
+    /// Recipient of the protocol fee, the global `fee_to` if zero.
+    pub fee_to: AccountId,
+    /// Part of the protocol fee sent to the pair creator, in basis points.
+    pub creator_share_bps: u16,
+}
+
+/// Protocol fee recipients of a pair, as read by the pair in `_mint_fee`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct ProtocolFee {
+    /// The protocol fee is off if zero.
+    pub fee_to: AccountId,
+    pub creator: AccountId,
+    pub creator_share_bps: u16,
+}
 
 #[openbrush::wrapper]
 pub type FactoryRef = dyn Factory;
@@ -13,6 +84,7 @@ pub trait Factory {
     #[ink(message)]
     fn all_pairs_length(&self) -> u64;
 
+    /// Code hash of the constant-product pool type.
     #[ink(message)]
     fn pair_contract_code_hash(&self) -> Hash;
 
@@ -23,6 +95,134 @@ pub trait Factory {
         token_b: AccountId,
     ) -> Result<AccountId, FactoryError>;
 
+    /// Creates the constant-product pair of `token_a` and `token_b` on behalf of `creator`,
+    /// recorded as the pair creator. Only callable by a trusted router.
+    #[ink(message)]
+    fn create_pair_for(
+        &mut self,
+        token_a: AccountId,
+        token_b: AccountId,
+        creator: AccountId,
+    ) -> Result<AccountId, FactoryError>;
+
+    /// Instantiates a pool of a registered and enabled `pool_type` for `tokens`.
+    /// `params` are forwarded to the pool constructor for `ConstructorKind::WithParams`.
+    #[ink(message)]
+    fn create_pool(
+        &mut self,
+        pool_type: PoolTypeId,
+        tokens: Vec<AccountId>,
+        params: Vec<u8>,
+    ) -> Result<AccountId, FactoryError>;
+
+    /// Registers or replaces `pool_type`. Pools are routed through the `Pair` interface,
+    /// which the contracts of every pool type implement.
+    #[ink(message)]
+    fn register_pool_type(
+        &mut self,
+        pool_type: PoolTypeId,
+        code_hash: Hash,
+        constructor: ConstructorKind,
+    ) -> Result<(), FactoryError>;
+
+    #[ink(message)]
+    fn set_pool_type_enabled(
+        &mut self,
+        pool_type: PoolTypeId,
+        enabled: bool,
+    ) -> Result<(), FactoryError>;
+
+    #[ink(message)]
+    fn pool_type(&self, pool_type: PoolTypeId) -> Option<PoolTypeInfo>;
+
+    #[ink(message)]
+    fn pool_types(&self) -> Vec<PoolTypeId>;
+
+    #[ink(message)]
+    fn pool_type_of(&self, pool: AccountId) -> Option<PoolTypeId>;
+
+    #[ink(message)]
+    fn get_pool(
+        &self,
+        pool_type: PoolTypeId,
+        token_a: AccountId,
+        token_b: AccountId,
+    ) -> Option<AccountId>;
+
+    /// Sets the reward token and distributor of the LP rewards of `pool`.
+    #[ink(message)]
+    fn set_lp_rewards(
+        &mut self,
+        pool: AccountId,
+        reward_token: AccountId,
+        reward_distributor: AccountId,
+    ) -> Result<(), FactoryError>;
+
+    /// Returns the account which created `pair`, the originating account for pairs created
+    /// through a trusted router.
+    #[ink(message)]
+    fn pair_creator(&self, pair: AccountId) -> Option<AccountId>;
+
+    /// Transfers the creator share of `pair`, only callable by its creator or the fee setter.
+    #[ink(message)]
+    fn set_pair_creator(&mut self, pair: AccountId, creator: AccountId)
+        -> Result<(), FactoryError>;
+
+    #[ink(message)]
+    fn pair_fee_config(&self, pair: AccountId) -> Option<PairFeeConfig>;
+
+    /// Overrides the protocol fee settings of `pair`, `None` restores the global settings.
+    #[ink(message)]
+    fn set_pair_fee_config(
+        &mut self,
+        pair: AccountId,
+        config: Option<PairFeeConfig>,
+    ) -> Result<(), FactoryError>;
+
+    /// Returns the protocol fee recipients of `pair` with its override applied.
+    #[ink(message)]
+    fn protocol_fee_of(&self, pair: AccountId) -> ProtocolFee;
+
+    /// Sets the dynamic swap fee of `pair`, `None` restores the default 0.3%.
+    #[ink(message)]
+    fn set_pair_dynamic_fee(
+        &mut self,
+        pair: AccountId,
+        config: Option<DynamicFeeConfig>,
+    ) -> Result<(), FactoryError>;
+
+    /// Enables the log-price oracle of `pair`.
+    #[ink(message)]
+    fn set_pair_log_price_oracle(
+        &mut self,
+        pair: AccountId,
+        enabled: bool,
+    ) -> Result<(), FactoryError>;
+
+    /// Sets how `pair` realises the protocol fee.
+    #[ink(message)]
+    fn set_protocol_fee_mode(
+        &mut self,
+        pair: AccountId,
+        mode: ProtocolFeeMode,
+    ) -> Result<(), FactoryError>;
+
+    /// Sends the protocol fees set aside by `pair` in `ProtocolFeeMode::Tokens` to its
+    /// `fee_to` and creator, split as the liquidity minted in `ProtocolFeeMode::MintLiquidity`.
+    /// Callable by anyone.
+    #[ink(message)]
+    fn collect_protocol_fees(
+        &mut self,
+        pair: AccountId,
+    ) -> Result<(Balance, Balance), FactoryError>;
+
+    /// Returns whether `router` may call `Pair::swap_with_fee`.
+    #[ink(message)]
+    fn is_trusted_router(&self, router: AccountId) -> bool;
+
+    #[ink(message)]
+    fn set_trusted_router(&mut self, router: AccountId, trusted: bool) -> Result<(), FactoryError>;
+
     #[ink(message)]
     fn set_fee_to(&mut self, fee_to: AccountId) -> Result<(), FactoryError>;
 
@@ -35,6 +235,7 @@ pub trait Factory {
     #[ink(message)]
     fn fee_to_setter(&self) -> AccountId;
 
+    /// Returns the constant-product pool of `token_a` and `token_b`.
     #[ink(message)]
     fn get_pair(&self, token_a: AccountId, token_b: AccountId) -> Option<AccountId>;
 }
@@ -43,11 +244,21 @@ pub trait Factory {
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
 pub enum FactoryError {
     PairError(PairError),
+    LpRewardsError(LpRewardsError),
+    HelperError(HelperError),
+    CallFailed(CallFailed),
     CallerIsNotFeeSetter,
     ZeroAddress,
     IdenticalAddresses,
     PairExists,
-    PairInstantiationFailed,
+    PoolTypeNotFound,
+    PoolTypeDisabled,
+    InvalidTokensLength,
+    PoolNotFound,
+    CallerIsNotCreator,
+    InvalidCreatorShare,
+    ProtocolFeeOff,
+    CallerIsNotTrustedRouter,
 }
 
 impl From<PairError> for FactoryError {
@@ -55,3 +266,21 @@ impl From<PairError> for FactoryError {
         FactoryError::PairError(error)
     }
 }
+
+impl From<LpRewardsError> for FactoryError {
+    fn from(error: LpRewardsError) -> Self {
+        FactoryError::LpRewardsError(error)
+    }
+}
+
+impl From<HelperError> for FactoryError {
+    fn from(error: HelperError) -> Self {
+        FactoryError::HelperError(error)
+    }
+}
+
+impl From<CallFailed> for FactoryError {
+    fn from(error: CallFailed) -> Self {
+        FactoryError::CallFailed(error)
+    }
+}
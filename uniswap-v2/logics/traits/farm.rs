@@ -0,0 +1,156 @@
+use crate::traits::{
+    errors::MathOp,
+    types::WrappedU256,
+};
+use openbrush::{
+    contracts::{
+        ownable::*,
+        reentrancy_guard::*,
+        traits::psp22::PSP22Error,
+    },
+    traits::{
+        AccountId,
+        Balance,
+        Timestamp,
+    },
+};
+
+pub type PoolId = u32;
+
+#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct PoolInfo {
+    pub lp_token: AccountId,
+    /// Share of the emissions of the pool, relative to the total allocation points.
+    pub alloc_point: u32,
+    pub last_reward_time: Timestamp,
+    /// Rewards accrued per staked LP token since the pool was added, scaled by `ACC_REWARD_PRECISION`.
+    pub acc_reward_per_share: WrappedU256,
+    pub total_staked: Balance,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct UserInfo {
+    pub amount: Balance,
+    /// Rewards already accounted for the staked amount, see `pending_reward`.
+    pub reward_debt: Balance,
+}
+
+#[openbrush::wrapper]
+pub type FarmRef = dyn Farm;
+
+#[openbrush::trait_definition]
+pub trait Farm {
+    #[ink(message)]
+    fn reward_token(&self) -> AccountId;
+
+    /// Amount of reward token emitted per second across all pools.
+    #[ink(message)]
+    fn reward_per_second(&self) -> Balance;
+
+    #[ink(message)]
+    fn total_alloc_point(&self) -> u32;
+
+    #[ink(message)]
+    fn pool_length(&self) -> PoolId;
+
+    #[ink(message)]
+    fn pool_info(&self, pool_id: PoolId) -> Option<PoolInfo>;
+
+    #[ink(message)]
+    fn pool_id_of(&self, lp_token: AccountId) -> Option<PoolId>;
+
+    #[ink(message)]
+    fn user_info(&self, pool_id: PoolId, user: AccountId) -> UserInfo;
+
+    /// Returns the rewards `user` would receive by harvesting `pool_id` now.
+    #[ink(message)]
+    fn pending_reward(&self, pool_id: PoolId, user: AccountId) -> Result<Balance, FarmError>;
+
+    /// Adds a pool for `lp_token`, only callable by the owner.
+    #[ink(message)]
+    fn add_pool(&mut self, lp_token: AccountId, alloc_point: u32) -> Result<PoolId, FarmError>;
+
+    /// Updates the allocation points of `pool_id`, only callable by the owner.
+    #[ink(message)]
+    fn set_pool(&mut self, pool_id: PoolId, alloc_point: u32) -> Result<(), FarmError>;
+
+    /// Updates the emission rate, only callable by the owner.
+    #[ink(message)]
+    fn set_reward_per_second(&mut self, reward_per_second: Balance) -> Result<(), FarmError>;
+
+    /// Accrues the rewards of `pool_id` up to the current block.
+    #[ink(message)]
+    fn update_pool(&mut self, pool_id: PoolId) -> Result<(), FarmError>;
+
+    #[ink(message)]
+    fn mass_update_pools(&mut self) -> Result<(), FarmError>;
+
+    /// Stakes `amount` of LP tokens in `pool_id` and harvests the pending rewards.
+    #[ink(message)]
+    fn deposit(&mut self, pool_id: PoolId, amount: Balance) -> Result<(), FarmError>;
+
+    /// Unstakes `amount` of LP tokens from `pool_id` and harvests the pending rewards.
+    #[ink(message)]
+    fn withdraw(&mut self, pool_id: PoolId, amount: Balance) -> Result<(), FarmError>;
+
+    /// Sends the pending rewards of `pool_id` to the caller, returns the amount sent.
+    #[ink(message)]
+    fn harvest(&mut self, pool_id: PoolId) -> Result<Balance, FarmError>;
+
+    /// Unstakes all the LP tokens of the caller from `pool_id` without harvesting.
+    /// The pending rewards are forfeited.
+    #[ink(message)]
+    fn emergency_withdraw(&mut self, pool_id: PoolId) -> Result<Balance, FarmError>;
+}
+
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum FarmError {
+    PSP22Error(PSP22Error),
+    OwnableError(OwnableError),
+    ReentrancyGuardError(ReentrancyGuardError),
+    PoolNotFound,
+    PoolAlreadyExists,
+    InvalidLpToken,
+    InsufficientAmount,
+    InsufficientStake,
+    InsufficientRewardBalance,
+    Arithmetic(MathOp, FarmLocation),
+}
+
+/// Step of the farm contract in which an arithmetic error occurred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum FarmLocation {
+    /// Rewards accrued by a stake since the pool was added.
+    AccruedReward,
+    /// Rewards owed to a user since its last update.
+    PendingReward,
+    AccRewardPerShare,
+    PoolId,
+    TotalAllocPoint,
+    /// Amount staked by a user or in a pool.
+    Stake,
+}
+
+macro_rules! impl_froms {
+    ( $( $error:ident ),* ) => {
+        $(
+            impl From<$error> for FarmError {
+                fn from(error: $error) -> Self {
+                    FarmError::$error(error)
+                }
+            }
+        )*
+    };
+}
+
+impl_froms!(PSP22Error, OwnableError, ReentrancyGuardError);
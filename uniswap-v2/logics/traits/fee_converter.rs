@@ -0,0 +1,139 @@
+use super::{
+    errors::{
+        CallFailed,
+        MathOp,
+    },
+    pair::PairError,
+};
+use crate::helpers::helper::HelperError;
+pub use crate::traits::types::Observation;
+use ink::prelude::vec::Vec;
+use openbrush::{
+    contracts::{
+        ownable::*,
+        reentrancy_guard::*,
+        traits::psp22::PSP22Error,
+    },
+    traits::{
+        AccountId,
+        Balance,
+        Timestamp,
+    },
+};
+
+#[openbrush::wrapper]
+pub type FeeConverterRef = dyn FeeConverter;
+
+/// Converts the LP tokens accrued as protocol fee into a single payout token.
+/// Meant to be set as `fee_to` of the factory.
+#[openbrush::trait_definition]
+pub trait FeeConverter {
+    #[ink(message)]
+    fn factory(&self) -> AccountId;
+
+    #[ink(message)]
+    fn payout_token(&self) -> AccountId;
+
+    /// Receiver of the payout token, e.g. a staking contract or the treasury.
+    #[ink(message)]
+    fn recipient(&self) -> AccountId;
+
+    /// Minimum time between two observations of a pair, in milliseconds.
+    #[ink(message)]
+    fn twap_period(&self) -> Timestamp;
+
+    /// Maximum deviation of each swap from the TWAP of its pair, in basis points.
+    #[ink(message)]
+    fn max_slippage_bps(&self) -> u16;
+
+    /// Returns the token `token` is swapped to on its way to the payout token.
+    #[ink(message)]
+    fn bridge_for(&self, token: AccountId) -> AccountId;
+
+    /// Only callable by the owner.
+    #[ink(message)]
+    fn set_bridge(&mut self, token: AccountId, bridge: AccountId) -> Result<(), FeeConverterError>;
+
+    /// Only callable by the owner.
+    #[ink(message)]
+    fn set_recipient(&mut self, recipient: AccountId) -> Result<(), FeeConverterError>;
+
+    /// Only callable by the owner.
+    #[ink(message)]
+    fn set_max_slippage_bps(&mut self, max_slippage_bps: u16) -> Result<(), FeeConverterError>;
+
+    /// Checkpoints the price accumulators of `pair`, callable by anyone once per `twap_period`.
+    #[ink(message)]
+    fn update_observation(&mut self, pair: AccountId) -> Result<(), FeeConverterError>;
+
+    #[ink(message)]
+    fn observation(&self, pair: AccountId) -> Option<Observation>;
+
+    /// Burns the LP tokens held for the pair of `token_a` and `token_b`, swaps the
+    /// underlying tokens into the payout token and sends it to the recipient.
+    /// Returns the amount of payout token sent.
+    #[ink(message)]
+    fn convert(
+        &mut self,
+        token_a: AccountId,
+        token_b: AccountId,
+    ) -> Result<Balance, FeeConverterError>;
+
+    /// Same as `convert` for several pairs.
+    #[ink(message)]
+    fn convert_multiple(
+        &mut self,
+        tokens_a: Vec<AccountId>,
+        tokens_b: Vec<AccountId>,
+    ) -> Result<Balance, FeeConverterError>;
+}
+
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum FeeConverterError {
+    PSP22Error(PSP22Error),
+    PairError(PairError),
+    HelperError(HelperError),
+    OwnableError(OwnableError),
+    ReentrancyGuardError(ReentrancyGuardError),
+    CallFailed(CallFailed),
+    PairNotFound,
+    InvalidBridge,
+    InvalidSlippage,
+    InvalidLength,
+    TooManyHops,
+    NoLiquidity,
+    ObservationNotDue,
+    TwapNotReady,
+    PriceDeviation,
+    Arithmetic(MathOp, FeeConverterLocation),
+}
+
+/// Step of the fee converter in which an arithmetic error occurred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum FeeConverterLocation {
+    /// Sum of the payout token converted.
+    AmountOut,
+}
+
+macro_rules! impl_froms {
+    ( $( $error:ident ),* ) => {
+        $(
+            impl From<$error> for FeeConverterError {
+                fn from(error: $error) -> Self {
+                    FeeConverterError::$error(error)
+                }
+            }
+        )*
+    };
+}
+
+impl_froms!(
+    PSP22Error,
+    PairError,
+    HelperError,
+    OwnableError,
+    ReentrancyGuardError,
+    CallFailed
+);
@@ -0,0 +1,144 @@
+use super::{
+    errors::{
+        CallFailed,
+        MathOp,
+    },
+    pair::PairError,
+};
+use crate::helpers::helper::HelperError;
+use ink::prelude::vec::Vec;
+use openbrush::{
+    contracts::{
+        reentrancy_guard::*,
+        traits::psp22::PSP22Error,
+    },
+    traits::{
+        AccountId,
+        Balance,
+        Timestamp,
+    },
+};
+
+pub type OrderId = u64;
+
+#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct Order {
+    pub owner: AccountId,
+    pub token_in: AccountId,
+    pub token_out: AccountId,
+    /// Amount of `token_in` deposited when the order was placed.
+    pub amount_in: Balance,
+    /// Minimum amount of `token_out` for the whole `amount_in`, defines the target price.
+    pub amount_out_min: Balance,
+    /// Amount of `token_in` not filled yet.
+    pub amount_in_remaining: Balance,
+    /// Amount of `token_out` already sent to the owner.
+    pub amount_out_filled: Balance,
+    pub expiry: Timestamp,
+}
+
+#[openbrush::wrapper]
+pub type LimitOrderRef = dyn LimitOrder;
+
+#[openbrush::trait_definition]
+pub trait LimitOrder {
+    #[ink(message)]
+    fn factory(&self) -> AccountId;
+
+    /// Deposits `amount_in` of `token_in` and places an order to sell it for at least
+    /// `amount_out_min` of `token_out` before `expiry`.
+    #[ink(message)]
+    fn place_order(
+        &mut self,
+        token_in: AccountId,
+        token_out: AccountId,
+        amount_in: Balance,
+        amount_out_min: Balance,
+        expiry: Timestamp,
+    ) -> Result<OrderId, LimitOrderError>;
+
+    /// Refunds the unfilled part of the order to its owner.
+    #[ink(message)]
+    fn cancel_order(&mut self, order_id: OrderId) -> Result<Balance, LimitOrderError>;
+
+    /// Swaps `amount_in` of the order through the pair. The caller receives the surplus over
+    /// the pro-rata `amount_out_min` as fill reward, up to `MAX_KEEPER_REWARD_BPS` of the
+    /// output, and the owner the rest.
+    #[ink(message)]
+    fn fill_order(
+        &mut self,
+        order_id: OrderId,
+        amount_in: Balance,
+    ) -> Result<Balance, LimitOrderError>;
+
+    /// Returns the fill reward `fill_order` would pay for the current pair reserves.
+    #[ink(message)]
+    fn quote_fill(&self, order_id: OrderId, amount_in: Balance)
+        -> Result<Balance, LimitOrderError>;
+
+    #[ink(message)]
+    fn get_order(&self, order_id: OrderId) -> Option<Order>;
+
+    /// Returns the ids of the open orders of `owner`.
+    #[ink(message)]
+    fn orders_of(&self, owner: AccountId) -> Vec<OrderId>;
+
+    #[ink(message)]
+    fn next_order_id(&self) -> OrderId;
+}
+
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum LimitOrderError {
+    PSP22Error(PSP22Error),
+    PairError(PairError),
+    HelperError(HelperError),
+    ReentrancyGuardError(ReentrancyGuardError),
+    CallFailed(CallFailed),
+    PairNotFound,
+    OrderNotFound,
+    CallerIsNotOwner,
+    Expired,
+    InvalidExpiry,
+    InsufficientAmount,
+    ExcessiveInputAmount,
+    PriceNotReached,
+    Arithmetic(MathOp, LimitOrderLocation),
+}
+
+/// Step of the limit order contract in which an arithmetic error occurred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum LimitOrderLocation {
+    OrderId,
+    /// Part of the fill owed to the owner.
+    OwnerAmount,
+    /// Amounts of the order left and filled.
+    Fill,
+    /// Fill reward of the keeper.
+    Reward,
+}
+
+macro_rules! impl_froms {
+    ( $( $error:ident ),* ) => {
+        $(
+            impl From<$error> for LimitOrderError {
+                fn from(error: $error) -> Self {
+                    LimitOrderError::$error(error)
+                }
+            }
+        )*
+    };
+}
+
+impl_froms!(
+    PSP22Error,
+    PairError,
+    HelperError,
+    ReentrancyGuardError,
+    CallFailed
+);
@@ -0,0 +1,120 @@
+use crate::traits::{
+    errors::MathOp,
+    types::WrappedU256,
+};
+use openbrush::{
+    contracts::{
+        reentrancy_guard::*,
+        traits::psp22::PSP22Error,
+    },
+    traits::{
+        AccountId,
+        Balance,
+        String,
+        Timestamp,
+    },
+};
+
+#[openbrush::wrapper]
+pub type LpRewardsRef = dyn LpRewards;
+
+/// Incentive rewards streamed to the holders of the LP token, without staking.
+/// Balances are checkpointed on every LP transfer, mint and burn.
+#[openbrush::trait_definition]
+pub trait LpRewards {
+    /// Returns the reward token, the zero address while rewards are not configured.
+    #[ink(message)]
+    fn reward_token(&self) -> AccountId;
+
+    #[ink(message)]
+    fn reward_distributor(&self) -> AccountId;
+
+    /// Amount of reward token streamed per millisecond, scaled by `REWARD_PRECISION`.
+    #[ink(message)]
+    fn reward_rate(&self) -> WrappedU256;
+
+    #[ink(message)]
+    fn period_finish(&self) -> Timestamp;
+
+    /// Rewards accrued per LP token since rewards were configured, scaled by `REWARD_PRECISION`.
+    #[ink(message)]
+    fn reward_per_token(&self) -> Result<WrappedU256, LpRewardsError>;
+
+    /// Rewards claimable by `account`.
+    #[ink(message)]
+    fn earned(&self, account: AccountId) -> Result<Balance, LpRewardsError>;
+
+    /// Sends the rewards of the caller to `to`, returns the amount sent.
+    #[ink(message)]
+    fn claim_rewards(&mut self, to: AccountId) -> Result<Balance, LpRewardsError>;
+
+    /// Pulls `amount` of reward token from the distributor and streams it, along with
+    /// the rewards not distributed yet, over `duration` milliseconds.
+    #[ink(message)]
+    fn notify_reward_amount(
+        &mut self,
+        amount: Balance,
+        duration: Timestamp,
+    ) -> Result<(), LpRewardsError>;
+
+    /// Sets the reward token and the distributor, only callable by the factory.
+    /// The reward token can not be changed once set.
+    #[ink(message)]
+    fn set_reward_config(
+        &mut self,
+        reward_token: AccountId,
+        reward_distributor: AccountId,
+    ) -> Result<(), LpRewardsError>;
+}
+
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum LpRewardsError {
+    PSP22Error(PSP22Error),
+    ReentrancyGuardError(ReentrancyGuardError),
+    CallerIsNotFactory,
+    CallerIsNotDistributor,
+    RewardsNotConfigured,
+    InvalidRewardToken,
+    RewardTokenAlreadySet,
+    InvalidDuration,
+    InsufficientRewardBalance,
+    Arithmetic(MathOp, LpRewardsLocation),
+}
+
+/// Step of the LP rewards in which an arithmetic error occurred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum LpRewardsLocation {
+    RewardPerToken,
+    /// Rewards earned by an account and not claimed yet.
+    Earned,
+    RewardRate,
+    PeriodFinish,
+    /// Rewards streamed since the last checkpoint.
+    RewardsOwed,
+}
+
+macro_rules! impl_froms {
+    ( $( $error:ident ),* ) => {
+        $(
+            impl From<$error> for LpRewardsError {
+                fn from(error: $error) -> Self {
+                    LpRewardsError::$error(error)
+                }
+            }
+        )*
+    };
+}
+
+impl_froms!(PSP22Error, ReentrancyGuardError);
+
+/// Lets the PSP22 internals of the pair fail on a reward checkpoint error.
+impl From<LpRewardsError> for PSP22Error {
+    fn from(error: LpRewardsError) -> Self {
+        match error {
+            LpRewardsError::PSP22Error(error) => error,
+            _ => PSP22Error::Custom(String::from("reward checkpoint failed")),
+        }
+    }
+}
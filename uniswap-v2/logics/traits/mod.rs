@@ -1,5 +1,13 @@
+pub mod conditional_order;
+pub mod dca;
+pub mod errors;
 pub mod factory;
+pub mod farm;
+pub mod fee_converter;
+pub mod limit_order;
+pub mod lp_rewards;
 pub mod pair;
 pub mod router;
+pub mod twamm;
 pub mod types;
 pub mod wnative;
@@ -14,7 +14,79 @@ use openbrush::{
     },
 };
 
-use super::types::WrappedU256;
+use crate::helpers::math::MAX_FEE_BPS;
+
+use super::{
+    errors::MathOp,
+    factory::ProtocolFee,
+    types::WrappedU256,
+};
+
+/// How the pair realises the protocol fee.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub enum ProtocolFeeMode {
+    /// Liquidity is minted to `fee_to` on the growth of sqrt(k), as in Uniswap V2.
+    #[default]
+    MintLiquidity,
+    /// A part of the input of each swap is set aside out of the reserves.
+    Tokens,
+}
+
+/// Parameters of a swap fee rising with the deviation of the spot price from its TWAP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct DynamicFeeConfig {
+    pub min_fee_bps: u16,
+    pub max_fee_bps: u16,
+    /// Fee added per basis point of deviation, in basis points.
+    pub volatility_factor_bps: u32,
+    /// Length of the TWAP window, in milliseconds.
+    pub window: Timestamp,
+}
+
+/// Version of the price accumulators, `UQ128x128` prices times milliseconds since 1.
+///
+/// Pairs have no `set_code_hash` message, a deployed pair keeps the accumulators of the code
+/// it was instantiated with: `FixedU128` prices for pairs deployed before version 1, which
+/// do not implement `price_accumulator_version`. The factory instantiates new pairs from the
+/// code hash registered for their pool type, and oracles only start observing pairs of this
+/// version, see `helpers::oracle::update_observation`.
+pub const PRICE_ACCUMULATOR_VERSION: u8 = 1;
+
+impl DynamicFeeConfig {
+    /// Returns whether the fee bounds are ordered, within `MAX_FEE_BPS`, and the window is set.
+    pub fn is_valid(&self) -> bool {
+        self.min_fee_bps <= self.max_fee_bps && self.max_fee_bps <= MAX_FEE_BPS && self.window > 0
+    }
+}
+
+/// Step of the pair in which an arithmetic error occurred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PairLocation {
+    /// Amounts deposited since the last update.
+    MintAmounts,
+    MintInitialLiquidity,
+    MintLiquidity,
+    BurnAmounts,
+    SwapAmountsIn,
+    /// Balances minus the swap fee, checked against k.
+    SwapAdjustedBalances,
+    SwapProtocolFees,
+    Skim,
+    MintFee,
+    /// Balances minus the protocol fees set aside.
+    Balances,
+    Update,
+    CollectProtocolFees,
+}
 
 #[openbrush::wrapper]
 pub type PairRef = dyn Pair;
@@ -24,12 +96,18 @@ pub trait Pair {
     #[ink(message)]
     fn get_reserves(&self) -> (Balance, Balance, Timestamp);
 
+    /// Sum of the `UQ128x128` prices of token 0 times the milliseconds elapsed, wrapping
+    /// around `U256::MAX`. See `helpers::oracle::average_price`.
     #[ink(message)]
     fn price_0_cumulative_last(&self) -> WrappedU256;
 
     #[ink(message)]
     fn price_1_cumulative_last(&self) -> WrappedU256;
 
+    /// Version of the encoding of the price accumulators, `PRICE_ACCUMULATOR_VERSION`.
+    #[ink(message)]
+    fn price_accumulator_version(&self) -> u8;
+
     #[ink(message)]
     fn initialize(&mut self, token_0: AccountId, token_1: AccountId) -> Result<(), PairError>;
 
@@ -47,12 +125,73 @@ pub trait Pair {
         to: AccountId,
     ) -> Result<(), PairError>;
 
+    /// Same as `swap` charging `fee_bps` instead of the pair fee. Only callable by a router
+    /// trusted by the factory, which may only discount the fee.
+    #[ink(message)]
+    fn swap_with_fee(
+        &mut self,
+        amount_0_out: Balance,
+        amount_1_out: Balance,
+        to: AccountId,
+        fee_bps: u16,
+    ) -> Result<(), PairError>;
+
     #[ink(message)]
     fn skim(&mut self, to: AccountId) -> Result<(), PairError>;
 
     #[ink(message)]
     fn sync(&mut self) -> Result<(), PairError>;
 
+    /// Returns the fee charged on the input of the next swap, in basis points.
+    #[ink(message)]
+    fn get_fee(&self) -> u16;
+
+    /// Returns the fee charged on the input of a swap at `reserve_0` and `reserve_1` instead
+    /// of the current reserves, in basis points.
+    #[ink(message)]
+    fn get_fee_at(&self, reserve_0: Balance, reserve_1: Balance) -> u16;
+
+    #[ink(message)]
+    fn dynamic_fee_config(&self) -> Option<DynamicFeeConfig>;
+
+    /// Sets the dynamic fee, `None` restores the default 0.3%. Only callable by the factory.
+    #[ink(message)]
+    fn set_dynamic_fee_config(&mut self, config: Option<DynamicFeeConfig>)
+        -> Result<(), PairError>;
+
+    /// Sum of the ticks (log base 1.0001) of the price of token 0 times the milliseconds
+    /// elapsed while the log-price oracle is enabled, wrapping. See
+    /// `helpers::oracle::average_tick`.
+    #[ink(message)]
+    fn log_price_cumulative_last(&self) -> i128;
+
+    #[ink(message)]
+    fn log_price_oracle_enabled(&self) -> bool;
+
+    /// Enables the log-price accumulator, averages over a window in which it was disabled
+    /// are wrong. Only callable by the factory.
+    #[ink(message)]
+    fn set_log_price_oracle_enabled(&mut self, enabled: bool) -> Result<(), PairError>;
+
+    #[ink(message)]
+    fn protocol_fee_mode(&self) -> ProtocolFeeMode;
+
+    /// Returns the protocol fees set aside and not collected yet.
+    #[ink(message)]
+    fn protocol_fees(&self) -> (Balance, Balance);
+
+    /// Only callable by the factory.
+    #[ink(message)]
+    fn set_protocol_fee_mode(&mut self, mode: ProtocolFeeMode) -> Result<(), PairError>;
+
+    /// Sends the protocol fees set aside to `protocol_fee.fee_to`, less the share of
+    /// `protocol_fee.creator`, only callable by the factory. Returns the amounts sent in total.
+    #[ink(message)]
+    fn collect_protocol_fees(
+        &mut self,
+        protocol_fee: ProtocolFee,
+    ) -> Result<(Balance, Balance), PairError>;
+
     #[ink(message)]
     fn get_token_0(&self) -> AccountId;
 
@@ -67,53 +206,17 @@ pub enum PairError {
     OwnableError(OwnableError),
     ReentrancyGuardError(ReentrancyGuardError),
     LangError(LangError),
-    TransferError,
     K,
     InsufficientLiquidityMinted,
     InsufficientLiquidityBurned,
     InsufficientOutputAmount,
     InsufficientLiquidity,
     InsufficientInputAmount,
-    SafeTransferFailed,
     InvalidTo,
-    Overflow,
-    Locked,
-    SubUnderFlow1,
-    SubUnderFlow2,
-    SubUnderFlow3,
-    SubUnderFlow4,
-    SubUnderFlow5,
-    SubUnderFlow6,
-    SubUnderFlow7,
-    SubUnderFlow8,
-    SubUnderFlow9,
-    SubUnderFlow10,
-    SubUnderFlow11,
-    SubUnderFlow12,
-    SubUnderFlow13,
-    SubUnderFlow14,
-    MulOverFlow1,
-    MulOverFlow2,
-    MulOverFlow3,
-    MulOverFlow4,
-    MulOverFlow5,
-    MulOverFlow6,
-    MulOverFlow7,
-    MulOverFlow8,
-    MulOverFlow9,
-    MulOverFlow10,
-    MulOverFlow11,
-    MulOverFlow12,
-    MulOverFlow13,
-    MulOverFlow14,
-    DivByZero1,
-    DivByZero2,
-    DivByZero3,
-    DivByZero4,
-    DivByZero5,
-    AddOverflow1,
-    CastOverflow1,
-    CastOverflow2,
+    InvalidFeeConfig,
+    InvalidFee,
+    CallerIsNotTrustedRouter,
+    Arithmetic(MathOp, PairLocation),
 }
 
 impl From<OwnableError> for PairError {
@@ -1,23 +1,97 @@
 use super::{
+    errors::{
+        CallFailed,
+        MathOp,
+    },
     factory::FactoryError,
     pair::PairError,
+    types::Price,
 };
 use crate::helpers::{
     helper::HelperError,
     transfer_helper::TransferHelperError,
 };
-use ink::{
-    prelude::vec::Vec,
-    LangError,
-};
+use ink::prelude::vec::Vec;
 use openbrush::{
     contracts::psp22::PSP22Error,
     traits::{
         AccountId,
         Balance,
+        BlockNumber,
     },
 };
 
+/// Fee discount granted to traders staking at least `min_balance` of the fee tier token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct FeeTier {
+    pub min_balance: Balance,
+    /// Part of the pair fee waived, in basis points of the fee.
+    pub discount_bps: u16,
+}
+
+/// Fee tier tokens staked in the router by a trader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct FeeTierStake {
+    pub token: AccountId,
+    pub amount: Balance,
+    /// Block of the last stake. The stake only counts towards the fee tier in later blocks,
+    /// so that tokens borrowed and returned within a transaction earn no discount.
+    pub staked_at: BlockNumber,
+}
+
+/// Fee paid by the trader to the integrator which referred the swap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct Referral {
+    pub referrer: AccountId,
+    /// Part of the amount paid or received by the trader, in basis points.
+    pub fee_bps: u16,
+    /// Takes the fee from the output token instead of the input token.
+    pub from_output: bool,
+}
+
+/// Swap through one pair of a path.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct HopQuote {
+    pub pair: AccountId,
+    pub token_in: AccountId,
+    pub token_out: AccountId,
+    pub amount_in: Balance,
+    pub amount_out: Balance,
+    pub fee_bps: u16,
+    /// Part of `amount_in` kept by the pair as swap fee, rounded down.
+    pub fee_amount: Balance,
+    /// Price of `token_in` in `token_out` at the reserves before and after the swap.
+    pub mid_price_before: Price,
+    pub mid_price_after: Price,
+}
+
+/// Breakdown of a swap along a path. Prices are of the first token of the path in the last
+/// one, rounded down.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct SwapQuote {
+    pub amount_in: Balance,
+    pub amount_out: Balance,
+    pub hops: Vec<HopQuote>,
+    pub mid_price_before: Price,
+    pub mid_price_after: Price,
+    /// `amount_out` per `amount_in`.
+    pub execution_price: Price,
+    /// Shortfall of the execution price from `mid_price_before`, fees included, in basis
+    /// points rounded up.
+    pub price_impact_bps: u16,
+}
+
 #[openbrush::wrapper]
 pub type RouterRef = dyn Router;
 
@@ -96,6 +170,32 @@ pub trait Router {
         deadline: u64,
     ) -> Result<Vec<Balance>, RouterError>;
 
+    /// Same as `swap_exact_tokens_for_tokens`, paying `referral` if any.
+    /// `amount_in` and `amount_out_min` include the referral fee.
+    #[ink(message)]
+    fn swap_exact_tokens_for_tokens_with_referral(
+        &mut self,
+        amount_in: Balance,
+        amount_out_min: Balance,
+        path: Vec<AccountId>,
+        to: AccountId,
+        deadline: u64,
+        referral: Option<Referral>,
+    ) -> Result<Vec<Balance>, RouterError>;
+
+    /// Same as `swap_tokens_for_exact_tokens`, paying `referral` if any.
+    /// `to` receives `amount_out` and `amount_in_max` includes the referral fee.
+    #[ink(message)]
+    fn swap_tokens_for_exact_tokens_with_referral(
+        &mut self,
+        amount_out: Balance,
+        amount_in_max: Balance,
+        path: Vec<AccountId>,
+        to: AccountId,
+        deadline: u64,
+        referral: Option<Referral>,
+    ) -> Result<Vec<Balance>, RouterError>;
+
     #[ink(message, payable)]
     fn swap_exact_native_for_tokens(
         &mut self,
@@ -134,6 +234,59 @@ pub trait Router {
         deadline: u64,
     ) -> Result<Vec<Balance>, RouterError>;
 
+    /// Same as `swap_exact_native_for_tokens`, paying `referral` if any.
+    /// The transferred value and `amount_out_min` include the referral fee, an input fee
+    /// is paid in native tokens.
+    #[ink(message, payable)]
+    fn swap_exact_native_for_tokens_with_referral(
+        &mut self,
+        amount_out_min: Balance,
+        path: Vec<AccountId>,
+        to: AccountId,
+        deadline: u64,
+        referral: Option<Referral>,
+    ) -> Result<Vec<Balance>, RouterError>;
+
+    /// Same as `swap_tokens_for_exact_native`, paying `referral` if any.
+    /// `to` receives `amount_out`, an output fee is paid in native tokens.
+    #[ink(message)]
+    fn swap_tokens_for_exact_native_with_referral(
+        &mut self,
+        amount_out: Balance,
+        amount_in_max: Balance,
+        path: Vec<AccountId>,
+        to: AccountId,
+        deadline: u64,
+        referral: Option<Referral>,
+    ) -> Result<Vec<Balance>, RouterError>;
+
+    /// Same as `swap_exact_tokens_for_native`, paying `referral` if any.
+    /// `amount_in` and `amount_out_min` include the referral fee, an output fee is paid in
+    /// native tokens.
+    #[ink(message)]
+    fn swap_exact_tokens_for_native_with_referral(
+        &mut self,
+        amount_in: Balance,
+        amount_out_min: Balance,
+        path: Vec<AccountId>,
+        to: AccountId,
+        deadline: u64,
+        referral: Option<Referral>,
+    ) -> Result<Vec<Balance>, RouterError>;
+
+    /// Same as `swap_native_for_exact_tokens`, paying `referral` if any.
+    /// `to` receives `amount_out` and the transferred value must cover the referral fee,
+    /// an input fee is paid in native tokens.
+    #[ink(message, payable)]
+    fn swap_native_for_exact_tokens_with_referral(
+        &mut self,
+        amount_out: Balance,
+        path: Vec<AccountId>,
+        to: AccountId,
+        deadline: u64,
+        referral: Option<Referral>,
+    ) -> Result<Vec<Balance>, RouterError>;
+
     #[ink(message)]
     fn quote(
         &self,
@@ -142,6 +295,7 @@ pub trait Router {
         reserve_b: Balance,
     ) -> Result<Balance, RouterError>;
 
+    /// Quotes at the default 0.3% fee, see `get_amount_out_with_fee` for dynamic fee pairs.
     #[ink(message)]
     fn get_amount_out(
         &self,
@@ -150,6 +304,7 @@ pub trait Router {
         reserve_out: Balance,
     ) -> Result<Balance, RouterError>;
 
+    /// Quotes at the default 0.3% fee, see `get_amount_in_with_fee` for dynamic fee pairs.
     #[ink(message)]
     fn get_amount_in(
         &self,
@@ -158,6 +313,27 @@ pub trait Router {
         reserve_out: Balance,
     ) -> Result<Balance, RouterError>;
 
+    /// Quotes at `fee_bps`, as returned by `Pair::get_fee`.
+    #[ink(message)]
+    fn get_amount_out_with_fee(
+        &self,
+        amount_in: Balance,
+        reserve_in: Balance,
+        reserve_out: Balance,
+        fee_bps: u16,
+    ) -> Result<Balance, RouterError>;
+
+    /// Quotes at `fee_bps`, as returned by `Pair::get_fee`.
+    #[ink(message)]
+    fn get_amount_in_with_fee(
+        &self,
+        amount_out: Balance,
+        reserve_in: Balance,
+        reserve_out: Balance,
+        fee_bps: u16,
+    ) -> Result<Balance, RouterError>;
+
+    /// Quotes each hop at the current fee of its pair.
     #[ink(message)]
     fn get_amounts_out(
         &self,
@@ -165,14 +341,126 @@ pub trait Router {
         path: Vec<AccountId>,
     ) -> Result<Vec<Balance>, RouterError>;
 
+    /// Quotes each hop at the current fee of its pair.
     #[ink(message)]
     fn get_amounts_in(
         &self,
         amount_out: Balance,
         path: Vec<AccountId>,
     ) -> Result<Vec<Balance>, RouterError>;
+
+    /// Same as `get_amounts_out` with the fee discount of `trader`.
+    #[ink(message)]
+    fn get_amounts_out_for(
+        &self,
+        trader: AccountId,
+        amount_in: Balance,
+        path: Vec<AccountId>,
+    ) -> Result<Vec<Balance>, RouterError>;
+
+    /// Same as `get_amounts_in` with the fee discount of `trader`.
+    #[ink(message)]
+    fn get_amounts_in_for(
+        &self,
+        trader: AccountId,
+        amount_out: Balance,
+        path: Vec<AccountId>,
+    ) -> Result<Vec<Balance>, RouterError>;
+
+    /// Same as `get_amounts_out` with the reserves of the pairs in `overrides`, given as
+    /// `(pair, reserve_0, reserve_1)`, instead of their current ones. Simulates a swap
+    /// after others that are not executed yet, the fee of an overridden pair is the one it
+    /// charges at the given reserves, discounted for the caller as `get_amounts_out_for`.
+    /// Fails if a pair is overridden more than once.
+    #[ink(message)]
+    fn get_amounts_out_with_overrides(
+        &self,
+        amount_in: Balance,
+        path: Vec<AccountId>,
+        overrides: Vec<(AccountId, Balance, Balance)>,
+    ) -> Result<Vec<Balance>, RouterError>;
+
+    /// Same as `get_amounts_in` with the reserves of the pairs in `overrides`, given as
+    /// `(pair, reserve_0, reserve_1)`, instead of their current ones, as
+    /// `get_amounts_out_with_overrides`.
+    #[ink(message)]
+    fn get_amounts_in_with_overrides(
+        &self,
+        amount_out: Balance,
+        path: Vec<AccountId>,
+        overrides: Vec<(AccountId, Balance, Balance)>,
+    ) -> Result<Vec<Balance>, RouterError>;
+
+    /// Same as `get_amounts_out_for` the caller with the pairs, fees and prices of each hop.
+    #[ink(message)]
+    fn quote_exact_in(
+        &self,
+        amount_in: Balance,
+        path: Vec<AccountId>,
+    ) -> Result<SwapQuote, RouterError>;
+
+    /// Same as `get_amounts_in_for` the caller with the pairs, fees and prices of each hop.
+    #[ink(message)]
+    fn quote_exact_out(
+        &self,
+        amount_out: Balance,
+        path: Vec<AccountId>,
+    ) -> Result<SwapQuote, RouterError>;
+
+    /// Returns the part of the pair fees waived for swaps of `trader`, in basis points.
+    /// The best of its fee tier and market maker discounts applies, none if the router
+    /// is not trusted by the factory.
+    #[ink(message)]
+    fn fee_discount_of(&self, trader: AccountId) -> u16;
+
+    #[ink(message)]
+    fn fee_tier_token(&self) -> AccountId;
+
+    #[ink(message)]
+    fn fee_tiers(&self) -> Vec<FeeTier>;
+
+    /// Sets the fee tiers, by increasing `min_balance`, of stakers of `token`.
+    /// Only callable by the fee setter of the factory.
+    #[ink(message)]
+    fn set_fee_tiers(&mut self, token: AccountId, tiers: Vec<FeeTier>) -> Result<(), RouterError>;
+
+    #[ink(message)]
+    fn fee_tier_stake(&self, account: AccountId) -> Option<FeeTierStake>;
+
+    /// Stakes `amount` of the fee tier token of the caller, its whole stake counts towards
+    /// its fee tier from the next block.
+    #[ink(message)]
+    fn stake_fee_tier_token(&mut self, amount: Balance) -> Result<(), RouterError>;
+
+    /// Returns `amount` of the tokens staked by the caller, also after the fee tier token
+    /// changed.
+    #[ink(message)]
+    fn unstake_fee_tier_token(&mut self, amount: Balance) -> Result<(), RouterError>;
+
+    #[ink(message)]
+    fn market_maker_discount(&self, account: AccountId) -> u16;
+
+    /// Whitelists `account` as a market maker, 0 removes it.
+    /// Only callable by the fee setter of the factory.
+    #[ink(message)]
+    fn set_market_maker_discount(
+        &mut self,
+        account: AccountId,
+        discount_bps: u16,
+    ) -> Result<(), RouterError>;
+
+    #[ink(message)]
+    fn max_referral_fee_bps(&self) -> u16;
+
+    /// Caps the referral fee of swaps, 0 disables referrals.
+    /// Only callable by the fee setter of the factory.
+    #[ink(message)]
+    fn set_max_referral_fee_bps(&mut self, max_referral_fee_bps: u16) -> Result<(), RouterError>;
 }
 
+/// Maximum number of fee tiers, bounding the work done on each swap.
+pub const MAX_FEE_TIERS: usize = 8;
+
 #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
 pub enum RouterError {
@@ -181,8 +469,7 @@ pub enum RouterError {
     PairError(PairError),
     HelperError(HelperError),
     TransferHelperError(TransferHelperError),
-    LangError(LangError),
-    TransferError,
+    CallFailed(CallFailed),
     PairNotFound,
     InsufficientAmount,
     InsufficientAAmount,
@@ -193,11 +480,26 @@ pub enum RouterError {
     ZeroAddress,
     IdenticalAddresses,
     Expired,
-    SubUnderFlow,
-    MulOverFlow,
-    DivByZero,
     TransferFailed,
     InvalidPath,
+    CallerIsNotFeeSetter,
+    InvalidFeeTiers,
+    InvalidDiscount,
+    InvalidReferralFee,
+    Arithmetic(MathOp, RouterLocation),
+    /// The caller staked a previous fee tier token, which must be unstaked first.
+    StakedTokenChanged,
+    InsufficientStake,
+}
+
+/// Step of the router in which an arithmetic error occurred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum RouterLocation {
+    ReferralFee,
+    /// Amount including the referral fee.
+    ReferralGrossAmount,
+    FeeTierStake,
 }
 
 macro_rules! impl_froms {
@@ -218,5 +520,5 @@ impl_froms!(
     PairError,
     HelperError,
     TransferHelperError,
-    LangError
+    CallFailed
 );
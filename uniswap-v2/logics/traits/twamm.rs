@@ -0,0 +1,183 @@
+use super::{
+    errors::{
+        CallFailed,
+        MathOp,
+    },
+    pair::PairError,
+};
+pub use crate::traits::types::Observation;
+use crate::{
+    helpers::helper::HelperError,
+    traits::types::WrappedU256,
+};
+use ink::prelude::vec::Vec;
+use openbrush::{
+    contracts::{
+        reentrancy_guard::*,
+        traits::psp22::PSP22Error,
+    },
+    traits::{
+        AccountId,
+        Balance,
+        Timestamp,
+    },
+};
+
+pub type OrderId = u64;
+
+#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct LongTermOrder {
+    pub owner: AccountId,
+    /// `true` if the order sells `token_0` for `token_1`.
+    pub sell_token_0: bool,
+    /// Amount sold per millisecond.
+    pub sell_rate: Balance,
+    pub expiry: Timestamp,
+    /// Reward factor of the order pool when the order was placed or its proceeds last withdrawn.
+    pub reward_factor: WrappedU256,
+}
+
+#[openbrush::wrapper]
+pub type TwammRef = dyn Twamm;
+
+#[openbrush::trait_definition]
+pub trait Twamm {
+    #[ink(message)]
+    fn pair(&self) -> AccountId;
+
+    #[ink(message)]
+    fn get_token_0(&self) -> AccountId;
+
+    #[ink(message)]
+    fn get_token_1(&self) -> AccountId;
+
+    /// Long-term orders expire on multiples of the order interval, in milliseconds.
+    #[ink(message)]
+    fn order_interval(&self) -> Timestamp;
+
+    #[ink(message)]
+    fn last_virtual_order_timestamp(&self) -> Timestamp;
+
+    /// Minimum time between two observations of the pair, in milliseconds.
+    #[ink(message)]
+    fn twap_period(&self) -> Timestamp;
+
+    /// Maximum deviation of the proceeds of each order pool from the TWAP of the pair over a
+    /// segment, fees included, in basis points.
+    #[ink(message)]
+    fn max_slippage_bps(&self) -> u16;
+
+    /// Returns the last observation of the pair, virtual orders are executed once it holds
+    /// average prices.
+    #[ink(message)]
+    fn observation(&self) -> Option<Observation>;
+
+    /// Returns the current sell rates of `token_0` and `token_1`.
+    #[ink(message)]
+    fn sell_rates(&self) -> (Balance, Balance);
+
+    /// Deposits `amount` of `token_in` to be sold evenly until the end of
+    /// `number_of_intervals` intervals. The dust not divisible by the duration is not taken.
+    #[ink(message)]
+    fn place_long_term_order(
+        &mut self,
+        token_in: AccountId,
+        amount: Balance,
+        number_of_intervals: u32,
+    ) -> Result<OrderId, TwammError>;
+
+    /// Stops the order, returns the unsold amount and the proceeds.
+    #[ink(message)]
+    fn cancel_long_term_order(
+        &mut self,
+        order_id: OrderId,
+    ) -> Result<(Balance, Balance), TwammError>;
+
+    /// Sends the proceeds of the order to its owner, the order is removed once expired.
+    #[ink(message)]
+    fn withdraw_proceeds(&mut self, order_id: OrderId) -> Result<Balance, TwammError>;
+
+    /// Executes the long-term orders up to the current block against the pair.
+    #[ink(message)]
+    fn execute_virtual_orders(&mut self) -> Result<(), TwammError>;
+
+    /// Executes the long-term orders up to `timestamp`, capped to the current block, crossing
+    /// at most `MAX_INTERVALS_PER_EXECUTION` interval boundaries. Returns the timestamp reached.
+    #[ink(message)]
+    fn execute_virtual_orders_until(
+        &mut self,
+        timestamp: Timestamp,
+    ) -> Result<Timestamp, TwammError>;
+
+    #[ink(message)]
+    fn get_order(&self, order_id: OrderId) -> Option<LongTermOrder>;
+
+    /// Returns the ids of the orders of `owner` not withdrawn yet.
+    #[ink(message)]
+    fn orders_of(&self, owner: AccountId) -> Vec<OrderId>;
+
+    #[ink(message)]
+    fn next_order_id(&self) -> OrderId;
+}
+
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum TwammError {
+    PSP22Error(PSP22Error),
+    PairError(PairError),
+    HelperError(HelperError),
+    ReentrancyGuardError(ReentrancyGuardError),
+    CallFailed(CallFailed),
+    InvalidToken,
+    InvalidInterval,
+    OrderNotFound,
+    CallerIsNotOwner,
+    OrderExpired,
+    InsufficientAmount,
+    InsufficientLiquidity,
+    Arithmetic(MathOp, TwammLocation),
+    InvalidSlippage,
+    PriceDeviation,
+    /// More than `MAX_INTERVALS_PER_EXECUTION` interval boundaries are pending,
+    /// `execute_virtual_orders_until` catches up in steps.
+    TooManyIntervals,
+}
+
+/// Step of the TWAMM contract in which an arithmetic error occurred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum TwammLocation {
+    Expiry,
+    /// Sell rate of an order pool, or ending at an interval boundary.
+    SellRate,
+    OrderId,
+    UnsoldAmount,
+    /// Amounts sold and matched over a segment of time.
+    Segment,
+    RewardFactor,
+    Proceeds,
+}
+
+macro_rules! impl_froms {
+    ( $( $error:ident ),* ) => {
+        $(
+            impl From<$error> for TwammError {
+                fn from(error: $error) -> Self {
+                    TwammError::$error(error)
+                }
+            }
+        )*
+    };
+}
+
+impl_froms!(
+    PSP22Error,
+    PairError,
+    HelperError,
+    ReentrancyGuardError,
+    CallFailed
+);
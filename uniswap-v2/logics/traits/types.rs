@@ -8,6 +8,7 @@ use ink_metadata::layout::{
     LayoutKey,
     LeafLayout,
 };
+use openbrush::traits::Timestamp;
 use primitive_types::U256;
 use scale::{
     Decode,
@@ -58,3 +59,63 @@ macro_rules! construct_from {
 }
 
 construct_from!(u8, u16, u32, u64, usize, i8, i16, i32, i64);
+
+/// Price of a token expressed as `FixedU128` inner value, `10^18` is a price of 1.
+pub type Price = u128;
+
+/// `Price` of 1.
+pub const PRICE_ONE: Price = 1_000_000_000_000_000_000;
+
+/// Unsigned fixed-point number with 128 integer and 128 fractional bits, as Uniswap's
+/// UQ112x112. Any ratio of two `u128` reserves is representable.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct UQ128x128(U256);
+
+impl UQ128x128 {
+    pub const FRACTIONAL_BITS: usize = 128;
+
+    /// Returns `numerator / denominator` rounded down, `None` if `denominator` is 0.
+    pub fn checked_from_rational(numerator: u128, denominator: u128) -> Option<Self> {
+        amm_math::fraction_x128(numerator, denominator).map(UQ128x128)
+    }
+
+    pub fn from_inner(inner: U256) -> Self {
+        UQ128x128(inner)
+    }
+
+    pub fn into_inner(self) -> U256 {
+        self.0
+    }
+
+    /// Returns the value as a `Price` rounded down, `None` if it exceeds `u128::MAX / 10^18`.
+    pub fn to_price(self) -> Option<Price> {
+        // Drops 64 fractional bits first so the product fits in 256 bits.
+        (((self.0 >> 64) * U256::from(PRICE_ONE)) >> 64)
+            .try_into()
+            .ok()
+    }
+}
+
+#[cfg(feature = "std")]
+impl StorageLayout for UQ128x128 {
+    fn layout(key: &Key) -> Layout {
+        Layout::Leaf(LeafLayout::from_key::<Self>(LayoutKey::from(key)))
+    }
+}
+
+/// Checkpoint of the price accumulators of a pair, as in Uniswap's `ExampleOracleSimple`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct Observation {
+    pub price_0_cumulative: WrappedU256,
+    pub price_1_cumulative: WrappedU256,
+    pub timestamp: Timestamp,
+    /// Average prices over the last completed period, only set after the second checkpoint.
+    pub price_0_average: Price,
+    pub price_1_average: Price,
+    pub has_average: bool,
+}
@@ -0,0 +1,64 @@
+use crate::{
+    AccountId,
+    Balance,
+    Timestamp,
+};
+use scale::{
+    Decode,
+    DecodeAll,
+    Encode,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+/// Event emitted by a pair, with the fields the simulation depends on.
+///
+/// In JSON an event is an object with the name of the variant as only key, as
+/// `{"Sync": {"reserve_0": 1, "reserve_1": 2}}`. Fields not listed here, as `sender`, are
+/// ignored.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub enum PairEvent {
+    Mint {
+        amount_0: Balance,
+        amount_1: Balance,
+    },
+    Burn {
+        amount_0: Balance,
+        amount_1: Balance,
+    },
+    Swap {
+        amount_0_in: Balance,
+        amount_1_in: Balance,
+        amount_0_out: Balance,
+        amount_1_out: Balance,
+    },
+    Sync {
+        reserve_0: Balance,
+        reserve_1: Balance,
+    },
+    /// Transfer of liquidity tokens, `None` on the side of a mint or a burn.
+    Transfer {
+        from: Option<AccountId>,
+        to: Option<AccountId>,
+        value: Balance,
+    },
+}
+
+/// Pair event with the timestamp of the block which emitted it.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct LoggedEvent {
+    pub timestamp: Timestamp,
+    pub event: PairEvent,
+}
+
+/// Parses a JSON array of logged events.
+pub fn events_from_json(json: &str) -> Result<Vec<LoggedEvent>, serde_json::Error> {
+    serde_json::from_str(json)
+}
+
+/// Decodes a SCALE-encoded `Vec<LoggedEvent>`.
+pub fn events_from_scale(mut bytes: &[u8]) -> Result<Vec<LoggedEvent>, scale::Error> {
+    Vec::<LoggedEvent>::decode_all(&mut bytes)
+}
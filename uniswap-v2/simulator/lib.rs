@@ -0,0 +1,38 @@
+//! Off-chain model of a `Pair` contract for backtesting.
+//!
+//! A [`pool::Pool`] holds the state of a pair in memory and updates it with the same
+//! `amm_math` functions as the contract. [`replay::Replay`] applies a log of pair events to
+//! it, either to reproduce the on-chain state or to replay the same trades at another fee.
+
+pub mod events;
+pub mod pool;
+pub mod replay;
+
+pub type Balance = u128;
+/// Block timestamp in milliseconds.
+pub type Timestamp = u64;
+pub type AccountId = [u8; 32];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimulatorError {
+    Math(amm_math::MathError),
+    InsufficientLiquidityMinted,
+    InsufficientLiquidityBurned,
+    InsufficientOutputAmount,
+    InsufficientInputAmount,
+    InsufficientLiquidity,
+    K,
+    /// The reserves logged by a `Sync` event differ from the simulated ones.
+    ReservesMismatch {
+        logged: (Balance, Balance),
+        simulated: (Balance, Balance),
+    },
+    /// A `Burn` event without liquidity burned by a `Transfer` event before it.
+    MissingBurnTransfer,
+}
+
+impl From<amm_math::MathError> for SimulatorError {
+    fn from(error: amm_math::MathError) -> Self {
+        SimulatorError::Math(error)
+    }
+}
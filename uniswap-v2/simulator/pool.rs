@@ -0,0 +1,322 @@
+use crate::{
+    Balance,
+    SimulatorError,
+    Timestamp,
+};
+use amm_math::{
+    Rounding,
+    MINIMUM_LIQUIDITY,
+    U256,
+};
+
+/// State of a pair, updated as by the `Pair` contract with the protocol fee minted as
+/// liquidity.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pool {
+    pub reserve_0: Balance,
+    pub reserve_1: Balance,
+    pub total_supply: Balance,
+    pub k_last: U256,
+    pub price_0_cumulative_last: U256,
+    pub price_1_cumulative_last: U256,
+    pub block_timestamp_last: Timestamp,
+    pub fee_bps: u16,
+    /// Whether the factory has a `fee_to`, which is minted the protocol fee.
+    pub fee_on: bool,
+    /// Liquidity minted to `fee_to`.
+    pub fee_to_liquidity: Balance,
+    /// Swap fees paid to liquidity providers in each token, rounded down.
+    pub fees_0: Balance,
+    pub fees_1: Balance,
+    /// Protocol fees set aside by logged swaps in `ProtocolFeeMode::Tokens`, out of the
+    /// reserves.
+    pub protocol_fees_0: Balance,
+    pub protocol_fees_1: Balance,
+}
+
+impl Pool {
+    pub fn new(fee_bps: u16, fee_on: bool) -> Self {
+        Pool {
+            fee_bps,
+            fee_on,
+            ..Default::default()
+        }
+    }
+
+    pub fn reserves(&self) -> (Balance, Balance) {
+        (self.reserve_0, self.reserve_1)
+    }
+
+    /// Deposits `amount_0` and `amount_1` and returns the liquidity minted, as `Pair::mint`.
+    pub fn mint(
+        &mut self,
+        amount_0: Balance,
+        amount_1: Balance,
+        timestamp: Timestamp,
+    ) -> Result<Balance, SimulatorError> {
+        let reserves = self.reserves();
+        let balance_0 = checked_add(reserves.0, amount_0)?;
+        let balance_1 = checked_add(reserves.1, amount_1)?;
+
+        let fee_on = self.mint_fee()?;
+        let liquidity = if self.total_supply == 0 {
+            let liquidity = amm_math::initial_liquidity(amount_0, amount_1)?;
+            self.total_supply = MINIMUM_LIQUIDITY;
+            liquidity
+        } else {
+            amm_math::liquidity_minted((amount_0, amount_1), reserves, self.total_supply)?
+        };
+        if liquidity == 0 {
+            return Err(SimulatorError::InsufficientLiquidityMinted)
+        }
+        self.total_supply = checked_add(self.total_supply, liquidity)?;
+
+        self.update(balance_0, balance_1, timestamp);
+        if fee_on {
+            // As the pair, from the reserves before the deposit.
+            self.k_last = amm_math::full_mul(reserves.0, reserves.1);
+        }
+        Ok(liquidity)
+    }
+
+    /// Burns `liquidity` and returns the amounts withdrawn, as `Pair::burn`.
+    pub fn burn(
+        &mut self,
+        liquidity: Balance,
+        timestamp: Timestamp,
+    ) -> Result<(Balance, Balance), SimulatorError> {
+        let reserves = self.reserves();
+
+        let fee_on = self.mint_fee()?;
+        let (amount_0, amount_1) = amm_math::burn_amounts(liquidity, reserves, self.total_supply)?;
+        if amount_0 == 0 || amount_1 == 0 {
+            return Err(SimulatorError::InsufficientLiquidityBurned)
+        }
+        self.total_supply = self
+            .total_supply
+            .checked_sub(liquidity)
+            .ok_or(amm_math::MathError::Underflow)?;
+
+        self.update(reserves.0 - amount_0, reserves.1 - amount_1, timestamp);
+        if fee_on {
+            // As the pair, from the reserves before the withdrawal.
+            self.k_last = amm_math::full_mul(reserves.0, reserves.1);
+        }
+        Ok((amount_0, amount_1))
+    }
+
+    /// Swaps with the given amounts, as `Pair::swap` after the input was transferred.
+    pub fn swap(
+        &mut self,
+        amount_0_in: Balance,
+        amount_1_in: Balance,
+        amount_0_out: Balance,
+        amount_1_out: Balance,
+        timestamp: Timestamp,
+    ) -> Result<(), SimulatorError> {
+        let (balance_0, balance_1) =
+            self.swap_balances(amount_0_in, amount_1_in, amount_0_out, amount_1_out)?;
+        if !k_preserved(
+            (balance_0, balance_1),
+            (amount_0_in, amount_1_in),
+            self.reserves(),
+            self.fee_bps,
+        )? {
+            return Err(SimulatorError::K)
+        }
+
+        let fee_0 = amm_math::fee_amount(amount_0_in, self.fee_bps, Rounding::Down)?;
+        let fee_1 = amm_math::fee_amount(amount_1_in, self.fee_bps, Rounding::Down)?;
+        self.fees_0 = self.fees_0.saturating_add(fee_0);
+        self.fees_1 = self.fees_1.saturating_add(fee_1);
+
+        self.update(balance_0, balance_1, timestamp);
+        Ok(())
+    }
+
+    /// Applies a swap logged by the pair and returns the fee derived from its amounts, the
+    /// highest fee in basis points the pair would have accepted them with. The fee of the pair
+    /// may differ from `fee_bps`, as with a dynamic fee or the discount of a router, so the
+    /// constant product is not checked.
+    ///
+    /// `reserves` are those of the `Sync` event of the swap. They are lower than the balances
+    /// by the protocol fees set aside in `ProtocolFeeMode::Tokens`, at most the fee.
+    pub fn swap_logged(
+        &mut self,
+        amount_0_in: Balance,
+        amount_1_in: Balance,
+        amount_0_out: Balance,
+        amount_1_out: Balance,
+        reserves: Option<(Balance, Balance)>,
+        timestamp: Timestamp,
+    ) -> Result<u16, SimulatorError> {
+        let balances = self.swap_balances(amount_0_in, amount_1_in, amount_0_out, amount_1_out)?;
+
+        // `k_preserved` only fails above the fee charged, find the highest fee it holds at.
+        let (mut low, mut high) = (0, amm_math::BPS);
+        while low < high {
+            let fee_bps = high - (high - low) / 2;
+            if k_preserved(
+                balances,
+                (amount_0_in, amount_1_in),
+                self.reserves(),
+                fee_bps,
+            )? {
+                low = fee_bps;
+            } else {
+                high = fee_bps - 1;
+            }
+        }
+
+        let fee_0 = amm_math::fee_amount(amount_0_in, low, Rounding::Down)?;
+        let fee_1 = amm_math::fee_amount(amount_1_in, low, Rounding::Down)?;
+
+        // The protocol fees are set aside from the swap fee.
+        let (reserve_0, reserve_1) = reserves.unwrap_or(balances);
+        let (protocol_fee_0, protocol_fee_1) = match (
+            balances.0.checked_sub(reserve_0),
+            balances.1.checked_sub(reserve_1),
+        ) {
+            (Some(protocol_fee_0), Some(protocol_fee_1))
+                if protocol_fee_0 <= fee_0 && protocol_fee_1 <= fee_1 =>
+            {
+                (protocol_fee_0, protocol_fee_1)
+            }
+            _ => {
+                return Err(SimulatorError::ReservesMismatch {
+                    logged: (reserve_0, reserve_1),
+                    simulated: balances,
+                })
+            }
+        };
+        self.protocol_fees_0 = checked_add(self.protocol_fees_0, protocol_fee_0)?;
+        self.protocol_fees_1 = checked_add(self.protocol_fees_1, protocol_fee_1)?;
+        self.fees_0 = self.fees_0.saturating_add(fee_0 - protocol_fee_0);
+        self.fees_1 = self.fees_1.saturating_add(fee_1 - protocol_fee_1);
+
+        self.update(reserve_0, reserve_1, timestamp);
+        Ok(low)
+    }
+
+    /// Swaps `amount_in` of token 0 if `zero_for_one`, of token 1 otherwise, for the output
+    /// quoted by the router, which is returned.
+    pub fn swap_exact_in(
+        &mut self,
+        amount_in: Balance,
+        zero_for_one: bool,
+        timestamp: Timestamp,
+    ) -> Result<Balance, SimulatorError> {
+        if zero_for_one {
+            let amount_out =
+                amm_math::amount_out(amount_in, self.reserve_0, self.reserve_1, self.fee_bps)?;
+            self.swap(amount_in, 0, 0, amount_out, timestamp)?;
+            Ok(amount_out)
+        } else {
+            let amount_out =
+                amm_math::amount_out(amount_in, self.reserve_1, self.reserve_0, self.fee_bps)?;
+            self.swap(0, amount_in, amount_out, 0, timestamp)?;
+            Ok(amount_out)
+        }
+    }
+
+    /// Sets the reserves to the balances of the pair, as `Pair::sync`.
+    pub fn sync(&mut self, balance_0: Balance, balance_1: Balance, timestamp: Timestamp) {
+        self.update(balance_0, balance_1, timestamp);
+    }
+
+    /// Returns the amounts `liquidity` is worth at the current reserves, without the protocol
+    /// fee the next mint or burn would take.
+    pub fn lp_value(&self, liquidity: Balance) -> Result<(Balance, Balance), SimulatorError> {
+        Ok(amm_math::burn_amounts(
+            liquidity,
+            self.reserves(),
+            self.total_supply,
+        )?)
+    }
+
+    /// Returns the balances of the pair after a swap with the given amounts, as `Pair::swap`
+    /// before the constant product check.
+    fn swap_balances(
+        &self,
+        amount_0_in: Balance,
+        amount_1_in: Balance,
+        amount_0_out: Balance,
+        amount_1_out: Balance,
+    ) -> Result<(Balance, Balance), SimulatorError> {
+        if amount_0_out == 0 && amount_1_out == 0 {
+            return Err(SimulatorError::InsufficientOutputAmount)
+        }
+        let reserves = self.reserves();
+        if amount_0_out >= reserves.0 || amount_1_out >= reserves.1 {
+            return Err(SimulatorError::InsufficientLiquidity)
+        }
+        if amount_0_in == 0 && amount_1_in == 0 {
+            return Err(SimulatorError::InsufficientInputAmount)
+        }
+        Ok((
+            checked_add(reserves.0 - amount_0_out, amount_0_in)?,
+            checked_add(reserves.1 - amount_1_out, amount_1_in)?,
+        ))
+    }
+
+    /// Mints the protocol fee accrued since the last mint or burn, as `Pair::_mint_fee`.
+    fn mint_fee(&mut self) -> Result<bool, SimulatorError> {
+        if !self.fee_on {
+            self.k_last = U256::zero();
+            return Ok(false)
+        }
+        if !self.k_last.is_zero() {
+            let root_k = amm_math::sqrt(
+                amm_math::full_mul(self.reserve_0, self.reserve_1),
+                Rounding::Down,
+            )
+            .low_u128();
+            let root_k_last = amm_math::sqrt(self.k_last, Rounding::Down).low_u128();
+            if root_k > root_k_last {
+                let liquidity =
+                    amm_math::protocol_fee_liquidity(self.total_supply, root_k, root_k_last)?;
+                self.total_supply = checked_add(self.total_supply, liquidity)?;
+                self.fee_to_liquidity = checked_add(self.fee_to_liquidity, liquidity)?;
+            }
+        }
+        Ok(true)
+    }
+
+    /// Accumulates the prices of the reserves until `timestamp` and sets them to the balances,
+    /// as `Pair::_update`.
+    fn update(&mut self, balance_0: Balance, balance_1: Balance, timestamp: Timestamp) {
+        if timestamp != self.block_timestamp_last {
+            (self.price_0_cumulative_last, self.price_1_cumulative_last) =
+                amm_math::update_cumulative(
+                    self.price_0_cumulative_last,
+                    self.price_1_cumulative_last,
+                    timestamp.saturating_sub(self.block_timestamp_last).into(),
+                    self.reserve_0,
+                    self.reserve_1,
+                );
+        }
+        self.reserve_0 = balance_0;
+        self.reserve_1 = balance_1;
+        self.block_timestamp_last = timestamp;
+    }
+}
+
+/// Returns whether `balances` after a swap of `amounts_in` with `fee_bps` charged keep the
+/// constant product of `reserves`.
+fn k_preserved(
+    balances: (Balance, Balance),
+    amounts_in: (Balance, Balance),
+    reserves: (Balance, Balance),
+    fee_bps: u16,
+) -> Result<bool, SimulatorError> {
+    let balance_0_adjusted = amm_math::adjusted_balance(balances.0, amounts_in.0, fee_bps)?;
+    let balance_1_adjusted = amm_math::adjusted_balance(balances.1, amounts_in.1, fee_bps)?;
+    Ok(amm_math::k_preserved(
+        (balance_0_adjusted, balance_1_adjusted),
+        reserves,
+    ))
+}
+
+fn checked_add(a: Balance, b: Balance) -> Result<Balance, SimulatorError> {
+    Ok(a.checked_add(b).ok_or(amm_math::MathError::Overflow)?)
+}
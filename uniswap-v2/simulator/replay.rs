@@ -0,0 +1,348 @@
+use crate::{
+    events::{
+        LoggedEvent,
+        PairEvent,
+    },
+    pool::Pool,
+    Balance,
+    SimulatorError,
+    Timestamp,
+};
+
+/// How the swaps of a log are replayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapMode {
+    /// With the logged amounts and the fee derived from them, each `Sync` event is checked
+    /// against the simulated reserves and the log is reproduced exactly.
+    Logged,
+    /// As exact input swaps quoted at the fee of the pool, to backtest another fee. Deposits
+    /// and withdrawals are replayed as logged and `sync` calls are skipped.
+    ExactIn,
+}
+
+/// Applies a log of pair events to a [`Pool`].
+///
+/// The pair emits `Sync` before the `Mint`, `Burn` or `Swap` event of the same call and burns
+/// the liquidity with a `Transfer` to `None` before them, both are held until that event.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub pool: Pool,
+    mode: SwapMode,
+    pending_sync: Option<(Balance, Balance, Timestamp)>,
+    pending_burn: Balance,
+}
+
+impl Replay {
+    pub fn new(pool: Pool, mode: SwapMode) -> Self {
+        Replay {
+            pool,
+            mode,
+            pending_sync: None,
+            pending_burn: 0,
+        }
+    }
+
+    pub fn apply(&mut self, logged: &LoggedEvent) -> Result<(), SimulatorError> {
+        let timestamp = logged.timestamp;
+        match logged.event {
+            PairEvent::Sync {
+                reserve_0,
+                reserve_1,
+            } => {
+                // A previous `Sync` without event of its own came from `Pair::sync`.
+                self.flush_sync();
+                self.pending_sync = Some((reserve_0, reserve_1, timestamp));
+                return Ok(())
+            }
+            PairEvent::Transfer {
+                to: None, value, ..
+            } => {
+                self.pending_burn = self.pending_burn.saturating_add(value);
+                return Ok(())
+            }
+            // Minted liquidity is computed by the pool.
+            PairEvent::Transfer { .. } => return Ok(()),
+            PairEvent::Mint { amount_0, amount_1 } => {
+                self.pool.mint(amount_0, amount_1, timestamp)?;
+            }
+            PairEvent::Burn { .. } => {
+                if self.pending_burn == 0 {
+                    return Err(SimulatorError::MissingBurnTransfer)
+                }
+                let liquidity = core::mem::take(&mut self.pending_burn);
+                self.pool.burn(liquidity, timestamp)?;
+            }
+            PairEvent::Swap {
+                amount_0_in,
+                amount_1_in,
+                amount_0_out,
+                amount_1_out,
+            } => {
+                match self.mode {
+                    SwapMode::Logged => {
+                        let reserves = self
+                            .pending_sync
+                            .map(|(reserve_0, reserve_1, _)| (reserve_0, reserve_1));
+                        self.pool.swap_logged(
+                            amount_0_in,
+                            amount_1_in,
+                            amount_0_out,
+                            amount_1_out,
+                            reserves,
+                            timestamp,
+                        )?;
+                    }
+                    SwapMode::ExactIn if amount_0_in >= amount_1_in => {
+                        self.pool.swap_exact_in(amount_0_in, true, timestamp)?;
+                    }
+                    SwapMode::ExactIn => {
+                        self.pool.swap_exact_in(amount_1_in, false, timestamp)?;
+                    }
+                }
+            }
+        }
+        self.check_sync()
+    }
+
+    /// Applies a trailing `Sync` and returns the pool.
+    pub fn finish(mut self) -> Pool {
+        self.flush_sync();
+        self.pool
+    }
+
+    /// Checks the reserves logged by the `Sync` of the call just applied.
+    fn check_sync(&mut self) -> Result<(), SimulatorError> {
+        match self.pending_sync.take() {
+            Some((reserve_0, reserve_1, _))
+                if self.mode == SwapMode::Logged
+                    && (reserve_0, reserve_1) != self.pool.reserves() =>
+            {
+                Err(SimulatorError::ReservesMismatch {
+                    logged: (reserve_0, reserve_1),
+                    simulated: self.pool.reserves(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn flush_sync(&mut self) {
+        if let Some((reserve_0, reserve_1, timestamp)) = self.pending_sync.take() {
+            if self.mode == SwapMode::Logged {
+                self.pool.sync(reserve_0, reserve_1, timestamp);
+            }
+        }
+    }
+}
+
+/// Replays `events` on `pool` and returns its final state.
+pub fn replay(pool: Pool, events: &[LoggedEvent], mode: SwapMode) -> Result<Pool, SimulatorError> {
+    let mut replay = Replay::new(pool, mode);
+    for event in events {
+        replay.apply(event)?;
+    }
+    Ok(replay.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{
+        events_from_json,
+        events_from_scale,
+    };
+    use scale::Encode;
+
+    const ONE: Balance = 1_000_000_000_000_000_000;
+    const USER: [u8; 32] = [1; 32];
+
+    /// Runs calls on a pool as the pair would and logs its events.
+    struct Chain {
+        pool: Pool,
+        events: Vec<LoggedEvent>,
+    }
+
+    impl Chain {
+        fn log(&mut self, timestamp: Timestamp, event: PairEvent) {
+            self.events.push(LoggedEvent { timestamp, event });
+        }
+
+        fn log_sync(&mut self, timestamp: Timestamp) {
+            let (reserve_0, reserve_1) = self.pool.reserves();
+            self.log(
+                timestamp,
+                PairEvent::Sync {
+                    reserve_0,
+                    reserve_1,
+                },
+            );
+        }
+
+        fn mint(&mut self, amount_0: Balance, amount_1: Balance, timestamp: Timestamp) {
+            let liquidity = self.pool.mint(amount_0, amount_1, timestamp).unwrap();
+            self.log(
+                timestamp,
+                PairEvent::Transfer {
+                    from: None,
+                    to: Some(USER),
+                    value: liquidity,
+                },
+            );
+            self.log_sync(timestamp);
+            self.log(timestamp, PairEvent::Mint { amount_0, amount_1 });
+        }
+
+        fn burn(&mut self, liquidity: Balance, timestamp: Timestamp) {
+            let (amount_0, amount_1) = self.pool.burn(liquidity, timestamp).unwrap();
+            self.log(
+                timestamp,
+                PairEvent::Transfer {
+                    from: Some(USER),
+                    to: None,
+                    value: liquidity,
+                },
+            );
+            self.log_sync(timestamp);
+            self.log(timestamp, PairEvent::Burn { amount_0, amount_1 });
+        }
+
+        fn swap(&mut self, amount_in: Balance, zero_for_one: bool, timestamp: Timestamp) {
+            let amount_out = self
+                .pool
+                .swap_exact_in(amount_in, zero_for_one, timestamp)
+                .unwrap();
+            self.log_sync(timestamp);
+            let (amount_0_in, amount_1_in, amount_0_out, amount_1_out) = if zero_for_one {
+                (amount_in, 0, 0, amount_out)
+            } else {
+                (0, amount_in, amount_out, 0)
+            };
+            self.log(
+                timestamp,
+                PairEvent::Swap {
+                    amount_0_in,
+                    amount_1_in,
+                    amount_0_out,
+                    amount_1_out,
+                },
+            );
+        }
+    }
+
+    fn chain() -> Chain {
+        let mut chain = Chain {
+            pool: Pool::new(30, true),
+            events: Vec::new(),
+        };
+        chain.mint(1_000 * ONE, 4_000 * ONE, 1_000);
+        chain.swap(10 * ONE, true, 13_000);
+        chain.swap(50 * ONE, false, 25_000);
+        chain.mint(100 * ONE, 400 * ONE, 37_000);
+        chain.swap(3 * ONE, true, 49_000);
+        chain.burn(500 * ONE, 61_000);
+        chain
+    }
+
+    #[test]
+    fn replay_reproduces_pool() {
+        let chain = chain();
+        let pool = replay(Pool::new(30, true), &chain.events, SwapMode::Logged).unwrap();
+        assert_eq!(pool, chain.pool);
+        assert!(pool.fee_to_liquidity > 0);
+        assert!(pool.fees_0 > 0 && pool.fees_1 > 0);
+    }
+
+    #[test]
+    fn replay_decodes_json_and_scale() {
+        let chain = chain();
+        let json = serde_json::to_string(&chain.events).unwrap();
+        assert_eq!(events_from_json(&json).unwrap(), chain.events);
+        assert_eq!(
+            events_from_scale(&chain.events.encode()).unwrap(),
+            chain.events
+        );
+
+        let json = r#"[
+            {"timestamp": 1000, "event": {"Sync": {"reserve_0": 2000, "reserve_1": 8000}}},
+            {"timestamp": 1000, "event": {"Mint": {"sender": "ignored", "amount_0": 2000, "amount_1": 8000}}},
+            {"timestamp": 2000, "event": {"Sync": {"reserve_0": 2100, "reserve_1": 7000}}}
+        ]"#;
+        let pool = replay(
+            Pool::new(30, false),
+            &events_from_json(json).unwrap(),
+            SwapMode::Logged,
+        )
+        .unwrap();
+        assert_eq!(pool.reserves(), (2_100, 7_000));
+        assert_eq!(pool.total_supply, 4_000);
+        assert_eq!(
+            pool.price_0_cumulative_last,
+            amm_math::U256::from(4_000u128) << 128
+        );
+    }
+
+    #[test]
+    fn replay_detects_mismatch() {
+        let mut events = chain().events;
+        events[3] = LoggedEvent {
+            timestamp: 13_000,
+            event: PairEvent::Sync {
+                reserve_0: 1,
+                reserve_1: 1,
+            },
+        };
+        assert!(matches!(
+            replay(Pool::new(30, true), &events, SwapMode::Logged),
+            Err(SimulatorError::ReservesMismatch { logged: (1, 1), .. })
+        ));
+    }
+
+    #[test]
+    fn replay_derives_fee_of_logged_swaps() {
+        let mut chain = chain();
+        // Swap at a fee discounted to 20 bps in `ProtocolFeeMode::Tokens`, where 1/6 of the fee
+        // is set aside out of the reserves.
+        let (reserve_0, reserve_1) = chain.pool.reserves();
+        let amount_in = 10 * ONE;
+        let amount_out = amm_math::amount_out(amount_in, reserve_0, reserve_1, 20).unwrap();
+        let protocol_fee = amount_in * 20 / (10_000 * 6);
+        let reserves = (reserve_0 + amount_in - protocol_fee, reserve_1 - amount_out);
+        chain.log(
+            73_000,
+            PairEvent::Sync {
+                reserve_0: reserves.0,
+                reserve_1: reserves.1,
+            },
+        );
+        chain.log(
+            73_000,
+            PairEvent::Swap {
+                amount_0_in: amount_in,
+                amount_1_in: 0,
+                amount_0_out: 0,
+                amount_1_out: amount_out,
+            },
+        );
+
+        let pool = replay(Pool::new(30, true), &chain.events, SwapMode::Logged).unwrap();
+        assert_eq!(pool.reserves(), reserves);
+        assert_eq!(pool.protocol_fees_0, protocol_fee);
+        assert_eq!(
+            pool.fees_0,
+            chain.pool.fees_0 + amount_in * 20 / 10_000 - protocol_fee
+        );
+        assert_eq!(pool.fees_1, chain.pool.fees_1);
+    }
+
+    #[test]
+    fn higher_fee_backtest_earns_more_fees() {
+        let chain = chain();
+        let pool = replay(Pool::new(100, true), &chain.events, SwapMode::ExactIn).unwrap();
+        assert!(pool.fees_0 > chain.pool.fees_0);
+        assert!(pool.fees_1 > chain.pool.fees_1);
+        // Liquidity is worth more in both tokens.
+        let value = pool.lp_value(ONE).unwrap();
+        let chain_value = chain.pool.lp_value(ONE).unwrap();
+        assert!(value.0 > chain_value.0 || value.1 > chain_value.1);
+    }
+}